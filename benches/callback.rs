@@ -0,0 +1,64 @@
+//! Cost of handing one input buffer from the audio callback to the reader,
+//! with the reader polling from another thread: the old `Mutex<VecDeque>`
+//! against the `ringbuf` SPSC ring `LiveRecording` uses.
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ringbuf::traits::{Consumer, Observer, Producer, Split};
+use ringbuf::HeapRb;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+const CAPACITY: usize = 88200;
+const BUFFER: [f32; 512] = [0.25; 512];
+
+fn bench_callback(c: &mut Criterion) {
+    let stop = Arc::new(AtomicBool::new(false));
+
+    let ring = Arc::new((
+        Mutex::new(VecDeque::<f32>::with_capacity(CAPACITY)),
+        Condvar::new(),
+    ));
+    let reader = {
+        let (ring, stop) = (ring.clone(), stop.clone());
+        thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                let mut samples = ring.0.lock().unwrap();
+                let available = samples.len();
+                samples.drain(..available);
+            }
+        })
+    };
+    c.bench_function("callback_mutex_vecdeque", |b| {
+        b.iter(|| {
+            let mut samples = ring.0.lock().unwrap();
+            let overflow = (samples.len() + BUFFER.len()).saturating_sub(CAPACITY);
+            samples.drain(..overflow);
+            samples.extend(black_box(&BUFFER));
+            ring.1.notify_all();
+        })
+    });
+    stop.store(true, Ordering::Relaxed);
+    reader.join().unwrap();
+
+    stop.store(false, Ordering::Relaxed);
+    let (mut producer, mut consumer) = HeapRb::<f32>::new(CAPACITY).split();
+    let reader = {
+        let stop = stop.clone();
+        thread::spawn(move || {
+            let mut chunk = vec![0.0; CAPACITY];
+            while !stop.load(Ordering::Relaxed) {
+                let available = consumer.occupied_len();
+                consumer.pop_slice(&mut chunk[..available]);
+            }
+        })
+    };
+    c.bench_function("callback_ringbuf", |b| {
+        b.iter(|| producer.push_slice(black_box(&BUFFER)))
+    });
+    stop.store(true, Ordering::Relaxed);
+    reader.join().unwrap();
+}
+
+criterion_group!(benches, bench_callback);
+criterion_main!(benches);
@@ -0,0 +1,75 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand_chacha::rand_core::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use randomize7::{
+    center_and_normalize, extract_from_wav, extract_random_data, normalize_audio, remove_dc_offset,
+    ExtractStrategy,
+};
+use std::path::Path;
+
+const BUFFER_BYTES: usize = 1 << 20;
+
+fn pseudo_random_samples() -> Vec<f32> {
+    let mut rng = ChaCha20Rng::seed_from_u64(0);
+    (0..BUFFER_BYTES / 4)
+        .map(|_| rng.next_u32() as f32 / u32::MAX as f32 * 2.0 - 1.0)
+        .collect()
+}
+
+fn bench_processing(c: &mut Criterion) {
+    let samples = pseudo_random_samples();
+
+    c.bench_function("remove_dc_offset", |b| {
+        b.iter_batched_ref(
+            || samples.clone(),
+            |samples| remove_dc_offset(samples),
+            criterion::BatchSize::LargeInput,
+        )
+    });
+    c.bench_function("normalize_audio", |b| {
+        b.iter_batched_ref(
+            || samples.clone(),
+            |samples| normalize_audio(samples, 1.0),
+            criterion::BatchSize::LargeInput,
+        )
+    });
+    c.bench_function("remove_dc_offset + normalize_audio", |b| {
+        b.iter_batched_ref(
+            || samples.clone(),
+            |samples| {
+                remove_dc_offset(samples);
+                normalize_audio(samples, 1.0);
+            },
+            criterion::BatchSize::LargeInput,
+        )
+    });
+    c.bench_function("center_and_normalize", |b| {
+        b.iter_batched_ref(
+            || samples.clone(),
+            |samples| center_and_normalize(samples, 1.0),
+            criterion::BatchSize::LargeInput,
+        )
+    });
+    c.bench_function("extract_random_data", |b| {
+        b.iter(|| {
+            extract_random_data(
+                black_box(&samples),
+                8,
+                32_768,
+                ExtractStrategy::default(),
+                false,
+                false,
+            )
+        })
+    });
+}
+
+fn bench_wav_pipeline(c: &mut Criterion) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/noise.wav");
+    c.bench_function("extract_from_wav", |b| {
+        b.iter(|| extract_from_wav(black_box(&path), 8, 32).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_processing, bench_wav_pipeline);
+criterion_main!(benches);
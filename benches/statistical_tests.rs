@@ -0,0 +1,47 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand_chacha::rand_core::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use randomize7::{
+    approximate_entropy_test, autocorrelation_test, block_frequency_test, chi_square_byte_test,
+    cumulative_sums_test, longest_run_test, matrix_rank_test, maurer_universal_test,
+    monobit_result, run_all_tests, runs_result, serial_test, spectral_test,
+    DEFAULT_APPROXIMATE_ENTROPY_BLOCK_LENGTH,
+};
+
+const BUFFER_BYTES: usize = 1 << 20;
+
+fn bench_tests(c: &mut Criterion) {
+    let mut data = vec![0u8; BUFFER_BYTES];
+    ChaCha20Rng::seed_from_u64(0).fill_bytes(&mut data);
+    let data = black_box(data.as_slice());
+
+    // A single test over 8 Mbit takes long enough that fewer samples suffice.
+    let mut group = c.benchmark_group("statistical_tests");
+    group.sample_size(10);
+
+    group.bench_function("monobit", |b| b.iter(|| monobit_result(data)));
+    group.bench_function("runs", |b| b.iter(|| runs_result(data)));
+    group.bench_function("block_frequency", |b| {
+        b.iter(|| block_frequency_test(data, 128))
+    });
+    group.bench_function("longest_run", |b| b.iter(|| longest_run_test(data)));
+    group.bench_function("spectral", |b| b.iter(|| spectral_test(data)));
+    group.bench_function("serial", |b| b.iter(|| serial_test(data, 16)));
+    group.bench_function("cumulative_sums", |b| b.iter(|| cumulative_sums_test(data)));
+    group.bench_function("approximate_entropy", |b| {
+        b.iter(|| approximate_entropy_test(data, DEFAULT_APPROXIMATE_ENTROPY_BLOCK_LENGTH))
+    });
+    group.bench_function("autocorrelation", |b| {
+        b.iter(|| autocorrelation_test(data, 1))
+    });
+    group.bench_function("chi_square_byte", |b| b.iter(|| chi_square_byte_test(data)));
+    group.bench_function("matrix_rank", |b| b.iter(|| matrix_rank_test(data)));
+    group.bench_function("maurer_universal", |b| {
+        b.iter(|| maurer_universal_test(data))
+    });
+    group.bench_function("run_all_tests", |b| b.iter(|| run_all_tests(data)));
+    group.finish();
+}
+
+criterion_group!(benches, bench_tests);
+criterion_main!(benches);
@@ -0,0 +1,30 @@
+use rand_chacha::rand_core::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use randomize7::run_all_tests;
+use std::time::Instant;
+
+// Compare `cargo run --release --example battery_bench` against the same
+// command with `--features parallel`.
+fn main() {
+    let mut data = vec![0u8; 125_000];
+    ChaCha20Rng::seed_from_u64(0).fill_bytes(&mut data);
+
+    const ROUNDS: u32 = 10;
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        run_all_tests(&data);
+    }
+    let elapsed = start.elapsed() / ROUNDS;
+
+    let mode = if cfg!(feature = "parallel") {
+        "parallel"
+    } else {
+        "sequential"
+    };
+    println!(
+        "{} battery over {} bits: {:?} per run",
+        mode,
+        data.len() * 8,
+        elapsed
+    );
+}
@@ -0,0 +1,15 @@
+use rand_core::RngCore;
+use randomize7::seed_chacha20;
+use std::time::Instant;
+
+fn main() -> Result<(), randomize7::RandomizeError> {
+    // One recording seeds the generator; everything after is pure ChaCha20.
+    let mut rng = seed_chacha20(256)?;
+
+    let start = Instant::now();
+    let mut output = vec![0u8; 1_000_000];
+    rng.fill_bytes(&mut output);
+    println!("Generated {} bytes in {:?}", output.len(), start.elapsed());
+
+    Ok(())
+}
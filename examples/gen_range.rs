@@ -0,0 +1,9 @@
+use rand::Rng;
+use randomize7::AudioRng;
+
+fn main() {
+    let mut rng = AudioRng::new();
+
+    let rolls: Vec<u32> = (0..10).map(|_| rng.gen_range(1..=6)).collect();
+    println!("Dice rolls: {:?}", rolls);
+}
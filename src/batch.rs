@@ -0,0 +1,99 @@
+use std::fmt;
+use std::ops::Deref;
+
+use crate::conditioning::whiten_sha256;
+use crate::error::RandomizeError;
+use crate::extraction::{process_samples, ExtractParams, ExtractStrategy};
+use crate::health::HealthPolicy;
+
+// Differences `EntropyBatch::try_from` XOR-folds into each unit; the
+// low bits of single differences are too biased to pass the policy.
+const BATCH_XOR_WINDOW: usize = 4;
+
+/// Bytes extracted from samples captured elsewhere that passed a
+/// [`HealthPolicy`].
+///
+/// ```
+/// use randomize7::{EntropyBatch, RandomizeError};
+///
+/// let samples = vec![0.25; 1000];
+/// assert!(matches!(
+///     EntropyBatch::try_from(&samples),
+///     Err(RandomizeError::HealthCheckFailed { .. })
+/// ));
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct EntropyBatch {
+    bytes: Vec<u8>,
+}
+
+impl EntropyBatch {
+    /// Rejects `samples` if they clip, then removes their DC offset,
+    /// normalizes them and extracts like [`process_samples`]. The extracted
+    /// bytes must pass [`HealthPolicy::check`] before any whitening, which
+    /// would hide their flaws from it.
+    pub fn from_samples(
+        samples: &[f32],
+        params: ExtractParams,
+        policy: &HealthPolicy,
+    ) -> Result<Self, RandomizeError> {
+        policy.check_clipping(samples)?;
+        let bytes = process_samples(
+            samples,
+            ExtractParams {
+                whiten: false,
+                ..params
+            },
+        )?;
+        policy.check(&bytes)?;
+        let bytes = if params.whiten {
+            whiten_sha256(&bytes, params.output_length)
+        } else {
+            bytes
+        };
+        Ok(EntropyBatch { bytes })
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// [`EntropyBatch::from_samples`] of every byte the samples support, one
+/// per [`XorDifferences`](ExtractStrategy::XorDifferences) unit of four
+/// differences, unwhitened, under the default policy.
+impl TryFrom<&[f32]> for EntropyBatch {
+    type Error = RandomizeError;
+
+    fn try_from(samples: &[f32]) -> Result<Self, RandomizeError> {
+        let params = ExtractParams {
+            strategy: ExtractStrategy::XorDifferences {
+                window: BATCH_XOR_WINDOW,
+            },
+            ..ExtractParams::new(8, samples.len().saturating_sub(1) / BATCH_XOR_WINDOW)
+        };
+        EntropyBatch::from_samples(samples, params, &HealthPolicy::default())
+    }
+}
+
+impl TryFrom<&Vec<f32>> for EntropyBatch {
+    type Error = RandomizeError;
+
+    fn try_from(samples: &Vec<f32>) -> Result<Self, RandomizeError> {
+        EntropyBatch::try_from(samples.as_slice())
+    }
+}
+
+impl Deref for EntropyBatch {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl fmt::Debug for EntropyBatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EntropyBatch({} bytes)", self.bytes.len())
+    }
+}
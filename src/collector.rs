@@ -0,0 +1,1028 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::conditioning::{Conditioner, Sha256Conditioner};
+use crate::entropy::{bias_report, entropy_capped_length, min_entropy_mcv, BiasReport};
+use crate::error::RandomizeError;
+use crate::extraction::{
+    estimate_duration_with, extract_random_data_with, Debiaser, ExtractParams,
+};
+use crate::health::{
+    check_autocorrelation, longest_repeated_buffer_run, HealthPolicy, DEFAULT_MAX_CLIPPING_RATIO,
+    DEFAULT_MAX_KURTOSIS_DEVIATION, DEFAULT_MAX_REPEATED_BUFFERS,
+};
+use crate::live::LiveRecording;
+use crate::processing::{
+    center_and_normalize, channel_cross_correlation, channel_difference, clipping_ratio,
+    decorrelate, deinterleave, detect_agc_drift, high_pass_filter, noise_shape_metrics,
+    normalize_audio_to, remove_dc_offset, sliding_normalize, spectral_whiten, AgcDrift,
+    NormalizeMode, DEFAULT_AGC_WINDOW, MAX_CHANNEL_CORRELATION,
+};
+use crate::recording::{DEFAULT_WARMUP, FILL_GRACE, RECORD_DURATION_SECS, SAMPLE_RATE};
+use crate::secure::Sensitive;
+use crate::source::{samples_in, Audio, CaptureConfig, Jitter, Microphone, SampleSource};
+
+type AdditionalSource = Mutex<Box<dyn FnMut(usize) -> Vec<u8> + Send>>;
+
+/// Builder for a configurable audio recording.
+///
+/// Records from a [`Microphone`] unless [`source`](Self::source) supplies
+/// another [`SampleSource`].
+///
+/// ```no_run
+/// use randomize7::EntropyCollector;
+/// use std::time::Duration;
+///
+/// let samples = EntropyCollector::new()
+///     .duration(Duration::from_millis(500))
+///     .sample_rate(48000)
+///     .collect()?;
+/// # Ok::<(), randomize7::RandomizeError>(())
+/// ```
+pub struct EntropyCollector {
+    duration: Duration,
+    duration_for_output: bool,
+    warmup: Duration,
+    microphone: Microphone,
+    source: Option<Box<dyn SampleSource>>,
+    high_pass_cutoff_hz: Option<f32>,
+    spectral_whiten_window: Option<usize>,
+    agc_window: Option<usize>,
+    decorrelate: bool,
+    health_policy: Option<HealthPolicy>,
+    significance_level: Option<f64>,
+    max_autocorrelation: Option<f64>,
+    channel_pair: Option<(usize, usize)>,
+    debiaser: Debiaser,
+    lsb_planes: bool,
+    conditioner: Option<Box<dyn Conditioner>>,
+    additional_source: Option<AdditionalSource>,
+    callback_timing: bool,
+    context: Vec<u8>,
+    full_rate: bool,
+    remove_dc: bool,
+    normalize: bool,
+    normalize_mode: NormalizeMode,
+    normalize_target: f32,
+    max_samples: Option<usize>,
+    jitter_fallback: bool,
+}
+
+impl EntropyCollector {
+    pub fn new() -> Self {
+        EntropyCollector {
+            duration: Duration::from_secs(RECORD_DURATION_SECS),
+            duration_for_output: false,
+            warmup: DEFAULT_WARMUP,
+            microphone: Microphone::new(),
+            source: None,
+            high_pass_cutoff_hz: None,
+            spectral_whiten_window: None,
+            agc_window: None,
+            decorrelate: false,
+            health_policy: None,
+            significance_level: None,
+            max_autocorrelation: None,
+            channel_pair: None,
+            debiaser: Debiaser::None,
+            lsb_planes: false,
+            conditioner: None,
+            additional_source: None,
+            callback_timing: false,
+            context: Vec::new(),
+            full_rate: false,
+            remove_dc: true,
+            normalize: true,
+            normalize_mode: NormalizeMode::Peak,
+            normalize_target: 1.0,
+            max_samples: None,
+            jitter_fallback: false,
+        }
+    }
+
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Records only as long as the requested bytes need, see
+    /// [`estimate_duration_for_bytes`](crate::estimate_duration_for_bytes), instead of for `duration` when
+    /// extracting. The estimate uses the requested sample rate, 44.1 kHz
+    /// unless set, and one channel; [`fill_bytes`](Self::fill_bytes) records
+    /// again if debiasing comes up short.
+    pub fn duration_for_output(mut self, duration_for_output: bool) -> Self {
+        self.duration_for_output = duration_for_output;
+        self
+    }
+
+    /// Stops recording once `max_samples` interleaved samples, after the
+    /// warm-up, have arrived, even if the duration hasn't elapsed. Whichever
+    /// of the two limits is reached first ends the recording.
+    pub fn max_samples(mut self, max_samples: usize) -> Self {
+        self.max_samples = Some(max_samples);
+        self
+    }
+
+    /// Drops the first `warmup` of each recording, where device start-up
+    /// transients live; [`DEFAULT_WARMUP`] unless set. The recording is
+    /// lengthened to match, so `duration` of usable audio remains.
+    pub fn warmup(mut self, warmup: Duration) -> Self {
+        self.warmup = warmup;
+        self
+    }
+
+    /// Requests a sample rate. If the device doesn't support it, its default
+    /// config is used instead and a warning is logged.
+    pub fn sample_rate(mut self, sample_rate: u32) -> Self {
+        self.microphone = self.microphone.sample_rate(sample_rate);
+        self
+    }
+
+    /// Records with exactly `config`, e.g. one picked from
+    /// [`list_supported_configs`](crate::list_supported_configs), instead of
+    /// the device's default.
+    pub fn stream_config(mut self, config: cpal::SupportedStreamConfig) -> Self {
+        self.microphone = self.microphone.stream_config(config);
+        self
+    }
+
+    /// Requests callback buffers of `frames` frames, see
+    /// [`Microphone::buffer_size`].
+    pub fn buffer_size(mut self, frames: u32) -> Self {
+        self.microphone = self.microphone.buffer_size(frames);
+        self
+    }
+
+    /// Records from the first input device whose name contains `name`
+    /// (case-insensitive) instead of the default one.
+    pub fn device(mut self, name: &str) -> Self {
+        self.microphone = self.microphone.device(name);
+        self
+    }
+
+    /// Records from the input device at `index` in [`list_input_devices`](crate::list_input_devices) order.
+    pub fn device_index(mut self, index: usize) -> Self {
+        self.microphone = self.microphone.device_index(index);
+        self
+    }
+
+    /// Records what the system plays, see [`Microphone::loopback`].
+    pub fn loopback(mut self) -> Self {
+        self.microphone = self.microphone.loopback();
+        self
+    }
+
+    /// Records from [`Jitter`] instead of failing when there is no input
+    /// device, or only a default device that isn't available, with a
+    /// warning on every recording. Off unless set: the fallback is weak and
+    /// not cryptographic, meant to keep tests and headless machines running.
+    /// [`live`](Self::live) never falls back.
+    pub fn jitter_fallback(mut self, jitter_fallback: bool) -> Self {
+        self.jitter_fallback = jitter_fallback;
+        self
+    }
+
+    /// Records from `source` instead of the microphone. The device and
+    /// sample rate settings are then ignored.
+    pub fn source(mut self, source: impl SampleSource + 'static) -> Self {
+        self.source = Some(Box::new(source));
+        self
+    }
+
+    /// High-pass filters the recording before it is returned, see
+    /// [`high_pass_filter`]. [`DEFAULT_HIGH_PASS_CUTOFF_HZ`](crate::DEFAULT_HIGH_PASS_CUTOFF_HZ)
+    /// is a reasonable starting point.
+    pub fn high_pass(mut self, cutoff_hz: f32) -> Self {
+        self.high_pass_cutoff_hz = Some(cutoff_hz);
+        self
+    }
+
+    /// Flattens the recording's spectrum after filtering, see
+    /// [`spectral_whiten`]. [`DEFAULT_SPECTRAL_WHITEN_WINDOW`](crate::DEFAULT_SPECTRAL_WHITEN_WINDOW)
+    /// is a reasonable window size.
+    pub fn spectral_whiten(mut self, window_size: usize) -> Self {
+        self.spectral_whiten_window = Some(window_size);
+        self
+    }
+
+    /// Compensates automatic gain control after high-pass filtering by
+    /// normalizing in windows of `window_size` frames instead of globally,
+    /// see [`sliding_normalize`]. [`DEFAULT_AGC_WINDOW`] is a reasonable
+    /// size; the same window is used to detect drift.
+    pub fn agc_compensation(mut self, window_size: usize) -> Self {
+        self.agc_window = Some(window_size);
+        self
+    }
+
+    /// Removes the correlation between neighbouring samples after
+    /// filtering, see [`decorrelate`].
+    pub fn decorrelate(mut self, decorrelate: bool) -> Self {
+        self.decorrelate = decorrelate;
+        self
+    }
+
+    /// Rejects clipped recordings and batches from
+    /// [`collect_random_data`](Self::collect_random_data) that fail `policy`.
+    /// Without a policy, clipping only triggers a warning.
+    pub fn health_policy(mut self, policy: HealthPolicy) -> Self {
+        self.health_policy = Some(policy);
+        self
+    }
+
+    /// Judges the health policy's statistical tests at `significance_level`
+    /// instead of the policy's own, whichever order the two are set in.
+    pub fn significance_level(mut self, significance_level: f64) -> Self {
+        self.significance_level = Some(significance_level);
+        self
+    }
+
+    /// Rejects batches whose extracted bits have an absolute lag-1
+    /// autocorrelation above `max_autocorrelation`, with or without a health
+    /// policy, see [`HealthPolicy::max_autocorrelation`]. Overrides the
+    /// policy's limit.
+    pub fn max_autocorrelation(mut self, max_autocorrelation: f64) -> Self {
+        self.max_autocorrelation = Some(max_autocorrelation);
+        self
+    }
+
+    /// Returns the per-frame difference between channels `first` and `second`
+    /// instead of the interleaved stream. Independent ADCs make the difference
+    /// a good extra entropy source. Mono devices fall back to their single
+    /// channel with a warning. Channels whose cross-correlation exceeds
+    /// [`MAX_CHANNEL_CORRELATION`] in either direction, as when one ADC feeds
+    /// both or one of them inverted, fail the recording with
+    /// [`RandomizeError::HealthCheckFailed`].
+    pub fn channel_difference(mut self, first: usize, second: usize) -> Self {
+        self.channel_pair = Some((first, second));
+        self
+    }
+
+    /// Runs the extracted bits through
+    /// [`von_neumann_debias`](crate::von_neumann_debias), which consumes
+    /// about four times as many samples per output byte.
+    pub fn debias(mut self, debias: bool) -> Self {
+        self.debiaser = if debias {
+            Debiaser::VonNeumann
+        } else {
+            Debiaser::None
+        };
+        self
+    }
+
+    /// Debiases the extracted bits with `debiaser`; [`Debiaser::None`]
+    /// unless set. Overrides [`debias`](Self::debias) and vice versa.
+    pub fn debiaser(mut self, debiaser: Debiaser) -> Self {
+        self.debiaser = debiaser;
+        self
+    }
+
+    /// Takes the extracted bits plane by plane rather than sample by sample,
+    /// see [`extract_lsb_planes`](crate::extract_lsb_planes). Off by default.
+    pub fn lsb_planes(mut self, lsb_planes: bool) -> Self {
+        self.lsb_planes = lsb_planes;
+        self
+    }
+
+    /// Normalizes the recording so its level, measured by `mode`, is
+    /// `target` before extraction; a peak of 1 unless set.
+    pub fn normalization(mut self, mode: NormalizeMode, target: f32) -> Self {
+        self.normalize_mode = mode;
+        self.normalize_target = target;
+        self
+    }
+
+    /// Whether the recording is scaled before extraction; on unless set.
+    ///
+    /// Scaling rounds every sample again, which reshuffles the mantissa's
+    /// low bits deterministically: it adds no entropy and can lose some.
+    /// Extractors that read those bits, [`LsbDifference`](ExtractStrategy::LsbDifference),
+    /// [`LsbRaw`](ExtractStrategy::LsbRaw), [`WindowParity`](ExtractStrategy::WindowParity)
+    /// and [`XorDifferences`](ExtractStrategy::XorDifferences), then see the
+    /// converter's own bits. [`SecondDifferenceSign`](ExtractStrategy::SecondDifferenceSign)
+    /// only reads signs and is unaffected.
+    pub fn normalize(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    /// Whether the DC offset is removed before extraction; on unless set.
+    /// Subtracting the mean rounds the samples like
+    /// [`normalize`](Self::normalize) does, and the same extractors benefit
+    /// from skipping it.
+    pub fn remove_dc(mut self, remove_dc: bool) -> Self {
+        self.remove_dc = remove_dc;
+        self
+    }
+
+    /// Conditions each extracted batch with [`Sha256Conditioner`], or
+    /// with the [`conditioner`](Self::conditioner) if one is set.
+    pub fn whiten(mut self, whiten: bool) -> Self {
+        self.conditioner = whiten.then(|| {
+            self.conditioner
+                .take()
+                .unwrap_or_else(|| Box::new(Sha256Conditioner))
+        });
+        self
+    }
+
+    /// Conditions each extracted batch with `conditioner`. The health
+    /// policy still judges the bytes before conditioning, where a failing
+    /// source is visible.
+    pub fn conditioner(mut self, conditioner: impl Conditioner + 'static) -> Self {
+        self.conditioner = Some(Box::new(conditioner));
+        self
+    }
+
+    /// Mixes `source(n)` into the conditioner input after every `n`
+    /// extracted bytes, e.g. system entropy from `getrandom`, conditioning
+    /// with [`Sha256Conditioner`] if no other conditioner is set.
+    ///
+    /// As long as the two are independent, the output is at least as
+    /// unpredictable as the stronger of the audio and `source`, so a weak
+    /// microphone can't compromise it on its own. The output cap adds the
+    /// min-entropy of both, see [`full_rate`](Self::full_rate). The health
+    /// policy still only judges the audio.
+    pub fn additional_source(
+        mut self,
+        source: impl FnMut(usize) -> Vec<u8> + Send + 'static,
+    ) -> Self {
+        self.additional_source = Some(Mutex::new(Box::new(source)));
+        self
+    }
+
+    /// Also mixes the timing of the input callbacks into the conditioner
+    /// input: the low byte of the nanoseconds between each two, which
+    /// scheduling and USB or driver latency make jitter independently of
+    /// the samples. Conditions with [`Sha256Conditioner`] if no other
+    /// conditioner is set.
+    ///
+    /// This is supplementary, not a replacement for the audio. There are
+    /// only a hundred or so callbacks a second, a coarse clock leaves the
+    /// low bits constant, and the output cap credits them only with their
+    /// measured min-entropy. The health policy doesn't judge them. Only the
+    /// microphone measures callback timing; other sources contribute
+    /// nothing.
+    pub fn callback_timing(mut self, callback_timing: bool) -> Self {
+        self.callback_timing = callback_timing;
+        self
+    }
+
+    /// Mixes `context` into the conditioning, see
+    /// [`Conditioner::condition_with_context`]. Has no effect without a
+    /// conditioner.
+    pub fn context(mut self, context: &[u8]) -> Self {
+        self.context = context.to_vec();
+        self
+    }
+
+    /// Conditions every extracted byte. By default the conditioned output
+    /// is capped at what the min-entropy of the extracted bytes justifies,
+    /// see [`entropy_capped_length`], with a warning when that is fewer
+    /// bytes than extracted.
+    pub fn full_rate(mut self, full_rate: bool) -> Self {
+        self.full_rate = full_rate;
+        self
+    }
+
+    pub fn collect(&self) -> Result<Vec<f32>, RandomizeError> {
+        self.collect_for(self.duration)
+    }
+
+    fn collect_for(&self, duration: Duration) -> Result<Vec<f32>, RandomizeError> {
+        let (audio, _, _) = self.record(duration)?;
+        self.filter(audio).map(|(samples, _)| samples)
+    }
+
+    /// Like [`collect_random_data`](Self::collect_random_data), but keeps
+    /// every intermediate signal for inspection.
+    pub fn collect_stages(
+        &self,
+        num_lsb: u32,
+        output_length: usize,
+    ) -> Result<CollectionStages, RandomizeError> {
+        let start = Instant::now();
+        let (audio, capture, stream_config) =
+            self.record(self.extraction_duration(num_lsb, output_length))?;
+        let recording_time = start.elapsed();
+        let timing = self.callback_timing_bytes(&audio);
+
+        let start = Instant::now();
+        let sample_rate = audio.sample_rate;
+        let raw = audio.samples.clone();
+        let agc_drift = self.agc_drift(&audio);
+        let (filtered, channels) = self.filter(audio)?;
+        let mut dc_removed = filtered.clone();
+        if self.remove_dc {
+            remove_dc_offset(&mut dc_removed);
+        }
+        let mut normalized = dc_removed.clone();
+        if self.normalize {
+            normalize_audio_to(&mut normalized, self.normalize_target, self.normalize_mode);
+        }
+        let extracted = self.extract_raw(&normalized, num_lsb, output_length)?;
+        let random_data = self.condition(&extracted, &timing);
+        let processing_time = start.elapsed();
+
+        Ok(CollectionStages {
+            sample_rate,
+            channels,
+            raw,
+            filtered,
+            dc_removed,
+            normalized,
+            extracted,
+            random_data,
+            agc_drift,
+            capture,
+            stream_config,
+            recording_time,
+            processing_time,
+        })
+    }
+
+    fn agc_drift(&self, audio: &Audio) -> AgcDrift {
+        let window = self.agc_window.unwrap_or(DEFAULT_AGC_WINDOW);
+        detect_agc_drift(&audio.samples, window * audio.channels.max(1) as usize)
+    }
+
+    // How long to record for `output_length` extracted bytes.
+    fn extraction_duration(&self, num_lsb: u32, output_length: usize) -> Duration {
+        if !self.duration_for_output {
+            return self.duration;
+        }
+        let sample_rate = self
+            .microphone
+            .requested_sample_rate()
+            .unwrap_or(SAMPLE_RATE);
+        estimate_duration_with(output_length, num_lsb, sample_rate, self.debiaser)
+    }
+
+    fn record(
+        &self,
+        duration: Duration,
+    ) -> Result<(Audio, CaptureStats, Option<CaptureConfig>), RandomizeError> {
+        let (mut audio, stream_config) = match &self.source {
+            Some(source) => (source.record(duration + self.warmup)?, None),
+            None => match self.microphone.record_at_most(
+                duration + self.warmup,
+                self.warmup,
+                self.max_samples,
+            ) {
+                // ALSA without a card still lists a default device, which
+                // then can't report a config.
+                Err(
+                    RandomizeError::NoInputDevice
+                    | RandomizeError::DefaultConfig(
+                        cpal::DefaultStreamConfigError::DeviceNotAvailable,
+                    ),
+                ) if self.jitter_fallback => {
+                    log::warn!(
+                        "no input device; falling back to CPU timing jitter, \
+                         which is weak and NOT suitable for cryptographic use"
+                    );
+                    (Jitter.record(duration + self.warmup)?, None)
+                }
+                result => {
+                    let (audio, config) = result?;
+                    (audio, Some(config))
+                }
+            },
+        };
+        let warmup_samples = samples_in(self.warmup, audio.sample_rate, audio.channels);
+        drop_warmup(&mut audio, warmup_samples);
+        // Other sources record the whole duration; keep what the cap allows.
+        if let Some(max_samples) = self.max_samples {
+            truncate_audio(&mut audio, max_samples);
+        }
+        if audio.samples.is_empty() {
+            return Err(RandomizeError::EmptyRecording);
+        }
+
+        let requested_samples = samples_in(duration, audio.sample_rate, audio.channels);
+        let capture = CaptureStats {
+            requested_samples: self.max_samples.map_or(requested_samples, |max_samples| {
+                max_samples.min(requested_samples)
+            }),
+            captured_samples: audio.samples.len(),
+            dropped_callbacks: audio.dropped_callbacks,
+        };
+        if capture.is_short() {
+            log::warn!(
+                "captured only {} of {} requested samples",
+                capture.captured_samples,
+                capture.requested_samples
+            );
+        }
+        if capture.dropped_callbacks > 0 {
+            log::warn!(
+                "the input dropped samples from {} callbacks",
+                capture.dropped_callbacks
+            );
+        }
+
+        let max_kurtosis_deviation = self
+            .health_policy
+            .as_ref()
+            .map_or(DEFAULT_MAX_KURTOSIS_DEVIATION, |policy| {
+                policy.max_kurtosis_deviation
+            });
+        let metrics = noise_shape_metrics(&audio.samples);
+        if metrics.looks_non_thermal(max_kurtosis_deviation) {
+            log::warn!(
+                "sample kurtosis is {:.2}, far from the 3 of thermal noise; \
+                 the input may be dominated by a tone or interference",
+                metrics.kurtosis
+            );
+        }
+        let agc_drift = self.agc_drift(&audio);
+        if agc_drift.detected && self.agc_window.is_none() {
+            log::warn!(
+                "the input level drifts by {:.1}x, as automatic gain control does; \
+                 consider agc_compensation",
+                agc_drift.ratio()
+            );
+        }
+
+        match &self.health_policy {
+            Some(policy) => {
+                policy.check_clipping(&audio.samples)?;
+                policy.check_repeated_buffers(&audio.samples, &audio.buffers)?;
+            }
+            None => {
+                let repetitions = longest_repeated_buffer_run(&audio.samples, &audio.buffers);
+                if repetitions > DEFAULT_MAX_REPEATED_BUFFERS {
+                    log::warn!(
+                        "the input delivered the same buffer {} times in a row",
+                        repetitions
+                    );
+                }
+                let ratio = clipping_ratio(&audio.samples);
+                if ratio > DEFAULT_MAX_CLIPPING_RATIO {
+                    log::warn!(
+                        "{:.1}% of samples are clipped, lower the input gain",
+                        ratio * 100.0
+                    );
+                }
+            }
+        }
+        Ok((audio, capture, stream_config))
+    }
+
+    // Applies the configured channel selection and filters; returns the
+    // samples and their channel count.
+    fn filter(&self, audio: Audio) -> Result<(Vec<f32>, u16), RandomizeError> {
+        let Audio {
+            mut samples,
+            sample_rate,
+            mut channels,
+            ..
+        } = audio;
+        if let Some((first, second)) = self.channel_pair {
+            samples = select_channel_difference(&samples, channels, first, second)?;
+            channels = 1;
+        }
+
+        if let Some(cutoff_hz) = self.high_pass_cutoff_hz {
+            high_pass_filter(&mut samples, sample_rate, cutoff_hz);
+        }
+
+        if let Some(window_size) = self.agc_window {
+            sliding_normalize(&mut samples, window_size * channels.max(1) as usize);
+        }
+
+        if let Some(window_size) = self.spectral_whiten_window {
+            spectral_whiten(&mut samples, window_size);
+        }
+
+        if self.decorrelate {
+            decorrelate(&mut samples);
+        }
+        Ok((samples, channels))
+    }
+
+    /// Starts a [`LiveRecording`] on the selected device and sample rate
+    /// instead of recording for a fixed duration. The live samples are raw:
+    /// no channel difference or filtering is applied, but the warm-up is
+    /// dropped before this returns. Only the microphone can be streamed; a
+    /// custom [`source`](Self::source) is an error.
+    pub fn live(&self, capacity: Duration) -> Result<LiveRecording, RandomizeError> {
+        if self.source.is_some() {
+            return Err(RandomizeError::InvalidParameter(
+                "live recording requires the microphone source".to_string(),
+            ));
+        }
+        let (live, _) = self.microphone.open(|input_device, input_config| {
+            LiveRecording::start_on(
+                input_device,
+                input_config,
+                self.microphone.stream_buffer_size(),
+                capacity,
+            )
+        })?;
+        let warmup_samples = samples_in(self.warmup, live.sample_rate(), live.channels());
+        live.pop_chunk(warmup_samples.min(live.capacity()))?;
+        Ok(live)
+    }
+
+    /// Records, removes the DC offset, normalizes and extracts `output_length`
+    /// bytes, then applies the health policy if one is set.
+    pub fn collect_random_data(
+        &self,
+        num_lsb: u32,
+        output_length: usize,
+    ) -> Result<Vec<u8>, RandomizeError> {
+        let (audio, _, _) = self.record(self.extraction_duration(num_lsb, output_length))?;
+        let timing = self.callback_timing_bytes(&audio);
+        let (samples, _) = self.filter(audio)?;
+        self.extract_from(samples, &timing, num_lsb, output_length)
+    }
+
+    /// Like [`collect_random_data`](Self::collect_random_data), but fails
+    /// with [`RandomizeError::Timeout`] instead of extracting from a short
+    /// recording when fewer than [`MIN_CAPTURE_RATIO`] of the requested
+    /// samples arrive. The microphone gives up half a second after the
+    /// recording and warm-up should have ended, so a stalled device can't
+    /// block it. A custom [`SampleSource`] is trusted to return.
+    pub fn collect_with_timeout(
+        &self,
+        num_lsb: u32,
+        output_length: usize,
+    ) -> Result<Vec<u8>, RandomizeError> {
+        let duration = self.extraction_duration(num_lsb, output_length);
+        let timeout = |captured_samples| RandomizeError::Timeout {
+            deadline: duration + self.warmup + FILL_GRACE,
+            captured_samples,
+        };
+        let (audio, capture, _) = match self.record(duration) {
+            Err(RandomizeError::EmptyRecording) => return Err(timeout(0)),
+            result => result?,
+        };
+        if capture.is_short() {
+            return Err(timeout(capture.captured_samples));
+        }
+        let timing = self.callback_timing_bytes(&audio);
+        let (samples, _) = self.filter(audio)?;
+        self.extract_from(samples, &timing, num_lsb, output_length)
+    }
+
+    fn extract_from(
+        &self,
+        samples: Vec<f32>,
+        timing: &[u8],
+        num_lsb: u32,
+        output_length: usize,
+    ) -> Result<Vec<u8>, RandomizeError> {
+        let extracted = self.extract_normalized(samples, num_lsb, output_length)?;
+        Ok(self.condition(&extracted, timing))
+    }
+
+    // Removes the DC offset and normalizes as configured, then extracts.
+    fn extract_normalized(
+        &self,
+        samples: Vec<f32>,
+        num_lsb: u32,
+        output_length: usize,
+    ) -> Result<Sensitive<Vec<u8>>, RandomizeError> {
+        let mut samples = Sensitive::new(samples);
+        match (self.remove_dc, self.normalize, self.normalize_mode) {
+            (true, true, NormalizeMode::Peak) => {
+                center_and_normalize(&mut samples, self.normalize_target)
+            }
+            (remove_dc, normalize, mode) => {
+                if remove_dc {
+                    remove_dc_offset(&mut samples);
+                }
+                if normalize {
+                    normalize_audio_to(&mut samples, self.normalize_target, mode);
+                }
+            }
+        }
+        let extracted = Sensitive::new(self.extract_raw(&samples, num_lsb, output_length)?);
+        drop(samples);
+        Ok(extracted)
+    }
+
+    /// Records until the raw extracted bytes justify a full-entropy
+    /// `N`-byte seed, then conditions them into one. Returns the seed and
+    /// the number of recordings it took.
+    ///
+    /// Each round records and extracts `8 * N` bytes like
+    /// [`collect_random_data`](Self::collect_random_data), under the health
+    /// policy if one is set, and adds them to what earlier rounds yielded.
+    /// Once [`entropy_capped_length`](crate::entropy_capped_length) credits
+    /// all of it with at least `8 * N` bits of min-entropy, it is
+    /// conditioned, with the additional source and callback timing if set,
+    /// by the configured conditioner or SHA-256. After
+    /// [`MAX_SEED_ROUNDS`] rounds that together fall short it fails with
+    /// [`RandomizeError::InsufficientData`].
+    pub fn fold_into_seed<const N: usize>(
+        &self,
+        num_lsb: u32,
+    ) -> Result<([u8; N], u32), RandomizeError> {
+        let mut raw = Sensitive::new(Vec::new());
+        let mut timing = Sensitive::new(Vec::new());
+        for round in 1..=MAX_SEED_ROUNDS {
+            let (audio, _, _) = self.record(self.extraction_duration(num_lsb, 8 * N))?;
+            timing.extend_from_slice(&self.callback_timing_bytes(&audio));
+            let (samples, _) = self.filter(audio)?;
+            raw.extend_from_slice(&self.extract_normalized(samples, num_lsb, 8 * N)?);
+            if entropy_capped_length(&raw, N) < N {
+                continue;
+            }
+
+            let mut input = Sensitive::new(raw.to_vec());
+            if let Some(additional) = self.additional_bytes(raw.len()) {
+                input.extend_from_slice(&additional);
+            }
+            input.extend_from_slice(&timing);
+            let conditioner = self.conditioner.as_deref().unwrap_or(&Sha256Conditioner);
+            let conditioned =
+                Sensitive::new(conditioner.condition_with_context(&input, &self.context, N));
+            let seed = <[u8; N]>::try_from(conditioned.as_slice()).map_err(|_| {
+                RandomizeError::InsufficientData {
+                    required_bits: 8 * N,
+                    available_bits: 8 * conditioned.len(),
+                }
+            })?;
+            return Ok((seed, round));
+        }
+        Err(RandomizeError::InsufficientData {
+            required_bits: 8 * N,
+            available_bits: (min_entropy_mcv(&raw) * raw.len() as f64) as usize,
+        })
+    }
+
+    /// Fills `buf` completely, recording again whenever one recording
+    /// yields fewer bytes than are still missing.
+    pub fn fill_bytes(&self, num_lsb: u32, buf: &mut [u8]) -> Result<(), RandomizeError> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            let random_data = self.collect_random_data(num_lsb, buf.len() - filled)?;
+            if random_data.is_empty() {
+                return Err(RandomizeError::InsufficientData {
+                    required_bits: (buf.len() - filled) * 8,
+                    available_bits: 0,
+                });
+            }
+            buf[filled..filled + random_data.len()].copy_from_slice(&random_data);
+            filled += random_data.len();
+        }
+        Ok(())
+    }
+
+    // Extracts and health-checks, without conditioning.
+    fn extract_raw(
+        &self,
+        samples: &[f32],
+        num_lsb: u32,
+        output_length: usize,
+    ) -> Result<Vec<u8>, RandomizeError> {
+        let random_data = extract_random_data_with(
+            samples,
+            ExtractParams {
+                debiaser: self.debiaser,
+                lsb_planes: self.lsb_planes,
+                ..ExtractParams::new(num_lsb, output_length)
+            },
+        )?;
+
+        match &self.health_policy {
+            Some(policy) => HealthPolicy {
+                significance_level: self.significance_level.unwrap_or(policy.significance_level),
+                max_autocorrelation: self.max_autocorrelation.or(policy.max_autocorrelation),
+                ..policy.clone()
+            }
+            .check(&random_data)?,
+            None => {
+                if let Some(max_autocorrelation) = self.max_autocorrelation {
+                    check_autocorrelation(&random_data, max_autocorrelation)?;
+                }
+            }
+        }
+        Ok(random_data)
+    }
+
+    // The low byte of every callback interval, if callback timing is mixed in.
+    fn callback_timing_bytes(&self, audio: &Audio) -> Sensitive<Vec<u8>> {
+        if !self.callback_timing {
+            return Sensitive::new(Vec::new());
+        }
+        Sensitive::new(
+            audio
+                .callback_intervals
+                .iter()
+                .map(|interval| interval.as_nanos() as u8)
+                .collect(),
+        )
+    }
+
+    // `length` bytes from the additional source, if there is one.
+    fn additional_bytes(&self, length: usize) -> Option<Sensitive<Vec<u8>>> {
+        self.additional_source.as_ref().map(|source| {
+            let mut source = source.lock().unwrap_or_else(|err| err.into_inner());
+            Sensitive::new(source(length))
+        })
+    }
+
+    fn condition(&self, extracted: &[u8], timing: &[u8]) -> Vec<u8> {
+        let mut additional = self.additional_bytes(extracted.len());
+        let conditioner: &dyn Conditioner = match (&self.conditioner, &additional) {
+            (Some(conditioner), _) => conditioner.as_ref(),
+            (None, Some(_)) => &Sha256Conditioner,
+            (None, None) if !timing.is_empty() => &Sha256Conditioner,
+            (None, None) => return extracted.to_vec(),
+        };
+        let output_length = if self.full_rate {
+            extracted.len()
+        } else {
+            let additional_length = additional.as_ref().map_or(0, |additional| {
+                entropy_capped_length(additional, additional.len())
+            });
+            (entropy_capped_length(extracted, extracted.len())
+                + additional_length
+                + entropy_capped_length(timing, timing.len()))
+            .min(extracted.len())
+        };
+        if !timing.is_empty() {
+            additional
+                .get_or_insert_with(|| Sensitive::new(Vec::new()))
+                .extend_from_slice(timing);
+        }
+        if output_length < extracted.len() {
+            log::warn!(
+                "the extracted bytes only justify {} of {} conditioned bytes",
+                output_length,
+                extracted.len()
+            );
+        }
+        match &additional {
+            Some(additional) => {
+                let mut input = Sensitive::new(extracted.to_vec());
+                input.extend_from_slice(additional);
+                conditioner.condition_with_context(&input, &self.context, output_length)
+            }
+            None => conditioner.condition_with_context(extracted, &self.context, output_length),
+        }
+    }
+}
+
+/// Recordings [`EntropyCollector::fold_into_seed`] makes at most.
+pub const MAX_SEED_ROUNDS: u32 = 16;
+
+/// Fraction of the requested samples below which a capture is reported as
+/// short.
+pub const MIN_CAPTURE_RATIO: f64 = 0.9;
+
+/// How much of the requested recording arrived, after the warm-up and any
+/// [`max_samples`](EntropyCollector::max_samples) cap. A short capture
+/// yields fewer extracted bytes without failing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureStats {
+    pub requested_samples: usize,
+    pub captured_samples: usize,
+    /// See [`Audio::dropped_callbacks`].
+    pub dropped_callbacks: usize,
+}
+
+impl CaptureStats {
+    /// Whether fewer than [`MIN_CAPTURE_RATIO`] of the requested samples
+    /// were captured.
+    pub fn is_short(&self) -> bool {
+        (self.captured_samples as f64) < self.requested_samples as f64 * MIN_CAPTURE_RATIO
+    }
+}
+
+/// Every signal of one [`collect_stages`](EntropyCollector::collect_stages)
+/// run, from the recording to the extracted bytes.
+pub struct CollectionStages {
+    pub sample_rate: u32,
+    /// Channels of `filtered` and later stages; 1 when a channel difference
+    /// is taken, otherwise the samples are interleaved.
+    pub channels: u16,
+    /// Samples as recorded, without the warm-up, interleaved.
+    pub raw: Vec<f32>,
+    /// After channel selection, high-pass filtering, AGC compensation,
+    /// spectral whitening and decorrelation.
+    pub filtered: Vec<f32>,
+    /// `filtered` without its DC offset, or unchanged with
+    /// [`remove_dc(false)`](EntropyCollector::remove_dc).
+    pub dc_removed: Vec<f32>,
+    /// `dc_removed` normalized, or unchanged with
+    /// [`normalize(false)`](EntropyCollector::normalize).
+    pub normalized: Vec<f32>,
+    /// The bytes extracted from `normalized`, before conditioning.
+    pub extracted: Vec<u8>,
+    /// `extracted` after conditioning; the same bytes without a conditioner.
+    pub random_data: Vec<u8>,
+    /// Slow level changes in `raw`, measured over the
+    /// [`agc_compensation`](EntropyCollector::agc_compensation) window or
+    /// [`DEFAULT_AGC_WINDOW`] frames.
+    pub agc_drift: AgcDrift,
+    /// How complete the recording was.
+    pub capture: CaptureStats,
+    /// The config the microphone's stream ran with; `None` for other
+    /// sources, including the jitter fallback.
+    pub stream_config: Option<CaptureConfig>,
+    /// Wall-clock time spent recording.
+    pub recording_time: Duration,
+    /// Time spent filtering, normalizing and extracting.
+    pub processing_time: Duration,
+}
+
+impl CollectionStages {
+    /// Extracted bytes per second of recording and processing time. A
+    /// recording too short for the requested bytes, or debiasing losses,
+    /// show up as a lower rate.
+    pub fn bytes_per_second(&self) -> f64 {
+        self.random_data.len() as f64 / (self.recording_time + self.processing_time).as_secs_f64()
+    }
+
+    /// Compares `extracted` with `random_data`.
+    pub fn bias_report(&self) -> BiasReport {
+        bias_report(&self.extracted, &self.random_data)
+    }
+}
+
+impl Default for EntropyCollector {
+    fn default() -> Self {
+        EntropyCollector::new()
+    }
+}
+
+// Drops the first `warmup_samples` samples and trims the buffer lengths to
+// match.
+fn drop_warmup(audio: &mut Audio, warmup_samples: usize) {
+    let dropped = warmup_samples.min(audio.samples.len());
+    audio.samples.drain(..dropped);
+
+    let mut remaining = dropped;
+    let whole = audio
+        .buffers
+        .iter()
+        .take_while(|&&length| {
+            let inside = length <= remaining;
+            if inside {
+                remaining -= length;
+            }
+            inside
+        })
+        .count();
+    audio.buffers.drain(..whole);
+    if let Some(first) = audio.buffers.first_mut() {
+        *first -= remaining;
+    }
+}
+
+// Keeps the first `max_samples` samples and trims the buffer lengths to
+// match.
+fn truncate_audio(audio: &mut Audio, max_samples: usize) {
+    audio.samples.truncate(max_samples);
+
+    let mut remaining = audio.samples.len();
+    audio.buffers.retain_mut(|length| {
+        if remaining == 0 {
+            return false;
+        }
+        *length = (*length).min(remaining);
+        remaining -= *length;
+        true
+    });
+}
+
+fn select_channel_difference(
+    samples: &[f32],
+    channels: u16,
+    first: usize,
+    second: usize,
+) -> Result<Vec<f32>, RandomizeError> {
+    if channels < 2 {
+        log::warn!("device has a single channel, using it instead of a channel difference");
+        return Ok(samples.to_vec());
+    }
+    if first >= channels as usize || second >= channels as usize || first == second {
+        return Err(RandomizeError::InvalidParameter(format!(
+            "channel pair ({}, {}) is invalid for a {}-channel device",
+            first, second, channels
+        )));
+    }
+
+    let per_channel = deinterleave(samples, channels);
+    let correlation = channel_cross_correlation(&per_channel[first], &per_channel[second]);
+    // An inverted copy is as dependent as a plain one: L - (-L) is just 2L.
+    if correlation.abs() > MAX_CHANNEL_CORRELATION {
+        log::warn!(
+            "channels {} and {} have a cross-correlation of {:.3}, so they probably \
+             share one ADC and their difference carries little entropy",
+            first,
+            second,
+            correlation
+        );
+        return Err(RandomizeError::HealthCheckFailed {
+            failing_tests: vec!["channel_correlation".to_string()],
+        });
+    }
+    Ok(channel_difference(
+        &per_channel[first],
+        &per_channel[second],
+    ))
+}
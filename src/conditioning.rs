@@ -0,0 +1,318 @@
+use sha2::digest::{Digest, FixedOutputReset};
+use sha2::Sha256;
+
+use crate::entropy::min_entropy_mcv;
+use crate::error::RandomizeError;
+use crate::extraction::{bits_to_bytes, bytes_to_bits};
+
+/// Conditions raw entropy with SHA-256.
+///
+/// The first 32 bytes are `SHA256(raw)`; further blocks are
+/// `SHA256(raw || counter)` with a big-endian `u32` counter starting at 1.
+pub fn whiten_sha256(raw: &[u8], output_length: usize) -> Vec<u8> {
+    hash_counter_mode::<Sha256>(raw, output_length)
+}
+
+// The construction of `whiten_sha256` for any fixed-output hash.
+fn hash_counter_mode<D: Digest + FixedOutputReset>(raw: &[u8], output_length: usize) -> Vec<u8> {
+    let mut output = Vec::with_capacity(output_length);
+    let mut hasher = D::new();
+    Digest::update(&mut hasher, raw);
+    output.extend_from_slice(&hasher.finalize_reset());
+
+    let mut counter: u32 = 1;
+    while output.len() < output_length {
+        Digest::update(&mut hasher, raw);
+        Digest::update(&mut hasher, counter.to_be_bytes());
+        output.extend_from_slice(&hasher.finalize_reset());
+        counter += 1;
+    }
+
+    output.truncate(output_length);
+    output
+}
+
+/// A hash that turns raw extracted bytes into `output_length` conditioned
+/// bytes, see [`EntropyCollector::conditioner`](crate::EntropyCollector::conditioner).
+pub trait Conditioner: Send + Sync {
+    fn condition(&self, raw: &[u8], output_length: usize) -> Vec<u8>;
+
+    /// Conditions `raw` for one application, so the same entropy yields
+    /// independent output under different contexts. By default the context
+    /// is length-prefixed and put in front of `raw`, as in HKDF's info
+    /// field; an empty context is plain [`condition`](Self::condition).
+    fn condition_with_context(&self, raw: &[u8], context: &[u8], output_length: usize) -> Vec<u8> {
+        if context.is_empty() {
+            return self.condition(raw, output_length);
+        }
+        let mut input = Vec::with_capacity(8 + context.len() + raw.len());
+        input.extend_from_slice(&(context.len() as u64).to_be_bytes());
+        input.extend_from_slice(context);
+        input.extend_from_slice(raw);
+        self.condition(&input, output_length)
+    }
+}
+
+/// [`whiten_sha256`], the default conditioner.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sha256Conditioner;
+
+impl Conditioner for Sha256Conditioner {
+    fn condition(&self, raw: &[u8], output_length: usize) -> Vec<u8> {
+        whiten_sha256(raw, output_length)
+    }
+}
+
+/// BLAKE3 in extendable-output mode: the first `output_length` bytes of
+/// its output stream for `raw`. Faster than SHA-256 on most hardware.
+#[cfg(feature = "blake3")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Blake3Conditioner;
+
+#[cfg(feature = "blake3")]
+impl Conditioner for Blake3Conditioner {
+    fn condition(&self, raw: &[u8], output_length: usize) -> Vec<u8> {
+        let mut output = vec![0; output_length];
+        blake3::Hasher::new()
+            .update(raw)
+            .finalize_xof()
+            .fill(&mut output);
+        output
+    }
+}
+
+/// SHA3-256 in the counter construction of [`whiten_sha256`].
+#[cfg(feature = "sha3")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sha3Conditioner;
+
+#[cfg(feature = "sha3")]
+impl Conditioner for Sha3Conditioner {
+    fn condition(&self, raw: &[u8], output_length: usize) -> Vec<u8> {
+        hash_counter_mode::<sha3::Sha3_256>(raw, output_length)
+    }
+}
+
+/// A conditioner fed raw entropy in chunks as it is extracted, so the raw
+/// bytes of a large output never have to be held at once.
+///
+/// [`sha256`](Self::sha256) gives the output of [`whiten_sha256`] over the
+/// concatenated chunks, [`blake3`](Self::blake3) that of
+/// [`Blake3Conditioner`], and [`shake256`](Self::shake256) the SHAKE256
+/// output stream.
+///
+/// ```
+/// use randomize7::{whiten_sha256, IncrementalConditioner};
+///
+/// let mut conditioner = IncrementalConditioner::sha256();
+/// conditioner.update(b"first chunk, ");
+/// conditioner.update(b"second chunk");
+/// assert_eq!(
+///     conditioner.finalize_xof(64),
+///     whiten_sha256(b"first chunk, second chunk", 64)
+/// );
+/// ```
+#[derive(Clone)]
+pub struct IncrementalConditioner {
+    hasher: IncrementalHasher,
+}
+
+#[derive(Clone)]
+enum IncrementalHasher {
+    Sha256(Sha256),
+    #[cfg(feature = "blake3")]
+    Blake3(Box<blake3::Hasher>),
+    #[cfg(feature = "sha3")]
+    Shake256(Box<sha3::Shake256>),
+}
+
+/// Names the hash, never its state, which holds the raw entropy.
+impl std::fmt::Debug for IncrementalConditioner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hash = match self.hasher {
+            IncrementalHasher::Sha256(_) => "sha256",
+            #[cfg(feature = "blake3")]
+            IncrementalHasher::Blake3(_) => "blake3",
+            #[cfg(feature = "sha3")]
+            IncrementalHasher::Shake256(_) => "shake256",
+        };
+        f.debug_struct("IncrementalConditioner")
+            .field("hash", &hash)
+            .finish_non_exhaustive()
+    }
+}
+
+/// [`IncrementalConditioner::sha256`], matching the default conditioner.
+impl Default for IncrementalConditioner {
+    fn default() -> Self {
+        IncrementalConditioner::sha256()
+    }
+}
+
+impl IncrementalConditioner {
+    pub fn sha256() -> Self {
+        IncrementalConditioner {
+            hasher: IncrementalHasher::Sha256(Sha256::new()),
+        }
+    }
+
+    #[cfg(feature = "blake3")]
+    pub fn blake3() -> Self {
+        IncrementalConditioner {
+            hasher: IncrementalHasher::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+
+    #[cfg(feature = "sha3")]
+    pub fn shake256() -> Self {
+        IncrementalConditioner {
+            hasher: IncrementalHasher::Shake256(Box::default()),
+        }
+    }
+
+    pub fn update(&mut self, raw_chunk: &[u8]) {
+        match &mut self.hasher {
+            IncrementalHasher::Sha256(hasher) => Digest::update(hasher, raw_chunk),
+            #[cfg(feature = "blake3")]
+            IncrementalHasher::Blake3(hasher) => {
+                hasher.update(raw_chunk);
+            }
+            #[cfg(feature = "sha3")]
+            IncrementalHasher::Shake256(hasher) => {
+                sha3::digest::Update::update(hasher.as_mut(), raw_chunk)
+            }
+        }
+    }
+
+    /// The first `output_length` bytes of output for everything passed to
+    /// [`update`](Self::update).
+    pub fn finalize_xof(self, output_length: usize) -> Vec<u8> {
+        let mut output = vec![0; output_length];
+        match self.hasher {
+            IncrementalHasher::Sha256(hasher) => {
+                // Every block hashes the raw bytes first, so they share
+                // the state left by the updates.
+                for (counter, block) in (0u32..).zip(output.chunks_mut(32)) {
+                    let mut block_hasher = hasher.clone();
+                    if counter > 0 {
+                        Digest::update(&mut block_hasher, counter.to_be_bytes());
+                    }
+                    block.copy_from_slice(&block_hasher.finalize()[..block.len()]);
+                }
+            }
+            #[cfg(feature = "blake3")]
+            IncrementalHasher::Blake3(hasher) => hasher.finalize_xof().fill(&mut output),
+            #[cfg(feature = "sha3")]
+            IncrementalHasher::Shake256(hasher) => {
+                use sha3::digest::{ExtendableOutput, XofReader};
+                hasher.finalize_xof().read(&mut output);
+            }
+        }
+        output
+    }
+}
+
+/// XORs the sources together byte by byte, truncated to the shortest source.
+pub fn xor_fold(sources: &[Vec<u8>]) -> Vec<u8> {
+    let length = sources.iter().map(Vec::len).min().unwrap_or(0);
+    (0..length)
+        .map(|i| {
+            sources
+                .iter()
+                .fold(0u8, |folded, source| folded ^ source[i])
+        })
+        .collect()
+}
+
+/// Multiplies `raw_bits` by the `output_bits × raw_bits.len()` Toeplitz
+/// matrix over GF(2) whose diagonals are given by `seed_bits`.
+///
+/// Entry `(i, j)` of the matrix is `seed_bits[i + raw_bits.len() - 1 - j]`,
+/// so the seed needs `raw_bits.len() + output_bits - 1` bits.
+///
+/// # Panics
+///
+/// Panics if `seed_bits` is too short.
+pub fn toeplitz_extract(raw_bits: &[bool], seed_bits: &[bool], output_bits: usize) -> Vec<bool> {
+    if output_bits == 0 || raw_bits.is_empty() {
+        return vec![false; output_bits];
+    }
+    let required = raw_bits.len() + output_bits - 1;
+    assert!(
+        seed_bits.len() >= required,
+        "Toeplitz seed needs {} bits but has {}",
+        required,
+        seed_bits.len()
+    );
+
+    (0..output_bits)
+        .map(|i| {
+            let diagonals = &seed_bits[i..i + raw_bits.len()];
+            raw_bits
+                .iter()
+                .zip(diagonals.iter().rev())
+                .fold(false, |bit, (&raw, &seed)| bit ^ (raw & seed))
+        })
+        .collect()
+}
+
+/// Number of bits [`toeplitz_condition`] extracts from `raw`: its
+/// [MCV min-entropy](min_entropy_mcv) estimate, rounded down to whole bytes.
+pub fn toeplitz_output_bits(raw: &[u8]) -> usize {
+    let min_entropy_bits = (min_entropy_mcv(raw) * raw.len() as f64).floor() as usize;
+    min_entropy_bits / 8 * 8
+}
+
+/// Conditions raw entropy with [`toeplitz_extract`], compressing it down to
+/// [`toeplitz_output_bits`]. `seed` must hold at least
+/// `raw.len() * 8 + toeplitz_output_bits(raw) - 1` bits; a fixed seed makes
+/// the output reproducible.
+pub fn toeplitz_condition(raw: &[u8], seed: &[u8]) -> Result<Vec<u8>, RandomizeError> {
+    let output_bits = toeplitz_output_bits(raw);
+    let raw_bits = bytes_to_bits(raw);
+    let seed_bits = bytes_to_bits(seed);
+
+    let required_bits = (raw_bits.len() + output_bits).saturating_sub(1);
+    if output_bits > 0 && seed_bits.len() < required_bits {
+        return Err(RandomizeError::InsufficientData {
+            required_bits,
+            available_bits: seed_bits.len(),
+        });
+    }
+
+    Ok(bits_to_bytes(&toeplitz_extract(
+        &raw_bits,
+        &seed_bits,
+        output_bits,
+    )))
+}
+
+/// [`toeplitz_extract`] with a fixed seed as a [`Conditioner`], so a Toeplitz
+/// hash can be chosen with
+/// [`EntropyCollector::conditioner`](crate::EntropyCollector::conditioner).
+///
+/// Output stops at [`toeplitz_output_bits`] of the input, and at what the
+/// seed is long enough for, so it may be shorter than `output_length`: a
+/// seed of `s` bits conditions `r` raw bits into at most `s - r + 1` bits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToeplitzConditioner {
+    seed: Vec<u8>,
+}
+
+impl ToeplitzConditioner {
+    pub fn new(seed: impl Into<Vec<u8>>) -> Self {
+        ToeplitzConditioner { seed: seed.into() }
+    }
+}
+
+impl Conditioner for ToeplitzConditioner {
+    fn condition(&self, raw: &[u8], output_length: usize) -> Vec<u8> {
+        let raw_bits = bytes_to_bits(raw);
+        let seed_bits = bytes_to_bits(&self.seed);
+        let seed_capacity = (seed_bits.len() + 1).saturating_sub(raw_bits.len()) / 8 * 8;
+        let output_bits = (output_length * 8)
+            .min(toeplitz_output_bits(raw))
+            .min(seed_capacity);
+        bits_to_bytes(&toeplitz_extract(&raw_bits, &seed_bits, output_bits))
+    }
+}
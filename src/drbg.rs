@@ -0,0 +1,218 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::fmt;
+
+#[cfg(feature = "microphone")]
+use crate::collector::EntropyCollector;
+#[cfg(feature = "microphone")]
+use crate::entropy::min_entropy_mcv;
+use crate::error::RandomizeError;
+
+/// Security strength of HMAC-SHA256, in bytes. Entropy inputs must carry at
+/// least this much entropy, nonces at least half.
+pub const DRBG_SECURITY_STRENGTH: usize = 32;
+
+/// Largest single request SP800-90A allows, 2^19 bits.
+pub const DRBG_MAX_BYTES_PER_REQUEST: usize = 1 << 16;
+
+// SP800-90A table 2.
+const RESEED_INTERVAL: u64 = 1 << 48;
+
+// Bytes extracted from one recording when seeding from audio; the first
+// part is the entropy input, the rest the nonce.
+#[cfg(feature = "microphone")]
+const AUDIO_ENTROPY_BYTES: usize = 384;
+#[cfg(feature = "microphone")]
+const AUDIO_NONCE_BYTES: usize = 128;
+
+/// HMAC_DRBG with SHA-256 as specified in SP800-90A section 10.1.2, without
+/// prediction resistance.
+///
+/// ```
+/// use randomize7::HmacDrbg;
+///
+/// let mut drbg = HmacDrbg::new(&[7; 32], &[1; 16], b"example")?;
+/// let key = drbg.generate(32);
+/// # Ok::<(), randomize7::RandomizeError>(())
+/// ```
+pub struct HmacDrbg {
+    key: [u8; 32],
+    value: [u8; 32],
+    reseed_counter: u64,
+}
+
+impl HmacDrbg {
+    /// Instantiates the DRBG. `entropy_input` must be at least
+    /// [`DRBG_SECURITY_STRENGTH`] bytes and `nonce` at least half that; the
+    /// caller is responsible for them actually holding that much entropy.
+    pub fn new(
+        entropy_input: &[u8],
+        nonce: &[u8],
+        personalization: &[u8],
+    ) -> Result<Self, RandomizeError> {
+        check_length(entropy_input, DRBG_SECURITY_STRENGTH)?;
+        check_length(nonce, DRBG_SECURITY_STRENGTH / 2)?;
+
+        let mut drbg = HmacDrbg {
+            key: [0x00; 32],
+            value: [0x01; 32],
+            reseed_counter: 1,
+        };
+        drbg.update(&[entropy_input, nonce, personalization]);
+        Ok(drbg)
+    }
+
+    /// Instantiates from one recording: the audio supplies both the entropy
+    /// input and the nonce, checked against their required min-entropy with
+    /// [`min_entropy_mcv`].
+    #[cfg(feature = "microphone")]
+    pub fn from_collector(
+        collector: &EntropyCollector,
+        num_lsb: u32,
+    ) -> Result<Self, RandomizeError> {
+        let (entropy_input, nonce) = collect_seed(collector, num_lsb)?;
+        HmacDrbg::new(&entropy_input, &nonce, &[])
+    }
+
+    /// Mixes fresh entropy into the state and resets the reseed counter.
+    pub fn reseed(
+        &mut self,
+        entropy_input: &[u8],
+        additional_input: &[u8],
+    ) -> Result<(), RandomizeError> {
+        check_length(entropy_input, DRBG_SECURITY_STRENGTH)?;
+        self.update(&[entropy_input, additional_input]);
+        self.reseed_counter = 1;
+        Ok(())
+    }
+
+    /// Reseeds from a new recording, using the whole batch as entropy input.
+    #[cfg(feature = "microphone")]
+    pub fn reseed_from_collector(
+        &mut self,
+        collector: &EntropyCollector,
+        num_lsb: u32,
+    ) -> Result<(), RandomizeError> {
+        let (entropy_input, nonce) = collect_seed(collector, num_lsb)?;
+        self.reseed(&[entropy_input, nonce].concat(), &[])
+    }
+
+    /// Returns `n` pseudorandom bytes. Requests above
+    /// [`DRBG_MAX_BYTES_PER_REQUEST`] are served as several requests.
+    ///
+    /// # Panics
+    ///
+    /// After 2^48 requests without a reseed, as SP800-90A requires.
+    pub fn generate(&mut self, n: usize) -> Vec<u8> {
+        self.generate_with_additional_input(n, &[])
+    }
+
+    /// Like [`generate`](Self::generate), mixing `additional_input` into
+    /// every request.
+    pub fn generate_with_additional_input(&mut self, n: usize, additional_input: &[u8]) -> Vec<u8> {
+        let mut output = Vec::with_capacity(n);
+        while output.len() < n {
+            let request = (n - output.len()).min(DRBG_MAX_BYTES_PER_REQUEST);
+            self.generate_request(request, additional_input, &mut output);
+        }
+        output
+    }
+
+    /// Requests served since instantiation or the last reseed, plus one.
+    pub fn reseed_counter(&self) -> u64 {
+        self.reseed_counter
+    }
+
+    // SP800-90A section 10.1.2.5.
+    fn generate_request(&mut self, n: usize, additional_input: &[u8], output: &mut Vec<u8>) {
+        assert!(
+            self.reseed_counter <= RESEED_INTERVAL,
+            "HMAC_DRBG must be reseeded"
+        );
+        if !additional_input.is_empty() {
+            self.update(&[additional_input]);
+        }
+
+        let end = output.len() + n;
+        while output.len() < end {
+            self.value = hmac(&self.key, &[&self.value]);
+            let take = (end - output.len()).min(self.value.len());
+            output.extend_from_slice(&self.value[..take]);
+        }
+
+        self.update(&[additional_input]);
+        self.reseed_counter += 1;
+    }
+
+    // HMAC_DRBG_Update, section 10.1.2.2. `provided_data` is the
+    // concatenation of its parts.
+    fn update(&mut self, provided_data: &[&[u8]]) {
+        for separator in [[0x00], [0x01]] {
+            let mut parts: Vec<&[u8]> = vec![&self.value, &separator];
+            parts.extend_from_slice(provided_data);
+            self.key = hmac(&self.key, &parts);
+            self.value = hmac(&self.key, &[&self.value]);
+
+            if provided_data.iter().all(|part| part.is_empty()) {
+                return;
+            }
+        }
+    }
+}
+
+/// Shows the reseed counter, never the key or value.
+impl fmt::Debug for HmacDrbg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HmacDrbg")
+            .field("reseed_counter", &self.reseed_counter)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for HmacDrbg {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.key);
+        zeroize::Zeroize::zeroize(&mut self.value);
+    }
+}
+
+fn hmac(key: &[u8], parts: &[&[u8]]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    for part in parts {
+        mac.update(part);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+fn check_length(input: &[u8], required: usize) -> Result<(), RandomizeError> {
+    if input.len() < required {
+        return Err(RandomizeError::InsufficientData {
+            required_bits: required * 8,
+            available_bits: input.len() * 8,
+        });
+    }
+    Ok(())
+}
+
+// Records one batch and splits it into an entropy input and a nonce, each
+// carrying enough estimated min-entropy for its role.
+#[cfg(feature = "microphone")]
+fn collect_seed(
+    collector: &EntropyCollector,
+    num_lsb: u32,
+) -> Result<(Vec<u8>, Vec<u8>), RandomizeError> {
+    let mut entropy_input =
+        collector.collect_random_data(num_lsb, AUDIO_ENTROPY_BYTES + AUDIO_NONCE_BYTES)?;
+    let nonce = entropy_input.split_off(AUDIO_ENTROPY_BYTES.min(entropy_input.len()));
+
+    let entropy_bits = |data: &[u8]| min_entropy_mcv(data) * data.len() as f64;
+    if entropy_bits(&entropy_input) < (DRBG_SECURITY_STRENGTH * 8) as f64
+        || entropy_bits(&nonce) < (DRBG_SECURITY_STRENGTH * 4) as f64
+    {
+        return Err(RandomizeError::HealthCheckFailed {
+            failing_tests: vec!["min_entropy".to_string()],
+        });
+    }
+    Ok((entropy_input, nonce))
+}
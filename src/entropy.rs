@@ -0,0 +1,149 @@
+use crate::statistical_tests::monobit_result;
+
+// Upper bound of the 99% confidence interval, per SP800-90B.
+const Z_99: f64 = 2.576;
+
+// Resolution `extraction_efficiency` assumes of the ADC behind the samples.
+const ADC_BITS: u32 = 16;
+
+/// SP800-90B most-common-value estimate, in bits of min-entropy per byte.
+///
+/// Takes the upper 99% confidence bound on the probability of the most common
+/// byte, so the result is conservative. Inputs of fewer than two bytes carry
+/// no usable estimate and return 0.
+pub fn min_entropy_mcv(data: &[u8]) -> f64 {
+    if data.len() < 2 {
+        return 0.0;
+    }
+
+    let counts = byte_histogram(data);
+    mcv_estimate(*counts.iter().max().unwrap(), data.len())
+}
+
+/// Shannon entropy of the byte distribution of `data`, in bits per byte; 0
+/// for empty input.
+///
+/// A descriptive statistic only: it averages over all byte values, while an
+/// attacker guesses the most likely one, so it overstates usable randomness
+/// next to [`min_entropy_mcv`] and shouldn't gate output.
+pub fn shannon_entropy(data: &[u8]) -> f64 {
+    let length = data.len() as f64;
+    byte_histogram(data)
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / length;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Output bits per bit of input min-entropy when `output_bytes` are
+/// extracted from `samples` at `num_lsb` bits per sample. Above 1.0 the
+/// extraction claims more randomness than the recording holds, e.g. because
+/// normalization smeared a quiet signal over float mantissa bits.
+///
+/// A continuous signal has no min-entropy of its own, so the samples are
+/// quantized to the 16 bits of a typical ADC, full scale at ±1.0, and the
+/// [most-common-value estimate](min_entropy_mcv) is taken over those
+/// levels. Each sample is credited at most `num_lsb` bits, the most
+/// extraction can take from it. Pass the samples before normalization,
+/// which only rescales them. Samples without entropy give infinity unless
+/// `output_bytes` is 0.
+pub fn extraction_efficiency(samples: &[f32], output_bytes: usize, num_lsb: u32) -> f64 {
+    let output_bits = output_bytes as f64 * 8.0;
+    if output_bits == 0.0 {
+        return 0.0;
+    }
+    if samples.len() < 2 {
+        return f64::INFINITY;
+    }
+
+    let full_scale = ((1u32 << (ADC_BITS - 1)) - 1) as f32;
+    let mut counts = vec![0u64; 1 << ADC_BITS];
+    for &sample in samples {
+        let level = (sample.clamp(-1.0, 1.0) * full_scale).round() as i32;
+        counts[(level + full_scale as i32) as usize] += 1;
+    }
+    let per_sample = mcv_estimate(*counts.iter().max().unwrap(), samples.len()).min(num_lsb as f64);
+    // A constant input estimates as -0.0.
+    if per_sample <= 0.0 {
+        return f64::INFINITY;
+    }
+    output_bits / (per_sample * samples.len() as f64)
+}
+
+// Min-entropy from the count of the most common of `length` values.
+fn mcv_estimate(max_count: u64, length: usize) -> f64 {
+    let length = length as f64;
+    let p_hat = max_count as f64 / length;
+    let p_upper = (p_hat + Z_99 * (p_hat * (1.0 - p_hat) / (length - 1.0)).sqrt()).min(1.0);
+    -p_upper.log2()
+}
+
+/// How many of `requested` conditioned bytes the min-entropy of `raw`
+/// justifies: at most `floor(min_entropy_mcv(raw) * raw.len() / 8)`, so a
+/// conditioner never stretches less entropy over more output.
+pub fn entropy_capped_length(raw: &[u8], requested: usize) -> usize {
+    let justified = (min_entropy_mcv(raw) * raw.len() as f64 / 8.0).floor() as usize;
+    requested.min(justified)
+}
+
+/// Occurrences of each byte value, indexed by value.
+pub fn byte_histogram(data: &[u8]) -> [u64; 256] {
+    let mut counts = [0u64; 256];
+    for &byte in data {
+        counts[byte as usize] += 1;
+    }
+    counts
+}
+
+/// Fraction of ones at each bit position, index 0 being the most
+/// significant bit. A stuck bit shows up as 0 or 1; empty input gives NaN.
+pub fn bit_position_bias(data: &[u8]) -> [f64; 8] {
+    let mut ones = [0u64; 8];
+    for &byte in data {
+        for (position, count) in ones.iter_mut().enumerate() {
+            *count += (byte >> (7 - position) & 1) as u64;
+        }
+    }
+    ones.map(|count| count as f64 / data.len() as f64)
+}
+
+/// Bias measures of one byte stream, see [`BiasReport`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BiasSummary {
+    /// Fraction of one bits; 0.5 for unbiased data.
+    pub ones_proportion: f64,
+    /// P-value of the monobit test.
+    pub monobit_p_value: f64,
+    /// [`min_entropy_mcv`], in bits per byte.
+    pub min_entropy_per_byte: f64,
+}
+
+impl BiasSummary {
+    pub fn of(data: &[u8]) -> Self {
+        let ones = data.iter().map(|&byte| byte.count_ones()).sum::<u32>();
+        BiasSummary {
+            ones_proportion: ones as f64 / (data.len() * 8).max(1) as f64,
+            monobit_p_value: monobit_result(data).p_value,
+            min_entropy_per_byte: min_entropy_mcv(data),
+        }
+    }
+}
+
+/// The same measures before and after conditioning, to show what the
+/// conditioner corrected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BiasReport {
+    pub raw: BiasSummary,
+    pub conditioned: BiasSummary,
+}
+
+/// Summarizes the extracted bytes and their conditioned output.
+pub fn bias_report(raw: &[u8], conditioned: &[u8]) -> BiasReport {
+    BiasReport {
+        raw: BiasSummary::of(raw),
+        conditioned: BiasSummary::of(conditioned),
+    }
+}
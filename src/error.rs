@@ -0,0 +1,179 @@
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug)]
+pub enum RandomizeError {
+    NoInputDevice,
+    LoopbackUnavailable(String),
+    DeviceNotFound {
+        requested: String,
+        available: Vec<String>,
+    },
+    UnsupportedConfig {
+        requested: String,
+        supported: Vec<String>,
+    },
+    #[cfg(feature = "microphone")]
+    Devices(cpal::DevicesError),
+    #[cfg(feature = "microphone")]
+    DefaultConfig(cpal::DefaultStreamConfigError),
+    #[cfg(feature = "microphone")]
+    SupportedConfigs(cpal::SupportedStreamConfigsError),
+    #[cfg(feature = "microphone")]
+    StreamBuild(cpal::BuildStreamError),
+    #[cfg(feature = "microphone")]
+    StreamPlay(cpal::PlayStreamError),
+    #[cfg(feature = "microphone")]
+    StreamFailed(cpal::StreamError),
+    EmptyRecording,
+    InsufficientSamples {
+        required: usize,
+        available: usize,
+    },
+    Wav(hound::Error),
+    Io(std::io::Error),
+    EmptyInput,
+    InvalidParameter(String),
+    HealthCheckFailed {
+        failing_tests: Vec<String>,
+    },
+    InsufficientData {
+        required_bits: usize,
+        available_bits: usize,
+    },
+    TooFewCycles {
+        required: usize,
+        available: usize,
+    },
+    RepeatedBuffer {
+        repetitions: usize,
+    },
+    AutocorrelationTooHigh {
+        measured: f64,
+    },
+    Timeout {
+        deadline: Duration,
+        captured_samples: usize,
+    },
+}
+
+impl fmt::Display for RandomizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RandomizeError::NoInputDevice => write!(f, "no input device available"),
+            RandomizeError::LoopbackUnavailable(reason) => {
+                write!(f, "loopback capture unavailable: {}", reason)
+            }
+            RandomizeError::DeviceNotFound {
+                requested,
+                available,
+            } => write!(
+                f,
+                "no input device matching {:?}; available devices: {}",
+                requested,
+                available.join(", ")
+            ),
+            RandomizeError::UnsupportedConfig {
+                requested,
+                supported,
+            } => write!(
+                f,
+                "the input device does not support {}; supported configs: {}",
+                requested,
+                supported.join(", ")
+            ),
+            #[cfg(feature = "microphone")]
+            RandomizeError::Devices(err) => write!(f, "failed to enumerate input devices: {}", err),
+            #[cfg(feature = "microphone")]
+            RandomizeError::DefaultConfig(err) => {
+                write!(f, "failed to get default input config: {}", err)
+            }
+            #[cfg(feature = "microphone")]
+            RandomizeError::SupportedConfigs(err) => {
+                write!(f, "failed to query supported input configs: {}", err)
+            }
+            #[cfg(feature = "microphone")]
+            RandomizeError::StreamBuild(err) => write!(f, "failed to build input stream: {}", err),
+            #[cfg(feature = "microphone")]
+            RandomizeError::StreamPlay(err) => write!(f, "failed to start input stream: {}", err),
+            #[cfg(feature = "microphone")]
+            RandomizeError::StreamFailed(err) => write!(f, "input stream failed: {}", err),
+            RandomizeError::EmptyRecording => write!(f, "recording captured no samples"),
+            RandomizeError::InsufficientSamples {
+                required,
+                available,
+            } => write!(
+                f,
+                "at least {} samples are required but only {} are available",
+                required, available
+            ),
+            RandomizeError::Wav(err) => write!(f, "failed to read WAV file: {}", err),
+            RandomizeError::Io(err) => write!(f, "I/O error: {}", err),
+            RandomizeError::EmptyInput => write!(f, "input data is empty"),
+            RandomizeError::InvalidParameter(reason) => write!(f, "invalid parameter: {}", reason),
+            RandomizeError::HealthCheckFailed { failing_tests } => {
+                write!(
+                    f,
+                    "entropy failed health checks: {}",
+                    failing_tests.join(", ")
+                )
+            }
+            RandomizeError::InsufficientData {
+                required_bits,
+                available_bits,
+            } => write!(
+                f,
+                "test requires at least {} bits but only {} are available",
+                required_bits, available_bits
+            ),
+            RandomizeError::TooFewCycles {
+                required,
+                available,
+            } => write!(
+                f,
+                "test requires at least {} random walk cycles but only {} were found",
+                required, available
+            ),
+            RandomizeError::RepeatedBuffer { repetitions } => write!(
+                f,
+                "the input delivered the same buffer {} times in a row",
+                repetitions
+            ),
+            RandomizeError::Timeout {
+                deadline,
+                captured_samples,
+            } => write!(
+                f,
+                "only {} samples arrived within {:?}",
+                captured_samples, deadline
+            ),
+            RandomizeError::AutocorrelationTooHigh { measured } => write!(
+                f,
+                "lag-1 autocorrelation of the extracted bits is {:.4}, above the limit",
+                measured
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RandomizeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "microphone")]
+            RandomizeError::Devices(err) => Some(err),
+            #[cfg(feature = "microphone")]
+            RandomizeError::DefaultConfig(err) => Some(err),
+            #[cfg(feature = "microphone")]
+            RandomizeError::SupportedConfigs(err) => Some(err),
+            #[cfg(feature = "microphone")]
+            RandomizeError::StreamBuild(err) => Some(err),
+            #[cfg(feature = "microphone")]
+            RandomizeError::StreamPlay(err) => Some(err),
+            #[cfg(feature = "microphone")]
+            RandomizeError::StreamFailed(err) => Some(err),
+            RandomizeError::Wav(err) => Some(err),
+            RandomizeError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,932 @@
+use bitvec::prelude::*;
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use num_complex::Complex;
+use std::time::Duration;
+
+use crate::conditioning::whiten_sha256;
+use crate::entropy::min_entropy_mcv;
+use crate::error::RandomizeError;
+use crate::fft::forward_fft;
+use crate::processing::center_and_normalize;
+
+// Von Neumann keeps on average one bit out of every four for unbiased input,
+// so that many more raw bytes are pulled before debiasing.
+const VON_NEUMANN_EXPANSION: usize = 4;
+
+/// Bits [`elias_debias`] maps at a time.
+pub const ELIAS_BLOCK_BITS: usize = 32;
+
+/// How extracted bits are debiased. The yields are output bits per input
+/// bit for independent input; a bias lowers all of them, and correlated
+/// bits aren't fixed by any.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Debiaser {
+    /// Keeps every bit.
+    #[default]
+    None,
+    /// [`von_neumann_debias`]: `p (1 - p)` for ones with probability `p`,
+    /// a quarter for unbiased input.
+    VonNeumann,
+    /// [`iterated_von_neumann_debias`]: approaches the entropy of the
+    /// input, about 0.97 for unbiased and 0.86 for 70% ones over long
+    /// inputs.
+    IteratedVonNeumann,
+    /// [`elias_debias`]: about 0.86 for unbiased and 0.73 for 70% ones
+    /// with blocks of [`ELIAS_BLOCK_BITS`].
+    Elias,
+}
+
+impl Debiaser {
+    pub fn debias(self, bits: &[bool]) -> Vec<bool> {
+        match self {
+            Debiaser::None => bits.to_vec(),
+            Debiaser::VonNeumann => von_neumann_debias(bits),
+            Debiaser::IteratedVonNeumann => iterated_von_neumann_debias(bits),
+            Debiaser::Elias => elias_debias(bits),
+        }
+    }
+
+    // How many more raw bits to extract than output is wanted; generous
+    // for the efficient debiasers so that biased input still fills it.
+    pub(crate) fn expansion(self) -> usize {
+        match self {
+            Debiaser::None => 1,
+            Debiaser::VonNeumann => VON_NEUMANN_EXPANSION,
+            Debiaser::IteratedVonNeumann | Debiaser::Elias => 2,
+        }
+    }
+}
+
+/// How bits are derived from the recording.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExtractStrategy {
+    /// The low `num_lsb` bits of each adjacent-sample difference.
+    #[default]
+    LsbDifference,
+    /// The low `num_lsb` bits of each raw sample.
+    LsbRaw,
+    /// One bit per window: the parity of the low `num_lsb` bits of the XOR of
+    /// every sample in the window. Windows cover the whole recording.
+    WindowParity,
+    /// One bit per sample: whether the second difference is positive.
+    SecondDifferenceSign,
+    /// The low `num_lsb` bits of the XOR of `window` consecutive
+    /// adjacent-sample differences, so every difference in a window adds to
+    /// the unit. Windows don't overlap; `window = 1` is `LsbDifference`.
+    XorDifferences { window: usize },
+}
+
+impl ExtractStrategy {
+    fn bits_per_unit(self, num_lsb: u32) -> u32 {
+        match self {
+            ExtractStrategy::LsbDifference
+            | ExtractStrategy::LsbRaw
+            | ExtractStrategy::XorDifferences { .. } => num_lsb,
+            ExtractStrategy::WindowParity | ExtractStrategy::SecondDifferenceSign => 1,
+        }
+    }
+
+    fn min_samples(self) -> usize {
+        match self {
+            ExtractStrategy::LsbRaw | ExtractStrategy::WindowParity => 1,
+            ExtractStrategy::LsbDifference => 2,
+            ExtractStrategy::SecondDifferenceSign => 3,
+            ExtractStrategy::XorDifferences { window } => window.max(1) + 1,
+        }
+    }
+
+    fn available_units(self, num_samples: usize) -> usize {
+        match self {
+            ExtractStrategy::XorDifferences { window } => {
+                num_samples.saturating_sub(1) / window.max(1)
+            }
+            _ => num_samples.saturating_sub(self.min_samples() - 1),
+        }
+    }
+
+    // Value of the unit starting at sample `unit`; `window` is the distance
+    // to the next unit that will be read.
+    fn unit_value(self, samples: &[f32], unit: usize, window: usize, num_lsb: u32) -> u32 {
+        match self {
+            ExtractStrategy::LsbDifference => (samples[unit + 1] - samples[unit]).to_bits(),
+            ExtractStrategy::LsbRaw => samples[unit].to_bits(),
+            ExtractStrategy::WindowParity => {
+                let end = (unit + window).min(samples.len());
+                let folded = samples[unit..end]
+                    .iter()
+                    .fold(0u32, |folded, sample| folded ^ sample.to_bits());
+                (folded & lsb_mask(num_lsb)).count_ones() & 1
+            }
+            ExtractStrategy::SecondDifferenceSign => {
+                let second_difference = samples[unit + 2] - 2.0 * samples[unit + 1] + samples[unit];
+                (second_difference > 0.0) as u32
+            }
+            ExtractStrategy::XorDifferences { window } => {
+                let start = unit * window.max(1);
+                samples[start..=start + window.max(1)]
+                    .windows(2)
+                    .fold(0u32, |folded, pair| folded ^ (pair[1] - pair[0]).to_bits())
+            }
+        }
+    }
+}
+
+/// Min-entropy per bit that [`auto_select_lsb`] requires of its choice.
+pub const MIN_ENTROPY_PER_BIT: f64 = 0.9;
+
+// Largest LSB count `auto_select_lsb` tries; higher bits of a float
+// difference are dominated by its exponent.
+const MAX_AUTO_LSB: u32 = 16;
+
+/// Picks the largest `num_lsb` for [`ExtractStrategy::LsbDifference`] whose
+/// output keeps at least [`MIN_ENTROPY_PER_BIT`] bits of min-entropy per bit.
+///
+/// Each candidate from 1 to 16 is extracted from every difference in
+/// `samples` and scored with [`min_entropy_mcv`]. Returns the chosen count
+/// and its estimate in bits per bit. If no candidate meets the threshold,
+/// 1 is returned with its (low) estimate. The estimate's confidence bound
+/// needs a few thousand bytes of trial output, so give it a second or more
+/// of audio.
+pub fn auto_select_lsb(samples: &[f32]) -> (u32, f64) {
+    let strategy = ExtractStrategy::LsbDifference;
+    let units = strategy.available_units(samples.len());
+    let entropy_per_bit = |num_lsb: u32| {
+        let trial = extract_random_bits(samples, num_lsb, units * num_lsb as usize, strategy)
+            .map(|bits| whole_bytes(bits).into_vec())
+            .unwrap_or_default();
+        min_entropy_mcv(&trial) / 8.0
+    };
+
+    (1..=MAX_AUTO_LSB)
+        .rev()
+        .map(|num_lsb| (num_lsb, entropy_per_bit(num_lsb)))
+        .find(|&(_, entropy)| entropy >= MIN_ENTROPY_PER_BIT)
+        .unwrap_or_else(|| (1, entropy_per_bit(1)))
+}
+
+// A sample's `f32` encoding has 32 bits, and taking none would produce
+// nothing but zeros.
+fn check_num_lsb(num_lsb: u32) -> Result<(), RandomizeError> {
+    if (1..=32).contains(&num_lsb) {
+        Ok(())
+    } else {
+        Err(RandomizeError::InvalidParameter(format!(
+            "num_lsb must be between 1 and 32, got {}",
+            num_lsb
+        )))
+    }
+}
+
+fn lsb_mask(num_lsb: u32) -> u32 {
+    if num_lsb >= 32 {
+        u32::MAX
+    } else {
+        (1 << num_lsb) - 1
+    }
+}
+
+/// Extracts `output_bits` bits from the recording using `strategy`, most
+/// significant bit of each unit first.
+///
+/// This is the bit stream [`extract_random_data`] packs into bytes, before
+/// any debiasing or whitening. It isn't rounded down to whole bytes, so a
+/// `num_lsb` that doesn't divide 8 keeps every bit. A recording too short to
+/// supply `output_bits` yields fewer bits. `num_lsb` must be 1 to 32.
+///
+/// With the `parallel` feature units are extracted in chunks on the rayon
+/// thread pool; the bits are identical to the sequential run.
+pub fn extract_random_bits(
+    samples: &[f32],
+    num_lsb: u32,
+    output_bits: usize,
+    strategy: ExtractStrategy,
+) -> Result<BitVec<u8, Msb0>, RandomizeError> {
+    check_num_lsb(num_lsb)?;
+    let required = strategy.min_samples().max(2);
+    if samples.len() < required {
+        return Err(RandomizeError::InsufficientSamples {
+            required,
+            available: samples.len(),
+        });
+    }
+
+    let bits_per_unit = strategy.bits_per_unit(num_lsb);
+    let units_needed = output_bits.div_ceil(bits_per_unit.max(1) as usize);
+    let (units, units_per_step) = unit_starts(strategy, samples.len(), units_needed);
+    let unit_value = |unit: usize| strategy.unit_value(samples, unit, units_per_step, num_lsb);
+    #[cfg(feature = "parallel")]
+    let values: Vec<u32> = {
+        use rayon::prelude::*;
+        // Each chunk of units reads the samples it spans plus the one after
+        // its last unit, so the difference across a chunk boundary is still
+        // taken, and chunks are concatenated in order.
+        let units: Vec<usize> = units.collect();
+        units
+            .par_chunks(PARALLEL_CHUNK_UNITS)
+            .flat_map_iter(|chunk| chunk.iter().map(|&unit| unit_value(unit)))
+            .collect()
+    };
+    #[cfg(not(feature = "parallel"))]
+    let values: Vec<u32> = units.map(unit_value).collect();
+
+    let mut bits = pack_lsb_bits(&values, bits_per_unit);
+    bits.truncate(output_bits);
+    Ok(bits)
+}
+
+/// Exactly `output_bits` bits from adjacent-sample differences, for values
+/// that aren't a whole number of bytes, such as a 521-bit P-521 scalar.
+///
+/// Unlike [`extract_random_bits`], a recording too short to supply every bit
+/// is an error rather than a shorter result.
+pub fn extract_random_bits_exact(
+    samples: &[f32],
+    num_lsb: u32,
+    output_bits: usize,
+) -> Result<Vec<bool>, RandomizeError> {
+    let bits = extract_random_bits(samples, num_lsb, output_bits, ExtractStrategy::default())?;
+    if bits.len() < output_bits {
+        return Err(RandomizeError::InsufficientData {
+            required_bits: output_bits,
+            available_bits: bits.len(),
+        });
+    }
+    Ok(bits.iter().by_vals().collect())
+}
+
+/// [`extract_random_bits_exact`] packed into `output_bits.div_ceil(8)` bytes,
+/// most significant bit first. The unused low bits of a final partial byte
+/// are zero, so e.g. 521 bits end in a byte of the form `b0000000`.
+pub fn extract_random_bits_exact_as_bytes(
+    samples: &[f32],
+    num_lsb: u32,
+    output_bits: usize,
+) -> Result<Vec<u8>, RandomizeError> {
+    let bits = extract_random_bits_exact(samples, num_lsb, output_bits)?;
+    let mut packed: BitVec<u8, Msb0> = bits.into_iter().collect();
+    packed.set_uninitialized(false);
+    Ok(packed.into_vec())
+}
+
+/// Like [`extract_random_bits`], but with the bits of each unit spread
+/// over separate planes: first the highest of the `num_lsb` bits of every
+/// unit, then the next, down to the lowest. Adjacent output bits then come
+/// from neighbouring units instead of the same one, which breaks up
+/// correlation between the bits of a sample.
+///
+/// Strategies with one bit per unit have a single plane and are
+/// unchanged.
+pub fn extract_lsb_planes(
+    samples: &[f32],
+    num_lsb: u32,
+    output_bits: usize,
+    strategy: ExtractStrategy,
+) -> Result<BitVec<u8, Msb0>, RandomizeError> {
+    // Whole units, so that every plane has a bit from each.
+    let bits_per_unit = strategy.bits_per_unit(num_lsb).max(1) as usize;
+    let unit_bits = output_bits.div_ceil(bits_per_unit) * bits_per_unit;
+    let bits = extract_random_bits(samples, num_lsb, unit_bits, strategy)?;
+
+    let units = bits.len() / bits_per_unit;
+    let mut planes: BitVec<u8, Msb0> = (0..bits_per_unit)
+        .flat_map(|plane| (0..units).map(move |unit| unit * bits_per_unit + plane))
+        .map(|bit| bits[bit])
+        .collect();
+    planes.truncate(output_bits);
+    Ok(planes)
+}
+
+/// `N` bytes from adjacent-sample differences as an array, e.g. a
+/// `[u8; 32]` seed, without debiasing or whitening.
+///
+/// Like [`extract_random_bits_exact`], a recording too short to fill all
+/// `N` bytes is an error rather than a shorter result.
+pub fn extract_fixed<const N: usize>(
+    samples: &[f32],
+    num_lsb: u32,
+) -> Result<[u8; N], RandomizeError> {
+    let bits = extract_random_bits(samples, num_lsb, N * 8, ExtractStrategy::default())?;
+    if bits.len() < N * 8 {
+        return Err(RandomizeError::InsufficientData {
+            required_bits: N * 8,
+            available_bits: bits.len(),
+        });
+    }
+    let mut bytes = [0; N];
+    bytes.copy_from_slice(bits.as_raw_slice());
+    Ok(bytes)
+}
+
+// Units extracted per rayon task; smaller chunks cost more in scheduling
+// than the extraction saves.
+#[cfg(feature = "parallel")]
+const PARALLEL_CHUNK_UNITS: usize = 16 * 1024;
+
+// Start samples of the units read for `units_needed` units, spread evenly
+// over the recording, and the distance between them.
+fn unit_starts(
+    strategy: ExtractStrategy,
+    num_samples: usize,
+    units_needed: usize,
+) -> (impl Iterator<Item = usize>, usize) {
+    // Short recordings can't spread the units over the samples;
+    // use every unit and return fewer bits instead.
+    let available_units = strategy.available_units(num_samples);
+    let units_per_step = (available_units / units_needed.max(1)).max(1);
+    let units = (0..available_units)
+        .step_by(units_per_step)
+        .take(units_needed);
+    (units, units_per_step)
+}
+
+/// One output byte of [`annotate_random_data`] and the differences its bits
+/// came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnotatedByte {
+    pub value: u8,
+    /// In bit order. A difference whose bits straddle a byte boundary is
+    /// listed under both bytes.
+    pub sources: Vec<ByteSource>,
+}
+
+/// A sample difference contributing bits to an [`AnnotatedByte`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ByteSource {
+    /// Index of the first sample of the difference.
+    pub sample_index: usize,
+    /// `samples[sample_index + 1] - samples[sample_index]`.
+    pub difference: f32,
+    /// The low `num_lsb` bits of the difference's encoding.
+    pub lsb: u32,
+}
+
+/// Like [`extract_random_data`] with the default strategy and no debiasing
+/// or whitening, but pairs each byte with where it came from. Long runs of
+/// identical differences, as a silent input produces, stand out here. Much
+/// slower than plain extraction; meant for diagnosis.
+pub fn annotate_random_data(
+    samples: &[f32],
+    num_lsb: u32,
+    output_length: usize,
+) -> Result<Vec<AnnotatedByte>, RandomizeError> {
+    let strategy = ExtractStrategy::LsbDifference;
+    let values = extract_random_bits(samples, num_lsb, output_length * 8, strategy)?;
+    let values = whole_bytes(values).into_vec();
+
+    let mask = lsb_mask(num_lsb);
+    let num_lsb = num_lsb as usize;
+    let units_needed = (output_length * 8).div_ceil(num_lsb);
+    let (units, _) = unit_starts(strategy, samples.len(), units_needed);
+    let sources: Vec<ByteSource> = units
+        .map(|unit| {
+            let difference = samples[unit + 1] - samples[unit];
+            ByteSource {
+                sample_index: unit,
+                difference,
+                lsb: difference.to_bits() & mask,
+            }
+        })
+        .collect();
+
+    Ok(values
+        .into_iter()
+        .enumerate()
+        .map(|(byte, value)| AnnotatedByte {
+            value,
+            sources: sources[byte * 8 / num_lsb..=(byte * 8 + 7) / num_lsb].to_vec(),
+        })
+        .collect())
+}
+
+/// Like [`extract_random_data`] with the settings in `params`, which can
+/// also pick any [`Debiaser`] and take the bits plane by plane. A debiaser
+/// extracts as many more raw bits as it is expected to discard.
+pub fn extract_random_data_with(
+    samples: &[f32],
+    params: ExtractParams,
+) -> Result<Vec<u8>, RandomizeError> {
+    let ExtractParams {
+        num_lsb,
+        output_length,
+        strategy,
+        debiaser,
+        lsb_planes,
+        whiten,
+    } = params;
+    let extract = if lsb_planes {
+        extract_lsb_planes
+    } else {
+        extract_random_bits
+    };
+    let random_data = if debiaser == Debiaser::None {
+        whole_bytes(extract(samples, num_lsb, output_length * 8, strategy)?).into_vec()
+    } else {
+        let raw = extract(
+            samples,
+            num_lsb,
+            output_length * debiaser.expansion() * 8,
+            strategy,
+        )?;
+        let raw: Vec<bool> = whole_bytes(raw).iter().by_vals().collect();
+        let mut debiased = bits_to_bytes(&debiaser.debias(&raw));
+        debiased.truncate(output_length);
+        debiased
+    };
+
+    if whiten {
+        Ok(whiten_sha256(&random_data, output_length))
+    } else {
+        Ok(random_data)
+    }
+}
+
+/// Extracts `output_length` bytes from the recording using `strategy`,
+/// by default from the LSBs of adjacent-sample differences.
+///
+/// The low `num_lsb` bits of each value's float encoding are packed
+/// densely, so e.g. `num_lsb = 3` contributes 3 bits per difference. A
+/// `num_lsb` outside 1 to 32 is rejected.
+///
+/// With `debias` set, the raw bytes are run through [`von_neumann_debias`].
+/// That discards roughly three quarters of the bits, so about four times as
+/// many samples are consumed, and fewer than `output_length` bytes are
+/// returned if the recording runs out before enough bits survive.
+///
+/// A recording with too few samples to supply `output_length * 8` bits
+/// yields fewer bytes than requested.
+///
+/// With `whiten` set, the extracted bytes are conditioned by [`whiten_sha256`]
+/// as a final step.
+pub fn extract_random_data(
+    samples: &[f32],
+    num_lsb: u32,
+    output_length: usize,
+    strategy: ExtractStrategy,
+    debias: bool,
+    whiten: bool,
+) -> Result<Vec<u8>, RandomizeError> {
+    let debiaser = if debias {
+        Debiaser::VonNeumann
+    } else {
+        Debiaser::None
+    };
+    extract_random_data_with(
+        samples,
+        ExtractParams {
+            strategy,
+            debiaser,
+            whiten,
+            ..ExtractParams::new(num_lsb, output_length)
+        },
+    )
+}
+
+/// Mono recording time [`extract_random_data`] needs for `output_length`
+/// bytes at `sample_rate` with the default strategy, counting the fourfold
+/// cost of `debias` on unbiased input. Biased input loses more bits to
+/// debiasing than this allows for. Panics if `sample_rate` is 0.
+pub fn estimate_duration_for_bytes(
+    output_length: usize,
+    num_lsb: u32,
+    sample_rate: u32,
+    debias: bool,
+) -> Duration {
+    let debiaser = if debias {
+        Debiaser::VonNeumann
+    } else {
+        Debiaser::None
+    };
+    estimate_duration_with(output_length, num_lsb, sample_rate, debiaser)
+}
+
+// `estimate_duration_for_bytes` for any debiaser.
+pub(crate) fn estimate_duration_with(
+    output_length: usize,
+    num_lsb: u32,
+    sample_rate: u32,
+    debiaser: Debiaser,
+) -> Duration {
+    let bits = (output_length * 8 * debiaser.expansion()) as u64;
+    if bits == 0 {
+        return Duration::ZERO;
+    }
+    // One difference per `num_lsb` bits, plus the sample the first
+    // difference starts from.
+    let samples = bits.div_ceil(num_lsb.clamp(1, 32) as u64) + 1;
+    // Half a sample period over, so truncating the duration back to samples
+    // can't come up one short.
+    let nanos = (2 * samples + 1) as u128 * 1_000_000_000;
+    Duration::from_nanos(nanos.div_ceil(2 * sample_rate as u128) as u64)
+}
+
+/// Window size [`PhaseParams::default`] transforms.
+pub const DEFAULT_PHASE_WINDOW: usize = 1024;
+
+/// Settings for [`extract_phase_data`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhaseParams {
+    /// Samples per FFT window; windows don't overlap.
+    pub window_size: usize,
+    /// Bins whose phase is read, between 1 and `window_size / 2`
+    /// (exclusive); DC, Nyquist and the mirrored half carry no independent
+    /// phase.
+    pub bins: std::ops::Range<usize>,
+    /// Bits each phase is quantized to, 1 to 16.
+    pub bits_per_bin: u32,
+}
+
+impl PhaseParams {
+    /// The middle half of the spectrum, bins `window_size / 8` to
+    /// `3 * window_size / 8`, at 2 bits per bin.
+    pub fn new(window_size: usize) -> Self {
+        PhaseParams {
+            window_size,
+            bins: window_size / 8..window_size * 3 / 8,
+            bits_per_bin: 2,
+        }
+    }
+
+    fn validate(&self) -> Result<(), RandomizeError> {
+        let valid_bins = 1..self.window_size / 2;
+        if self.bins.is_empty()
+            || !valid_bins.contains(&self.bins.start)
+            || self.bins.end > valid_bins.end
+        {
+            return Err(RandomizeError::InvalidParameter(format!(
+                "phase bins {:?} must be a non-empty range within {:?}",
+                self.bins, valid_bins
+            )));
+        }
+        if !(1..=16).contains(&self.bits_per_bin) {
+            return Err(RandomizeError::InvalidParameter(format!(
+                "{} bits per phase bin is outside 1 to 16",
+                self.bits_per_bin
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Default for PhaseParams {
+    fn default() -> Self {
+        PhaseParams::new(DEFAULT_PHASE_WINDOW)
+    }
+}
+
+/// Extracts `output_length` bytes from the phase spectrum instead of the
+/// sample LSBs.
+///
+/// Each window of `params.window_size` samples is transformed, and the
+/// phase of every bin in `params.bins` is quantized into
+/// `params.bits_per_bin` bits, most significant first: a phase of -π maps
+/// to 0, just below π to all ones. The phase of noise is uniform whatever
+/// its level, so this still works when a tone dominates the amplitude, as
+/// long as the tone's own bins are left out. A recording too short to
+/// supply `output_length` bytes yields fewer.
+pub fn extract_phase_data(
+    samples: &[f32],
+    params: &PhaseParams,
+    output_length: usize,
+) -> Result<Vec<u8>, RandomizeError> {
+    params.validate()?;
+    let levels = (1u32 << params.bits_per_bin) as f64;
+    extract_spectral_bits(
+        samples,
+        params.window_size,
+        output_length,
+        |spectrum, bits| {
+            for bin in &spectrum[params.bins.clone()] {
+                let turn = (bin.arg() + std::f64::consts::PI) / std::f64::consts::TAU;
+                let level = ((turn * levels) as u32).min(levels as u32 - 1);
+                for bit in (0..params.bits_per_bin).rev() {
+                    bits.push((level >> bit) & 1 == 1);
+                }
+            }
+        },
+    )
+}
+
+/// Window size [`MagnitudeParams::default`] transforms.
+pub const DEFAULT_MAGNITUDE_WINDOW: usize = 1024;
+
+/// Settings for [`extract_magnitude_data`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MagnitudeParams {
+    /// Samples per FFT window; windows don't overlap.
+    pub window_size: usize,
+    /// Bins whose magnitude is read, between 1 and `window_size / 2`
+    /// (inclusive); DC holds the offset and the mirrored half repeats the
+    /// rest.
+    pub bins: std::ops::Range<usize>,
+    /// Low bits taken from each magnitude's `f32` encoding, 1 to 23, so
+    /// that none come from the exponent.
+    pub lsb_per_bin: u32,
+}
+
+impl MagnitudeParams {
+    /// The middle half of the spectrum, bins `window_size / 8` to
+    /// `3 * window_size / 8`, at 4 LSBs per bin.
+    pub fn new(window_size: usize) -> Self {
+        MagnitudeParams {
+            window_size,
+            bins: window_size / 8..window_size * 3 / 8,
+            lsb_per_bin: 4,
+        }
+    }
+
+    fn validate(&self) -> Result<(), RandomizeError> {
+        let valid_bins = 1..self.window_size / 2 + 1;
+        if self.bins.is_empty()
+            || !valid_bins.contains(&self.bins.start)
+            || self.bins.end > valid_bins.end
+        {
+            return Err(RandomizeError::InvalidParameter(format!(
+                "magnitude bins {:?} must be a non-empty range within {:?}",
+                self.bins, valid_bins
+            )));
+        }
+        if !(1..=23).contains(&self.lsb_per_bin) {
+            return Err(RandomizeError::InvalidParameter(format!(
+                "{} LSBs per magnitude bin is outside 1 to 23",
+                self.lsb_per_bin
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Default for MagnitudeParams {
+    fn default() -> Self {
+        MagnitudeParams::new(DEFAULT_MAGNITUDE_WINDOW)
+    }
+}
+
+/// Extracts `output_length` bytes from the magnitude spectrum.
+///
+/// Each window of `params.window_size` samples is transformed, and the low
+/// `params.lsb_per_bin` bits of every magnitude in `params.bins`, as an
+/// `f32`, are packed most significant first. Over a thermal noise floor
+/// the magnitudes vary at random from bin to bin and window to window, so
+/// like [`extract_phase_data`] this still works when a tone dominates the
+/// waveform, as long as the tone's own bins are left out. A recording too
+/// short to supply `output_length` bytes yields fewer.
+pub fn extract_magnitude_data(
+    samples: &[f32],
+    params: &MagnitudeParams,
+    output_length: usize,
+) -> Result<Vec<u8>, RandomizeError> {
+    params.validate()?;
+    extract_spectral_bits(
+        samples,
+        params.window_size,
+        output_length,
+        |spectrum, bits| {
+            for bin in &spectrum[params.bins.clone()] {
+                let magnitude = (bin.norm() as f32).to_bits();
+                for bit in (0..params.lsb_per_bin).rev() {
+                    bits.push((magnitude >> bit) & 1 == 1);
+                }
+            }
+        },
+    )
+}
+
+// Transforms each whole window of `window_size` samples and lets `read`
+// append bits from its spectrum until there are enough for `output_length`
+// bytes.
+fn extract_spectral_bits(
+    samples: &[f32],
+    window_size: usize,
+    output_length: usize,
+    mut read: impl FnMut(&[Complex<f64>], &mut BitVec<u8, Msb0>),
+) -> Result<Vec<u8>, RandomizeError> {
+    if samples.len() < window_size {
+        return Err(RandomizeError::InsufficientSamples {
+            required: window_size,
+            available: samples.len(),
+        });
+    }
+
+    let output_bits = output_length * 8;
+    let mut bits: BitVec<u8, Msb0> = BitVec::with_capacity(output_bits);
+    let mut spectrum = vec![Complex::new(0.0f64, 0.0); window_size];
+    for window in samples.chunks_exact(window_size) {
+        if bits.len() >= output_bits {
+            break;
+        }
+        for (bin, &sample) in spectrum.iter_mut().zip(window) {
+            *bin = Complex::new(sample as f64, 0.0);
+        }
+        forward_fft(&mut spectrum);
+        read(&spectrum, &mut bits);
+    }
+    bits.truncate(output_bits);
+    Ok(whole_bytes(bits).into_vec())
+}
+
+/// Settings for [`extract_random_data_with`] and [`process_samples`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtractParams {
+    pub num_lsb: u32,
+    pub output_length: usize,
+    pub strategy: ExtractStrategy,
+    pub debiaser: Debiaser,
+    /// Takes the bits one LSB plane at a time, see [`extract_lsb_planes`],
+    /// instead of sample by sample.
+    pub lsb_planes: bool,
+    pub whiten: bool,
+}
+
+impl ExtractParams {
+    /// The default strategy in sample order, without debiasing or
+    /// whitening.
+    pub fn new(num_lsb: u32, output_length: usize) -> Self {
+        ExtractParams {
+            num_lsb,
+            output_length,
+            strategy: ExtractStrategy::default(),
+            debiaser: Debiaser::None,
+            lsb_planes: false,
+            whiten: false,
+        }
+    }
+}
+
+/// Removes the DC offset, normalizes to a peak of 1 and extracts, the
+/// pipeline the crate runs over its own recordings, for samples captured
+/// anywhere else.
+pub fn process_samples(samples: &[f32], params: ExtractParams) -> Result<Vec<u8>, RandomizeError> {
+    if samples.is_empty() {
+        return Err(RandomizeError::EmptyRecording);
+    }
+    let mut samples = samples.to_vec();
+    center_and_normalize(&mut samples, 1.0);
+    extract_random_data_with(&samples, params)
+}
+
+// Drops a trailing partial byte.
+fn whole_bytes(mut bits: BitVec<u8, Msb0>) -> BitVec<u8, Msb0> {
+    bits.truncate(bits.len() / 8 * 8);
+    bits
+}
+
+/// Concatenates the low `num_lsb` bits of each value (most significant first)
+/// and packs them densely into bytes. A trailing partial byte is dropped.
+pub fn pack_lsbs(values: &[u32], num_lsb: u32) -> Vec<u8> {
+    whole_bytes(pack_lsb_bits(values, num_lsb)).into_vec()
+}
+
+fn pack_lsb_bits(values: &[u32], num_lsb: u32) -> BitVec<u8, Msb0> {
+    let num_lsb = num_lsb.min(32) as usize;
+    let mut bits = BitVec::repeat(false, values.len() * num_lsb);
+    if num_lsb > 0 {
+        for (chunk, &value) in bits.chunks_exact_mut(num_lsb).zip(values) {
+            chunk.store_be(value);
+        }
+    }
+    bits
+}
+
+/// Byte order for serializing multi-byte values.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Endianness {
+    #[default]
+    Little,
+    Big,
+}
+
+impl Endianness {
+    fn read_f32(self, bytes: &[u8]) -> f32 {
+        match self {
+            Endianness::Little => LittleEndian::read_f32(bytes),
+            Endianness::Big => BigEndian::read_f32(bytes),
+        }
+    }
+
+    fn write_f32(self, buffer: &mut [u8], value: f32) {
+        match self {
+            Endianness::Little => LittleEndian::write_f32(buffer, value),
+            Endianness::Big => BigEndian::write_f32(buffer, value),
+        }
+    }
+}
+
+/// Serializes samples as little-endian `f32` bytes.
+pub fn f32_to_u8(data: &[f32]) -> Vec<u8> {
+    f32_to_u8_with(data, Endianness::Little)
+}
+
+/// Serializes samples as `f32` bytes in the given byte order.
+pub fn f32_to_u8_with(data: &[f32], endianness: Endianness) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(data.len() * 4);
+    for value in data {
+        let mut buffer = [0u8; 4];
+        endianness.write_f32(&mut buffer, *value);
+        bytes.extend_from_slice(&buffer);
+    }
+    bytes
+}
+
+/// Inverse of [`f32_to_u8`]. A trailing partial value is dropped.
+pub fn u8_to_f32(bytes: &[u8]) -> Vec<f32> {
+    u8_to_f32_with(bytes, Endianness::Little)
+}
+
+/// Inverse of [`f32_to_u8_with`]. A trailing partial value is dropped.
+pub fn u8_to_f32_with(bytes: &[u8], endianness: Endianness) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| endianness.read_f32(chunk))
+        .collect()
+}
+
+/// Debiases a bit stream pairwise: `01` becomes 0, `10` becomes 1, and `00`/`11` are dropped.
+pub fn von_neumann_debias(bits: &[bool]) -> Vec<bool> {
+    bits.chunks_exact(2)
+        .filter(|pair| pair[0] != pair[1])
+        .map(|pair| pair[0])
+        .collect()
+}
+
+/// Peres's iterated Von Neumann extractor: the bits of
+/// [`von_neumann_debias`], then the same recursively applied to the XOR of
+/// each pair and to the common bit of each equal pair, which plain Von
+/// Neumann discards.
+pub fn iterated_von_neumann_debias(bits: &[bool]) -> Vec<bool> {
+    let mut output = Vec::with_capacity(bits.len());
+    peres(bits, &mut output);
+    output
+}
+
+fn peres(bits: &[bool], output: &mut Vec<bool>) {
+    if bits.len() < 2 {
+        return;
+    }
+    let pairs = bits.chunks_exact(2);
+    output.extend(
+        pairs
+            .clone()
+            .filter(|pair| pair[0] != pair[1])
+            .map(|pair| pair[0]),
+    );
+    let xors: Vec<bool> = pairs.clone().map(|pair| pair[0] ^ pair[1]).collect();
+    let equal: Vec<bool> = pairs
+        .filter(|pair| pair[0] == pair[1])
+        .map(|pair| pair[0])
+        .collect();
+    peres(&xors, output);
+    peres(&equal, output);
+}
+
+/// Elias's extractor over blocks of [`ELIAS_BLOCK_BITS`]: independent bits
+/// make each arrangement of a block's ones equally likely, so the block's
+/// rank among them is uniform. The rank is turned into bits by splitting
+/// the `C(n, k)` arrangements into power-of-two ranges, largest first, and
+/// emitting the position within the range it falls in. A trailing partial
+/// block is dropped.
+pub fn elias_debias(bits: &[bool]) -> Vec<bool> {
+    let n = ELIAS_BLOCK_BITS;
+    // Pascal's triangle up to n.
+    let mut binomial = vec![vec![0u64; n + 1]; n + 1];
+    for row in 0..=n {
+        binomial[row][0] = 1;
+        for k in 1..=row {
+            binomial[row][k] = binomial[row - 1][k - 1] + binomial[row - 1][k];
+        }
+    }
+
+    let mut output = Vec::with_capacity(bits.len());
+    for block in bits.chunks_exact(n) {
+        let mut ones = block.iter().filter(|&&bit| bit).count();
+        let mut arrangements = binomial[n][ones];
+        // Lexicographic rank among the blocks with as many ones.
+        let mut rank = 0;
+        for (i, &bit) in block.iter().enumerate() {
+            if bit {
+                rank += binomial[n - i - 1][ones];
+                ones -= 1;
+            }
+        }
+        while arrangements > 0 {
+            let width = arrangements.ilog2();
+            if rank < 1 << width {
+                output.extend((0..width).rev().map(|shift| rank >> shift & 1 == 1));
+                break;
+            }
+            rank -= 1 << width;
+            arrangements -= 1 << width;
+        }
+    }
+    output
+}
+
+/// Unpacks bytes into bits, most significant bit first.
+pub fn bytes_to_bits(bytes: &[u8]) -> Vec<bool> {
+    bytes.view_bits::<Msb0>().iter().by_vals().collect()
+}
+
+/// Packs bits into bytes, most significant bit first. A trailing partial byte is dropped.
+pub fn bits_to_bytes(bits: &[bool]) -> Vec<u8> {
+    let whole: BitVec<u8, Msb0> = bits.iter().collect();
+    whole_bytes(whole).into_vec()
+}
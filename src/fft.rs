@@ -0,0 +1,17 @@
+use num_complex::Complex;
+use rustfft::{FftNum, FftPlanner};
+
+/// In-place forward FFT of the whole buffer.
+pub(crate) fn forward_fft<T: FftNum>(buffer: &mut [Complex<T>]) {
+    FftPlanner::new()
+        .plan_fft_forward(buffer.len())
+        .process(buffer);
+}
+
+/// In-place inverse FFT of the whole buffer. Like rustfft, the result is not
+/// divided by the length.
+pub(crate) fn inverse_fft<T: FftNum>(buffer: &mut [Complex<T>]) {
+    FftPlanner::new()
+        .plan_fft_inverse(buffer.len())
+        .process(buffer);
+}
@@ -0,0 +1,69 @@
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+use crate::conditioning::whiten_sha256;
+use crate::entropy::entropy_capped_length;
+use crate::error::RandomizeError;
+use crate::pipeline::record_random_data;
+use crate::secure::Sensitive;
+
+const RECORDING_DURATION_MILLIS: u64 = 500;
+const NUM_LSB: u32 = 8;
+// Raw bytes extracted per conditioned output byte.
+const RAW_BYTES_PER_OUTPUT_BYTE: usize = 2;
+const MIN_RAW_BYTES: usize = 64;
+// Recordings after which a call gives up, about 8 seconds in all.
+const MAX_RECORDINGS: usize = 16;
+
+const NO_INPUT_DEVICE: u32 = getrandom::Error::CUSTOM_START;
+const STREAM_FAILURE: u32 = getrandom::Error::CUSTOM_START + 1;
+const EXTRACTION_FAILURE: u32 = getrandom::Error::CUSTOM_START + 2;
+
+/// Fills `buf` with SHA-256 conditioned audio entropy, for use with
+/// `getrandom::register_custom_getrandom!(audio_getrandom)`.
+///
+/// Every call blocks while it records half a second of audio at a time,
+/// until there are two raw bytes per output byte and their min-entropy
+/// justifies all of `buf`. Small requests need one recording; after 16,
+/// about 8 seconds, the call fails, as it does for a muted microphone. This
+/// suits seeding a CSPRNG at start-up rather than serving frequent or large
+/// requests. Failures are reported as custom `getrandom` error codes.
+pub fn audio_getrandom(buf: &mut [u8]) -> Result<(), getrandom::Error> {
+    if buf.is_empty() {
+        return Ok(());
+    }
+
+    let raw_length = (buf.len() * RAW_BYTES_PER_OUTPUT_BYTE).max(MIN_RAW_BYTES);
+    let mut raw = Sensitive::new(Vec::new());
+    for _ in 0..MAX_RECORDINGS {
+        let recorded = Sensitive::new(
+            record_random_data(
+                Duration::from_millis(RECORDING_DURATION_MILLIS),
+                NUM_LSB,
+                raw_length,
+            )
+            .map_err(to_getrandom_error)?,
+        );
+        raw.extend_from_slice(&recorded);
+        if raw.len() >= raw_length && entropy_capped_length(&raw, buf.len()) == buf.len() {
+            buf.copy_from_slice(&whiten_sha256(&raw, buf.len()));
+            return Ok(());
+        }
+    }
+    Err(NonZeroU32::new(EXTRACTION_FAILURE).unwrap().into())
+}
+
+fn to_getrandom_error(err: RandomizeError) -> getrandom::Error {
+    let code = match err {
+        RandomizeError::NoInputDevice | RandomizeError::DeviceNotFound { .. } => NO_INPUT_DEVICE,
+        RandomizeError::Devices(_)
+        | RandomizeError::DefaultConfig(_)
+        | RandomizeError::SupportedConfigs(_)
+        | RandomizeError::StreamBuild(_)
+        | RandomizeError::StreamPlay(_)
+        | RandomizeError::StreamFailed(_)
+        | RandomizeError::EmptyRecording => STREAM_FAILURE,
+        _ => EXTRACTION_FAILURE,
+    };
+    NonZeroU32::new(code).unwrap().into()
+}
@@ -0,0 +1,191 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::entropy::min_entropy_mcv;
+use crate::error::RandomizeError;
+use crate::processing::clipping_ratio;
+use crate::statistical_tests::{
+    lag1_autocorrelation, monobit_result, runs_result, SIGNIFICANCE_LEVEL,
+};
+
+/// Bounds a freshly extracted batch must meet before it is handed out,
+/// modelled on the SP800-90B start-up and continuous health tests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthPolicy {
+    /// Lowest acceptable most-common-value estimate, in bits per byte.
+    pub min_entropy_per_byte: f64,
+    /// Longest acceptable run of one repeated byte value; a stuck ADC
+    /// produces long runs.
+    pub max_repetitions: usize,
+    /// Largest acceptable fraction of near-full-scale samples, see
+    /// [`clipping_ratio`].
+    pub max_clipping_ratio: f32,
+    /// Significance level for the monobit and runs tests, see
+    /// [`SIGNIFICANCE_LEVEL`].
+    pub significance_level: f64,
+    /// Most consecutive identical input buffers accepted; some virtual
+    /// devices replay one buffer, which needn't be silent.
+    pub max_repeated_buffers: usize,
+    /// Kurtosis deviation from 3 above which a recording gets an advisory
+    /// warning, see [`NoiseMetrics::looks_non_thermal`](crate::NoiseMetrics::looks_non_thermal).
+    /// Never rejects.
+    pub max_kurtosis_deviation: f64,
+    /// Largest acceptable absolute [lag-1 autocorrelation](lag1_autocorrelation)
+    /// of the extracted bits, or `None` not to check it. Correlation between
+    /// neighbouring samples is the main way audio entropy is overestimated.
+    pub max_autocorrelation: Option<f64>,
+}
+
+/// Clipping ratio above which a recording is reported as clipped.
+pub const DEFAULT_MAX_CLIPPING_RATIO: f32 = 0.01;
+
+/// Kurtosis deviation that flags a pure tone (1.5) but not uniform noise
+/// (1.8).
+pub const DEFAULT_MAX_KURTOSIS_DEVIATION: f64 = 1.25;
+
+/// A lag-1 autocorrelation limit. The estimate from `n` independent bits has
+/// a standard deviation of about `1 / sqrt(n)`, so batches of at least 10 000
+/// bits (1250 bytes) exceed it with probability below 10^-6; smaller batches
+/// need a looser limit.
+pub const DEFAULT_MAX_AUTOCORRELATION: f64 = 0.05;
+
+/// Run of identical input buffers above which a recording is reported as
+/// stuck.
+pub const DEFAULT_MAX_REPEATED_BUFFERS: usize = 3;
+
+impl HealthPolicy {
+    /// Rejects recordings with too many clipped samples.
+    pub fn check_clipping(&self, samples: &[f32]) -> Result<(), RandomizeError> {
+        if clipping_ratio(samples) > self.max_clipping_ratio {
+            Err(RandomizeError::HealthCheckFailed {
+                failing_tests: vec!["clipping".to_string()],
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Rejects recordings in which the same buffer arrived more than
+    /// `max_repeated_buffers` times in a row. `buffers` are the lengths
+    /// `samples` is split into, see [`Audio::buffers`](crate::Audio::buffers).
+    pub fn check_repeated_buffers(
+        &self,
+        samples: &[f32],
+        buffers: &[usize],
+    ) -> Result<(), RandomizeError> {
+        let repetitions = longest_repeated_buffer_run(samples, buffers);
+        if repetitions > self.max_repeated_buffers {
+            Err(RandomizeError::RepeatedBuffer { repetitions })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Rejects batches whose bits are correlated with their neighbours by
+    /// more than `max_autocorrelation`.
+    pub fn check_autocorrelation(&self, data: &[u8]) -> Result<(), RandomizeError> {
+        match self.max_autocorrelation {
+            Some(max_autocorrelation) => check_autocorrelation(data, max_autocorrelation),
+            None => Ok(()),
+        }
+    }
+
+    /// Runs every check and reports all that failed. Too high an
+    /// autocorrelation is reported on its own, as
+    /// [`RandomizeError::AutocorrelationTooHigh`].
+    pub fn check(&self, data: &[u8]) -> Result<(), RandomizeError> {
+        self.check_autocorrelation(data)?;
+        let mut failing_tests = Vec::new();
+
+        if !monobit_result(data).passes(self.significance_level) {
+            failing_tests.push("monobit".to_string());
+        }
+        if !runs_result(data).is_ok_and(|result| result.passes(self.significance_level)) {
+            failing_tests.push("runs".to_string());
+        }
+        if min_entropy_mcv(data) < self.min_entropy_per_byte {
+            failing_tests.push("min_entropy".to_string());
+        }
+        if longest_repetition(data) > self.max_repetitions {
+            failing_tests.push("repetition_count".to_string());
+        }
+
+        if failing_tests.is_empty() {
+            Ok(())
+        } else {
+            Err(RandomizeError::HealthCheckFailed { failing_tests })
+        }
+    }
+}
+
+impl Default for HealthPolicy {
+    fn default() -> Self {
+        HealthPolicy {
+            min_entropy_per_byte: 1.0,
+            // SP800-90B repetition count cutoff 1 + ceil(20 / H) for H = 1 bit.
+            max_repetitions: 21,
+            max_clipping_ratio: DEFAULT_MAX_CLIPPING_RATIO,
+            significance_level: SIGNIFICANCE_LEVEL,
+            max_repeated_buffers: DEFAULT_MAX_REPEATED_BUFFERS,
+            max_kurtosis_deviation: DEFAULT_MAX_KURTOSIS_DEVIATION,
+            max_autocorrelation: None,
+        }
+    }
+}
+
+pub(crate) fn check_autocorrelation(
+    data: &[u8],
+    max_autocorrelation: f64,
+) -> Result<(), RandomizeError> {
+    let measured = lag1_autocorrelation(data);
+    if measured.abs() > max_autocorrelation {
+        Err(RandomizeError::AutocorrelationTooHigh { measured })
+    } else {
+        Ok(())
+    }
+}
+
+/// Longest run of consecutive buffers with identical contents.
+pub(crate) fn longest_repeated_buffer_run(samples: &[f32], buffers: &[usize]) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    let mut previous = None;
+    let mut start = 0;
+    for &length in buffers {
+        let end = (start + length).min(samples.len());
+        let buffer = &samples[start..end];
+        start = end;
+
+        let mut hasher = DefaultHasher::new();
+        buffer.len().hash(&mut hasher);
+        for sample in buffer {
+            sample.to_bits().hash(&mut hasher);
+        }
+        let hash = hasher.finish();
+
+        current = if previous == Some(hash) {
+            current + 1
+        } else {
+            1
+        };
+        longest = longest.max(current);
+        previous = Some(hash);
+    }
+    longest
+}
+
+fn longest_repetition(data: &[u8]) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    let mut previous = None;
+    for &byte in data {
+        current = if previous == Some(byte) {
+            current + 1
+        } else {
+            1
+        };
+        longest = longest.max(current);
+        previous = Some(byte);
+    }
+    longest
+}
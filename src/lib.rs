@@ -0,0 +1,136 @@
+mod batch;
+#[cfg(feature = "microphone")]
+mod collector;
+mod conditioning;
+mod drbg;
+mod entropy;
+mod error;
+mod extraction;
+mod fft;
+#[cfg(feature = "getrandom-backend")]
+mod getrandom_backend;
+mod health;
+#[cfg(feature = "microphone")]
+mod live;
+mod monitor;
+mod output;
+mod pcm;
+#[cfg(feature = "microphone")]
+mod pipeline;
+mod pool;
+mod processing;
+#[cfg(feature = "microphone")]
+mod recording;
+#[cfg(feature = "microphone")]
+mod rng;
+mod secure;
+mod self_test;
+mod source;
+mod statistical_tests;
+pub mod stats;
+#[cfg(feature = "microphone")]
+mod stream;
+mod supervisor;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod wav;
+
+pub use batch::EntropyBatch;
+/// The bit-vector crate used by [`extract_random_bits`] and the bit-level tests.
+pub use bitvec;
+#[cfg(feature = "microphone")]
+pub use collector::{
+    CaptureStats, CollectionStages, EntropyCollector, MAX_SEED_ROUNDS, MIN_CAPTURE_RATIO,
+};
+#[cfg(feature = "blake3")]
+pub use conditioning::Blake3Conditioner;
+#[cfg(feature = "sha3")]
+pub use conditioning::Sha3Conditioner;
+pub use conditioning::{
+    toeplitz_condition, toeplitz_extract, toeplitz_output_bits, whiten_sha256, xor_fold,
+    Conditioner, IncrementalConditioner, Sha256Conditioner, ToeplitzConditioner,
+};
+/// The audio I/O crate, for naming the types of [`list_supported_configs`].
+#[cfg(feature = "microphone")]
+pub use cpal;
+pub use drbg::{HmacDrbg, DRBG_MAX_BYTES_PER_REQUEST, DRBG_SECURITY_STRENGTH};
+pub use entropy::{
+    bias_report, bit_position_bias, byte_histogram, entropy_capped_length, extraction_efficiency,
+    min_entropy_mcv, shannon_entropy, BiasReport, BiasSummary,
+};
+pub use error::RandomizeError;
+pub use extraction::{
+    annotate_random_data, auto_select_lsb, bits_to_bytes, bytes_to_bits, elias_debias,
+    estimate_duration_for_bytes, extract_fixed, extract_lsb_planes, extract_magnitude_data,
+    extract_phase_data, extract_random_bits, extract_random_bits_exact,
+    extract_random_bits_exact_as_bytes, extract_random_data, extract_random_data_with, f32_to_u8,
+    f32_to_u8_with, iterated_von_neumann_debias, pack_lsbs, process_samples, u8_to_f32,
+    u8_to_f32_with, von_neumann_debias, AnnotatedByte, ByteSource, Debiaser, Endianness,
+    ExtractParams, ExtractStrategy, MagnitudeParams, PhaseParams, DEFAULT_MAGNITUDE_WINDOW,
+    DEFAULT_PHASE_WINDOW, ELIAS_BLOCK_BITS, MIN_ENTROPY_PER_BIT,
+};
+#[cfg(feature = "getrandom-backend")]
+pub use getrandom_backend::audio_getrandom;
+pub use health::{
+    HealthPolicy, DEFAULT_MAX_AUTOCORRELATION, DEFAULT_MAX_CLIPPING_RATIO,
+    DEFAULT_MAX_KURTOSIS_DEVIATION, DEFAULT_MAX_REPEATED_BUFFERS,
+};
+#[cfg(feature = "microphone")]
+pub use live::LiveRecording;
+pub use monitor::{BiasMeter, StreamingMonitor};
+#[cfg(feature = "json")]
+pub use output::report_json;
+pub use output::{
+    encode_base64, encode_hex, print_random_data_as_hex, read_framed, write_formatted, write_raw,
+    OutputFormat, ReportFormat,
+};
+pub use pcm::{extract_from_raw_pcm, read_raw_pcm, PcmFormat, PcmSampleType};
+#[cfg(feature = "tokio")]
+pub use pipeline::collect_async;
+#[cfg(feature = "microphone")]
+pub use pipeline::{collect_multi, collect_xor_folded, fold_into_seed, record_random_data};
+pub use pool::{EntropyPool, DEFAULT_RESEED_THRESHOLD_BITS, SEED_FILE_BYTES};
+pub use processing::{
+    average_spectrum, center_and_normalize, channel_cross_correlation, channel_difference,
+    clipping_ratio, decorrelate, deinterleave, detect_agc_drift, downmix, high_pass_filter,
+    i16_to_f32, magnitude_spectrum, noise_shape_metrics, normalize_audio, normalize_audio_to,
+    remove_dc_offset, sliding_normalize, spectral_whiten, u16_to_f32, window_rms, AgcDrift,
+    NoiseMetrics, NormalizeMode, AGC_DRIFT_RATIO, DECORRELATION_ORDER, DEFAULT_AGC_WINDOW,
+    DEFAULT_HIGH_PASS_CUTOFF_HZ, DEFAULT_SPECTRAL_WHITEN_WINDOW, MAX_CHANNEL_CORRELATION,
+};
+#[cfg(feature = "microphone")]
+pub use recording::{
+    list_input_devices, list_supported_configs, record_samples, DEFAULT_WARMUP,
+    RECORD_DURATION_SECS, SAMPLE_RATE,
+};
+#[cfg(feature = "microphone")]
+pub use rng::{
+    audio_rng, random_f64_unit, random_u32, random_u32_with, random_u64, random_u64_with,
+    seed_chacha20, seed_std_rng, AudioRng, ThreadAudioRng,
+};
+pub use secure::secure_compare;
+pub use self_test::{self_test, SelfTestResult};
+pub use source::{Audio, Jitter, SampleSource, Synthetic};
+#[cfg(feature = "microphone")]
+pub use source::{CaptureConfig, Microphone};
+pub use statistical_tests::{
+    approximate_entropy_test, autocorrelation_test, berlekamp_massey, block_frequency_test,
+    byte_rank_correlation, chi_square_byte_test, coverage_test, cumulative_sums_test,
+    fips_140_2_suite, fips_long_run, fips_monobit, gf2_rank, lag1_autocorrelation,
+    linear_complexity_test, longest_run_test, matrix_rank_test, maurer_universal_test,
+    monobit_p_value, monobit_result, monobit_test, non_overlapping_template_test,
+    non_overlapping_template_test_bits, overall_verdict, overall_verdict_at,
+    overlapping_template_test, poker_test, quality_score, random_excursions_test,
+    random_excursions_variant_test, run_all_tests, run_all_tests_at, run_length_distribution,
+    run_tests, runs_result, runs_test, serial_test, serial_test_bits, spectral_test,
+    BatteryResults, FipsResult, Recommendation, SkippedTest, TestKind, TestResult, Verdict,
+    APERIODIC_TEMPLATES_2, APERIODIC_TEMPLATES_3, APERIODIC_TEMPLATES_4, APERIODIC_TEMPLATES_5,
+    DEFAULT_APPROXIMATE_ENTROPY_BLOCK_LENGTH, OVERLAPPING_TEMPLATE_BLOCK_SIZE, RUN_LENGTH_BUCKETS,
+    SIGNIFICANCE_LEVEL, SUSPECT_P_VALUE,
+};
+#[cfg(feature = "microphone")]
+pub use stream::RandomByteStream;
+pub use supervisor::{RetestPolicy, Supervisor, SupervisorEvent};
+#[cfg(feature = "wasm")]
+pub use wasm::extract_from_buffer;
+pub use wav::{extract_from_wav, read_wav, read_wav_samples, write_recording_wav, write_wav};
@@ -0,0 +1,181 @@
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use ringbuf::traits::{Consumer, Observer, Producer, Split};
+use ringbuf::{HeapCons, HeapRb};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::error::RandomizeError;
+use crate::recording::{build_input_stream, with_usable_config};
+
+/// An input stream that keeps running in the background, buffering its
+/// samples in a lock-free single-producer ring buffer of fixed capacity, so
+/// the audio callback never waits on the reader.
+///
+/// The buffer holds `capacity` worth of audio; when the consumer falls
+/// behind, new samples are dropped until it catches up, and counted in
+/// [`dropped_callbacks`](Self::dropped_callbacks). It is allocated up front as
+/// `sample_rate * channels * capacity` interleaved `f32` samples, i.e. 4
+/// bytes per sample: ten seconds of 44.1 kHz stereo take about 3.5 MB.
+/// Recording stops when the value is dropped.
+///
+/// ```no_run
+/// use randomize7::LiveRecording;
+/// use std::time::Duration;
+///
+/// let live = LiveRecording::start(Duration::from_secs(10))?;
+/// let samples = live.pop_chunk(live.sample_rate() as usize)?;
+/// # Ok::<(), randomize7::RandomizeError>(())
+/// ```
+pub struct LiveRecording {
+    _stream: cpal::Stream,
+    samples: Mutex<HeapCons<f32>>,
+    error: Arc<Mutex<Option<cpal::StreamError>>>,
+    dropped_callbacks: Arc<AtomicUsize>,
+    capacity: usize,
+    sample_rate: u32,
+    channels: u16,
+}
+
+// Bounds on how long `pop_chunk` sleeps between checks of the ring.
+const MIN_POLL: Duration = Duration::from_millis(1);
+const MAX_POLL: Duration = Duration::from_millis(50);
+
+impl LiveRecording {
+    /// Starts recording from the default input device with its default config.
+    pub fn start(capacity: Duration) -> Result<Self, RandomizeError> {
+        let input_device = cpal::default_host()
+            .default_input_device()
+            .ok_or(RandomizeError::NoInputDevice)?;
+        let default_config = input_device
+            .default_input_config()
+            .map_err(RandomizeError::DefaultConfig);
+        with_usable_config(&input_device, default_config, |input_config| {
+            LiveRecording::start_on(
+                &input_device,
+                input_config,
+                cpal::BufferSize::Default,
+                capacity,
+            )
+        })
+        .map(|(live, _)| live)
+    }
+
+    pub(crate) fn start_on(
+        input_device: &cpal::Device,
+        input_config: &cpal::SupportedStreamConfig,
+        buffer_size: cpal::BufferSize,
+        capacity: Duration,
+    ) -> Result<Self, RandomizeError> {
+        let capacity = (input_config.sample_rate().0 as f64
+            * input_config.channels() as f64
+            * capacity.as_secs_f64()) as usize;
+        if capacity == 0 {
+            return Err(RandomizeError::InvalidParameter(
+                "ring buffer capacity must be at least one sample".to_string(),
+            ));
+        }
+
+        let (mut producer, consumer) = HeapRb::<f32>::new(capacity).split();
+        let error = Arc::new(Mutex::new(None));
+        let error_writer = error.clone();
+        let dropped_callbacks = Arc::new(AtomicUsize::new(0));
+        let dropped_writer = dropped_callbacks.clone();
+
+        let stream = build_input_stream(
+            input_device,
+            input_config,
+            buffer_size,
+            move |data: &[f32]| {
+                // Whatever doesn't fit is dropped; the callback never blocks.
+                if producer.push_slice(data) < data.len() {
+                    dropped_writer.fetch_add(1, Ordering::Relaxed);
+                }
+            },
+            move |err| {
+                *error_writer.lock().unwrap_or_else(|err| err.into_inner()) = Some(err);
+            },
+        )?;
+        stream.play().map_err(RandomizeError::StreamPlay)?;
+
+        Ok(LiveRecording {
+            _stream: stream,
+            samples: Mutex::new(consumer),
+            error,
+            dropped_callbacks,
+            capacity,
+            sample_rate: input_config.sample_rate().0,
+            channels: input_config.channels(),
+        })
+    }
+
+    /// Removes and returns the oldest `n` samples, blocking until that many
+    /// have been recorded. Fails if `n` exceeds the capacity or the stream
+    /// reported an error.
+    pub fn pop_chunk(&self, n: usize) -> Result<Vec<f32>, RandomizeError> {
+        if n > self.capacity {
+            return Err(RandomizeError::InvalidParameter(format!(
+                "chunk of {} samples exceeds the ring buffer capacity of {}",
+                n, self.capacity
+            )));
+        }
+
+        loop {
+            if let Some(err) = self
+                .error
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .take()
+            {
+                return Err(RandomizeError::StreamFailed(err));
+            }
+            let missing = {
+                let mut samples = self.consumer();
+                let available = samples.occupied_len();
+                if available >= n {
+                    let mut chunk = vec![0.0; n];
+                    samples.pop_slice(&mut chunk);
+                    return Ok(chunk);
+                }
+                n - available
+            };
+            // About as long as the missing samples take to arrive.
+            let frames = missing as f64 / self.channels.max(1) as f64;
+            let wait = Duration::from_secs_f64(frames / self.sample_rate.max(1) as f64);
+            std::thread::sleep(wait.clamp(MIN_POLL, MAX_POLL));
+        }
+    }
+
+    /// Number of samples currently buffered.
+    pub fn len(&self) -> usize {
+        self.consumer().occupied_len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Callbacks so far whose samples didn't all fit because the consumer
+    /// fell behind.
+    pub fn dropped_callbacks(&self) -> usize {
+        self.dropped_callbacks.load(Ordering::Relaxed)
+    }
+
+    /// Capacity of the ring buffer in samples.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Samples are interleaved, one per channel for every frame.
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn consumer(&self) -> std::sync::MutexGuard<'_, HeapCons<f32>> {
+        self.samples.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
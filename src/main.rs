@@ -1,148 +1,808 @@
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use byteorder::{ByteOrder, LittleEndian};
-use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use clap::Parser;
+use randomize7::{
+    annotate_random_data, average_spectrum, bias_report, bit_position_bias, center_and_normalize,
+    clipping_ratio, downmix, encode_base64, entropy_capped_length, extract_random_data_with,
+    lag1_autocorrelation, min_entropy_mcv, noise_shape_metrics, normalize_audio,
+    overall_verdict_at, print_random_data_as_hex, read_wav, remove_dc_offset, run_tests, self_test,
+    shannon_entropy, whiten_sha256, write_formatted, write_recording_wav, BatteryResults,
+    BiasMeter, BiasReport, Debiaser, EntropyCollector, ExtractParams, HealthPolicy, OutputFormat,
+    RandomizeError, ReportFormat, RetestPolicy, StreamingMonitor, Supervisor, TestKind,
+    DEFAULT_MAX_AUTOCORRELATION, RECORD_DURATION_SECS, SIGNIFICANCE_LEVEL, SUSPECT_P_VALUE,
+};
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-extern crate num_complex;
-use num_complex::Complex;
+/// Extracts random bytes from microphone noise.
+#[derive(Parser)]
+#[command(version)]
+struct Args {
+    /// Low bits taken from each adjacent-sample difference.
+    #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u32).range(1..=32))]
+    lsb: u32,
 
-const SAMPLE_RATE: u32 = 44100;
-// const CHANNELS: u16 = 1;
-const RECORD_DURATION_SECS: u64 = 2;
-// const RECORDING_FILE: &str = "recording.wav";
+    /// Von Neumann debias the extracted bits, at about a quarter of the throughput.
+    #[arg(long)]
+    debias: bool,
+
+    /// Print, for each output byte, the sample differences its bits came from.
+    #[arg(long, conflicts_with_all = ["debias", "count", "lsb_planes"])]
+    verbose: bool,
+
+    /// Condition the extracted bytes with SHA-256.
+    #[arg(long)]
+    whiten: bool,
+
+    /// Whiten every extracted byte instead of capping the output at what
+    /// their min-entropy estimate justifies.
+    #[arg(long, requires = "whiten")]
+    full_rate: bool,
+
+    /// Compare the bias of the extracted bytes before and after whitening.
+    #[arg(long, requires = "whiten")]
+    bias_report: bool,
+
+    /// Print a bar chart of the recording's averaged spectrum to stderr, to
+    /// find hum or tones.
+    #[arg(long)]
+    spectrum: bool,
+
+    /// Number of random bytes to output.
+    #[arg(long, default_value_t = 32)]
+    bytes: usize,
+
+    /// Recording duration in seconds.
+    #[arg(long, default_value_t = RECORD_DURATION_SECS as f64)]
+    duration: f64,
+
+    /// Record from the first input device whose name contains this (case-insensitive).
+    #[arg(long)]
+    device: Option<String>,
+
+    /// Record what the system plays instead of an input: the default output
+    /// in loopback mode on Windows, a PulseAudio or PipeWire monitor source
+    /// on Linux. Unavailable elsewhere.
+    #[arg(long, conflicts_with = "device")]
+    loopback: bool,
+
+    /// Without any input device, fall back to CPU timing jitter. Weak and
+    /// not cryptographic; for CI and headless machines only.
+    #[arg(long)]
+    jitter_fallback: bool,
+
+    /// Also mix the timing of the input callbacks into the conditioning.
+    /// Supplementary to the audio, never a replacement.
+    #[arg(long)]
+    callback_timing: bool,
+
+    /// Take the extracted bits one LSB plane at a time, so adjacent output
+    /// bits come from different samples.
+    #[arg(long)]
+    lsb_planes: bool,
+
+    /// Output format: hex, base64, raw or framed (raw, each batch prefixed
+    /// with its length as 4 little-endian bytes).
+    #[arg(long, default_value = "hex")]
+    format: OutputFormat,
+
+    /// Test report format: human or json.
+    #[arg(long, default_value = "human")]
+    report: ReportFormat,
+
+    /// Significance level: a test fails when its p-value is below this. Good
+    /// output still fails each test with this probability.
+    #[arg(long, default_value_t = SIGNIFICANCE_LEVEL, value_parser = parse_alpha)]
+    alpha: f64,
+
+    /// Comma-separated tests to run on the output, or `all`. Tests the
+    /// output is too short for are reported as skipped.
+    #[arg(long, value_delimiter = ',', default_value = "monobit,runs")]
+    tests: Vec<TestSelection>,
+
+    /// Write the random bytes to this file (created or truncated) instead of stdout.
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Read samples from a WAV file instead of recording.
+    #[arg(long, conflicts_with_all = ["duration", "device", "loopback", "count"])]
+    wav: Option<PathBuf>,
+
+    /// Write the samples the bytes are extracted from, after DC removal and
+    /// normalization, to this mono WAV file.
+    #[arg(long, conflicts_with_all = ["wav", "count"])]
+    dump_wav: Option<PathBuf>,
+
+    /// Generate this many batches, one line each (0 runs until interrupted).
+    /// The microphone keeps recording between batches. Tests are only
+    /// reported for a single batch.
+    #[arg(long, default_value_t = 1)]
+    count: u64,
+
+    /// Write raw bytes to stdout until killed, for piping into test suites
+    /// such as dieharder. Each batch yields all the bytes its samples
+    /// support; status goes to stderr.
+    #[arg(long, conflicts_with_all = [
+        "bytes", "format", "report", "tests", "output", "wav", "dump_wav", "count", "verbose",
+        "bias_report",
+    ])]
+    pipe_raw: bool,
+
+    /// In continuous mode, re-run the full battery on recent output every
+    /// this many MiB. A rejected re-test stops output until freshly
+    /// recorded bytes pass, retrying with exponential backoff.
+    #[arg(long, value_name = "MIB")]
+    retest_every: Option<f64>,
+
+    /// Check the statistical tests against the SP800-22 worked examples
+    /// instead of extracting anything. Fails if any disagrees.
+    #[arg(long)]
+    self_test: bool,
+
+    /// Record once and print a health report of the input: capture, level,
+    /// noise shape, bit bias, entropy and the full battery's verdict.
+    #[arg(long, conflicts_with_all = ["wav", "count", "pipe_raw", "self_test"])]
+    benchmark_source: bool,
+
+    /// Run as a health check until killed: record a short sample every
+    /// `--monitor-interval` seconds, run the quick tests on it and print an
+    /// OK, WARN or FAIL status line. Exits non-zero once the source has
+    /// failed for longer than `--grace-period` seconds.
+    #[arg(long, conflicts_with_all = [
+        "wav", "count", "pipe_raw", "self_test", "benchmark_source",
+    ])]
+    monitor: bool,
+
+    /// Seconds between two samples in monitor mode.
+    #[arg(long, default_value_t = 10.0, requires = "monitor")]
+    monitor_interval: f64,
+
+    /// Seconds the source may keep failing in monitor mode before it exits.
+    #[arg(long, default_value_t = 60.0, requires = "monitor")]
+    grace_period: f64,
+}
+
+#[derive(Clone)]
+enum TestSelection {
+    All,
+    Test(TestKind),
+}
+
+impl FromStr for TestSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "all" {
+            Ok(TestSelection::All)
+        } else {
+            s.parse().map(TestSelection::Test)
+        }
+    }
+}
 
 fn main() {
-    let host = cpal::default_host();
-    let input_device = host.default_input_device().expect("Failed to get default input device");
-
-    let input_config = input_device.default_input_config().expect("Failed to get default input config");
-    let sample_rate = input_config.sample_rate().0;
-    let record_duration_samples = sample_rate * RECORD_DURATION_SECS as u32;
-
-    let recording = Arc::new(Mutex::new(Vec::new()));
-    let recording_writer = recording.clone();
-
-    let input_stream = input_device.build_input_stream(
-        &input_config.into(),
-        move |data: &[f32], _: &cpal::InputCallbackInfo| {
-            let mut recording = recording_writer.lock().unwrap();
-            for &sample in data.iter() {
-                recording.push(sample);
-                if recording.len() as u32 >= record_duration_samples {
-                    break;
+    // Library warnings stay visible unless RUST_LOG says otherwise.
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+    if let Err(err) = run() {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let mut args = Args::parse();
+    if args.pipe_raw {
+        args.count = 0;
+        args.format = OutputFormat::Raw;
+    }
+    // Raw bytes and JSON reports go straight to stdout, so keep it free of
+    // any other text.
+    let raw_to_stdout =
+        matches!(args.format, OutputFormat::Raw | OutputFormat::Framed) && args.output.is_none();
+    let verbose = !raw_to_stdout && args.report == ReportFormat::Human;
+    #[cfg(not(feature = "json"))]
+    if args.report == ReportFormat::Json {
+        return Err("JSON reports require the `json` feature".into());
+    }
+
+    if args.self_test {
+        return run_self_test();
+    }
+
+    if args.benchmark_source {
+        return run_benchmark_source(&args);
+    }
+
+    if args.monitor {
+        return run_monitor(&args);
+    }
+
+    if args.count != 1 {
+        return run_batches(&args);
+    }
+
+    let random_data = match &args.wav {
+        Some(path) => {
+            let start = Instant::now();
+            let audio = read_wav(path)?;
+            let mut recording = downmix(&audio.samples, audio.channels);
+            if verbose {
+                println!(
+                    "Read {} samples ({} channels) from {}.",
+                    audio.samples.len(),
+                    audio.channels,
+                    path.display()
+                );
+            }
+            remove_dc_offset(&mut recording);
+            if verbose {
+                println!("Offsetting complete.");
+            }
+            normalize_audio(&mut recording, 1.0);
+            if verbose {
+                println!("normalization complete.");
+            }
+            let extracted =
+                extract_random_data_with(&recording, extract_params(&args, args.bytes))?;
+            let random_data = if args.whiten {
+                let length = if args.full_rate {
+                    extracted.len()
+                } else {
+                    entropy_capped_length(&extracted, extracted.len())
+                };
+                whiten_sha256(&extracted, length)
+            } else {
+                extracted.clone()
+            };
+            if verbose {
+                print_throughput(random_data.len(), start.elapsed());
+            }
+            if verbose && args.bias_report {
+                print_bias_report(&bias_report(&extracted, &random_data));
+            }
+            if verbose && args.verbose {
+                print_annotations(&recording, args.lsb, args.bytes)?;
+            }
+            if args.spectrum {
+                print_spectrum(&recording, audio.sample_rate);
+            }
+            random_data
+        }
+        None => {
+            let mut collector = EntropyCollector::new()
+                .duration(Duration::try_from_secs_f64(args.duration)?)
+                .debias(args.debias)
+                .whiten(args.whiten)
+                .full_rate(args.full_rate)
+                .jitter_fallback(args.jitter_fallback)
+                .callback_timing(args.callback_timing)
+                .lsb_planes(args.lsb_planes);
+            if let Some(device) = &args.device {
+                collector = collector.device(device);
+            }
+            if args.loopback {
+                collector = collector.loopback();
+            }
+            let stages = collector.collect_stages(args.lsb, args.bytes)?;
+            if verbose {
+                println!("Recording complete.");
+                if let Some(config) = &stages.stream_config {
+                    println!("Recorded {}.", config);
                 }
+                println!("Offsetting complete.");
+                println!("normalization complete.");
+                print_throughput(
+                    stages.random_data.len(),
+                    stages.recording_time + stages.processing_time,
+                );
             }
-        },
-        move |err| {
-            eprintln!("An error occurred on the input stream: {}", err);
-        },
-    ).unwrap();
+            if verbose && args.bias_report {
+                print_bias_report(&stages.bias_report());
+            }
+            if verbose && args.verbose {
+                print_annotations(&stages.normalized, args.lsb, args.bytes)?;
+            }
+            if args.spectrum {
+                print_spectrum(
+                    &downmix(&stages.normalized, stages.channels),
+                    stages.sample_rate,
+                );
+            }
+            if let Some(path) = &args.dump_wav {
+                write_recording_wav(&stages.normalized, stages.sample_rate, path)?;
+                if verbose {
+                    println!("Wrote recording to {}.", path.display());
+                }
+            }
+            stages.random_data
+        }
+    };
 
-    input_stream.play().unwrap();
-    std::thread::sleep(Duration::from_secs(RECORD_DURATION_SECS));
+    if random_data.len() < args.bytes {
+        log::warn!(
+            "the recording only supplied {} of the {} requested bytes",
+            random_data.len(),
+            args.bytes
+        );
+    }
 
-    let mut recording = recording.lock().unwrap();
+    if let Some(path) = &args.output {
+        let mut file = BufWriter::new(File::create(path)?);
+        write_formatted(&random_data, args.format, &mut file)?;
+        if verbose {
+            println!("Wrote {} bytes to {}.", random_data.len(), path.display());
+        }
+    } else if raw_to_stdout {
+        write_formatted(&random_data, args.format, &mut std::io::stdout().lock())?;
+        return Ok(());
+    }
 
-    println!("Recording complete.");
+    let BatteryResults { results, skipped } =
+        run_tests(&random_data, &selected_tests(&args.tests), args.alpha);
 
-    remove_dc_offset(&mut recording);
-    println!("Offsetting complete.");
+    match args.report {
+        ReportFormat::Human => {
+            if args.output.is_none() {
+                match args.format {
+                    OutputFormat::Base64 => {
+                        println!("Random data (base64): {}", encode_base64(&random_data))
+                    }
+                    _ => print_random_data_as_hex(&random_data),
+                }
+            }
+            for result in &results {
+                println!("Is output ({}) random: {}", result.name, result.passed);
+            }
+            for test in &skipped {
+                println!("Skipped ({}): {}", test.name, test.reason);
+            }
+            let verdict = overall_verdict_at(&results, args.alpha);
+            println!(
+                "Overall: {} of {} tests passed, minimum p-value {:.4}: {:?}",
+                verdict.passed, verdict.total, verdict.min_p_value, verdict.recommendation
+            );
+            println!(
+                "Entropy: {:.4} bits/byte Shannon, {:.4} bits/byte min-entropy.",
+                shannon_entropy(&random_data),
+                min_entropy_mcv(&random_data)
+            );
+        }
+        #[cfg(feature = "json")]
+        ReportFormat::Json => println!("{}", randomize7::report_json(&results)),
+        #[cfg(not(feature = "json"))]
+        ReportFormat::Json => unreachable!("rejected before recording"),
+    }
 
-    normalize_audio(&mut recording, 1.0);
-    println!("normalization complete.");
+    Ok(())
+}
 
-    let num_lsb = 8; // Adjust this value depending on the desired quality of randomness
-    let output_length = 32; // Set the desired output length (in bytes)
-    let random_data = extract_random_data(&recording, num_lsb, output_length);
+// The debiasing and bit order the flags ask for, unwhitened.
+fn extract_params(args: &Args, output_length: usize) -> ExtractParams {
+    ExtractParams {
+        debiaser: if args.debias {
+            Debiaser::VonNeumann
+        } else {
+            Debiaser::None
+        },
+        lsb_planes: args.lsb_planes,
+        ..ExtractParams::new(args.lsb, output_length)
+    }
+}
 
-    print_random_data_as_hex(&random_data);
+// How often continuous mode reports the running ones fraction on stderr.
+const BIAS_READOUT_INTERVAL: Duration = Duration::from_secs(5);
 
-    let rec = f32_to_u8(&recording);
-    let mono_score = monobit_test(&rec);
-    println!("Is output (monobit) random: {}", mono_score);
+// Extracts `args.count` batches from one live stream, writing each as soon
+// as it is ready, and reports the running ones fraction every
+// `BIAS_READOUT_INTERVAL`. Ctrl-C finishes the batch being recorded, stops
+// the stream and flushes the output before returning, as does the reader
+// closing a `--pipe-raw` pipe.
+fn run_batches(args: &Args) -> Result<(), Box<dyn Error>> {
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let handler_flag = interrupted.clone();
+    ctrlc::set_handler(move || handler_flag.store(true, Ordering::SeqCst))?;
 
-    let runs_score = runs_test(&rec);
-    println!("Is output (runs) random: {}", runs_score);
+    let duration = Duration::try_from_secs_f64(args.duration)?;
+    let mut collector = EntropyCollector::new();
+    if let Some(device) = &args.device {
+        collector = collector.device(device);
+    }
+    if args.loopback {
+        collector = collector.loopback();
+    }
+    // Room for one batch being consumed while the next is recorded.
+    let live = collector.live(duration * 2)?;
+    let batch_samples =
+        (live.sample_rate() as f64 * live.channels() as f64 * duration.as_secs_f64()) as usize;
+    let batch_bytes = if args.pipe_raw {
+        let bytes = batch_samples.saturating_sub(1) * args.lsb as usize / 8;
+        if args.debias {
+            bytes / 4
+        } else {
+            bytes
+        }
+    } else {
+        args.bytes
+    };
+    if args.pipe_raw {
+        eprintln!(
+            "Writing about {} bytes every {:.2} s to stdout.",
+            batch_bytes,
+            duration.as_secs_f64()
+        );
+    }
+
+    let mut output: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(std::io::stdout().lock()),
+    };
+
+    let next_batch = || {
+        let mut samples = live.pop_chunk(batch_samples)?;
+        center_and_normalize(&mut samples, 1.0);
+        extract_random_data_with(
+            &samples,
+            ExtractParams {
+                whiten: args.whiten,
+                ..extract_params(args, batch_bytes)
+            },
+        )
+    };
+    let mut next_batch: Box<dyn FnMut() -> Result<Vec<u8>, RandomizeError>> =
+        match args.retest_every {
+            Some(mib) => {
+                let mut supervisor = Supervisor::new(next_batch).policy(RetestPolicy {
+                    interval_bytes: (mib * (1 << 20) as f64) as u64,
+                    ..RetestPolicy::default()
+                });
+                Box::new(move || supervisor.next_batch())
+            }
+            None => Box::new(next_batch),
+        };
+
+    let mut meter = BiasMeter::new();
+    let mut last_readout = Instant::now();
+    let mut batch = 0;
+    while (args.count == 0 || batch < args.count) && !interrupted.load(Ordering::SeqCst) {
+        let random_data = next_batch()?;
+        meter.update(&random_data);
+        if last_readout.elapsed() >= BIAS_READOUT_INTERVAL {
+            eprintln!(
+                "Ones fraction: {:.5} over {} bits",
+                meter.ones_fraction(),
+                meter.bits()
+            );
+            last_readout = Instant::now();
+        }
+        match write_formatted(&random_data, args.format, &mut output) {
+            Err(err) if args.pipe_raw && err.kind() == std::io::ErrorKind::BrokenPipe => {
+                return Ok(())
+            }
+            result => result?,
+        }
+        batch += 1;
+    }
+
+    drop(next_batch);
+    drop(live);
+    output.flush()?;
+    Ok(())
 }
 
-fn remove_dc_offset(samples: &mut Vec<f32>) {
-    let mean: f32 = samples.iter().sum::<f32>() / samples.len() as f32;
-    samples.iter_mut().for_each(|sample| *sample -= mean);
+fn run_self_test() -> Result<(), Box<dyn Error>> {
+    let results = self_test();
+    for result in &results {
+        println!(
+            "{:<26}{:<18}expected {:.6}, got {:.6}: {}",
+            result.name,
+            result.reference,
+            result.expected,
+            result.actual,
+            if result.passed { "ok" } else { "FAILED" }
+        );
+    }
+    let failed = results.iter().filter(|result| !result.passed).count();
+    if failed > 0 {
+        return Err(format!("{} of {} self-tests failed", failed, results.len()).into());
+    }
+    Ok(())
 }
 
-fn normalize_audio(samples: &mut Vec<f32>, max_level: f32) {
-    let max_sample = samples
+// Asks the extraction for every byte the recording supports.
+const ALL_BYTES: usize = usize::MAX / 8;
+
+fn run_benchmark_source(args: &Args) -> Result<(), Box<dyn Error>> {
+    let mut collector = EntropyCollector::new()
+        .duration(Duration::try_from_secs_f64(args.duration)?)
+        .jitter_fallback(args.jitter_fallback);
+    if let Some(device) = &args.device {
+        collector = collector.device(device);
+    }
+    if args.loopback {
+        collector = collector.loopback();
+    }
+    println!("Recording {:.1} s...", args.duration);
+    let stages = collector.collect_stages(args.lsb, ALL_BYTES)?;
+
+    let capture = stages.capture;
+    println!(
+        "Captured:        {} of {} samples, {} channels at {} Hz, {} dropped callbacks",
+        capture.captured_samples,
+        capture.requested_samples,
+        stages.channels,
+        stages.sample_rate,
+        capture.dropped_callbacks
+    );
+    if let Some(config) = &stages.stream_config {
+        println!("Stream:          {}", config);
+    }
+    let metrics = noise_shape_metrics(&stages.raw);
+    println!("Clipping ratio:  {:.4}", clipping_ratio(&stages.raw));
+    println!("DC offset:       {:.6}", metrics.mean);
+    println!(
+        "RMS:             {:.6}",
+        (metrics.variance + metrics.mean * metrics.mean).sqrt()
+    );
+    println!(
+        "Kurtosis:        {:.3} (3 for thermal noise)",
+        metrics.kurtosis
+    );
+
+    let extracted = &stages.extracted;
+    let bias = bit_position_bias(extracted)
         .iter()
-        .cloned()
-        .map(f32::abs)
-        .fold(f32::MIN, f32::max);
-    let normalization_factor = max_level / max_sample;
-    samples.iter_mut().for_each(|sample| *sample *= normalization_factor);
+        .map(|fraction| format!("{:.3}", fraction))
+        .collect::<Vec<_>>()
+        .join(" ");
+    println!("Bit bias:        {} (MSB first)", bias);
+    println!(
+        "Entropy:         {:.4} bits/byte min-entropy, {:.4} Shannon, over {} bytes at {} LSBs",
+        min_entropy_mcv(extracted),
+        shannon_entropy(extracted),
+        extracted.len(),
+        args.lsb
+    );
+
+    let BatteryResults { results, skipped } = run_tests(extracted, &TestKind::ALL, args.alpha);
+    for result in &results {
+        println!(
+            "  {:<36}p = {:.4}: {}",
+            result.name,
+            result.p_value,
+            if result.passed { "pass" } else { "FAIL" }
+        );
+    }
+    for test in &skipped {
+        println!("  {:<36}skipped: {}", test.name, test.reason);
+    }
+    let verdict = overall_verdict_at(&results, args.alpha);
+    println!(
+        "Verdict:         {} of {} tests passed, minimum p-value {:.4}: {:?}",
+        verdict.passed, verdict.total, verdict.min_p_value, verdict.recommendation
+    );
+    Ok(())
 }
 
-fn extract_random_data(samples: &[f32], num_lsb: u32, output_length: usize) -> Vec<u8> {
-    let mut random_data = Vec::with_capacity(output_length);
+// Bytes tested per sample in monitor mode; enough for the lag-1
+// autocorrelation limit to hold.
+const MONITOR_SAMPLE_BYTES: usize = 2500;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
 
-    let samples_per_byte = (samples.len() - 1) / output_length;
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Status::Ok => "OK",
+            Status::Warn => "WARN",
+            Status::Fail => "FAIL",
+        })
+    }
+}
 
-    for i in (1..samples.len()).step_by(samples_per_byte) {
-        let difference = samples[i] - samples[i - 1];
-        let difference_as_int = difference.to_bits();
-        let lsb_bits = difference_as_int & ((1 << num_lsb) - 1);
+// Samples the source every `monitor_interval` and prints a status line. A
+// sample the health policy rejects, or that can't be recorded at all, is
+// FAIL; one that passes is WARN if the running tests over every sample that
+// passed so far look suspect, e.g. from a slowly growing bias, and OK
+// otherwise. Gives up once FAIL has lasted longer than `grace_period`.
+fn run_monitor(args: &Args) -> Result<(), Box<dyn Error>> {
+    let interval = Duration::try_from_secs_f64(args.monitor_interval)?;
+    let grace_period = Duration::try_from_secs_f64(args.grace_period)?;
+    let mut collector = EntropyCollector::new()
+        .duration_for_output(true)
+        .jitter_fallback(args.jitter_fallback)
+        .health_policy(HealthPolicy {
+            significance_level: args.alpha,
+            max_autocorrelation: Some(DEFAULT_MAX_AUTOCORRELATION),
+            ..HealthPolicy::default()
+        });
+    if let Some(device) = &args.device {
+        collector = collector.device(device);
+    }
+    if args.loopback {
+        collector = collector.loopback();
+    }
 
-        random_data.push(lsb_bits as u8);
+    let mut running = StreamingMonitor::new();
+    let mut failing_since: Option<Instant> = None;
+    loop {
+        let started = Instant::now();
+        let (status, details) = match collector.collect_stages(args.lsb, MONITOR_SAMPLE_BYTES) {
+            Ok(stages) => {
+                let mut sample = StreamingMonitor::new();
+                sample.update(&stages.extracted);
+                running.update(&stages.extracted);
+                let p_values = |monitor: &StreamingMonitor| {
+                    monitor
+                        .snapshot()
+                        .iter()
+                        .map(|result| format!("{} p={:.4}", result.name, result.p_value))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                let suspect = running
+                    .snapshot()
+                    .iter()
+                    .any(|result| result.p_value < SUSPECT_P_VALUE);
+                let status = if suspect { Status::Warn } else { Status::Ok };
+                let details = format!(
+                    "{}, autocorrelation {:+.4}; over {} bytes: {}",
+                    p_values(&sample),
+                    lag1_autocorrelation(&stages.extracted),
+                    running.len(),
+                    p_values(&running)
+                );
+                (status, details)
+            }
+            Err(err) => (Status::Fail, err.to_string()),
+        };
+        println!("{:<4} {}", status, details);
 
-        if random_data.len() >= output_length {
-            break;
+        if status == Status::Fail {
+            let since = *failing_since.get_or_insert(started);
+            if since.elapsed() > grace_period {
+                println!(
+                    "FAIL the source has failed for {:.0} s, giving up",
+                    since.elapsed().as_secs_f64()
+                );
+                return Err(format!(
+                    "the source failed for longer than the {:.0} s grace period",
+                    grace_period.as_secs_f64()
+                )
+                .into());
+            }
+        } else {
+            failing_since = None;
         }
+        std::thread::sleep(interval.saturating_sub(started.elapsed()));
+    }
+}
+
+fn parse_alpha(s: &str) -> Result<f64, String> {
+    let alpha: f64 = s.parse().map_err(|err| format!("{}", err))?;
+    if alpha > 0.0 && alpha < 1.0 {
+        Ok(alpha)
+    } else {
+        Err(format!("{} is not between 0 and 1", alpha))
+    }
+}
+
+fn print_throughput(bytes: usize, elapsed: Duration) {
+    println!(
+        "Throughput: {:.1} bytes/s ({} bytes in {:.2} s).",
+        bytes as f64 / elapsed.as_secs_f64(),
+        bytes,
+        elapsed.as_secs_f64()
+    );
+}
+
+const SPECTRUM_WINDOW: usize = 1024;
+const SPECTRUM_BANDS: usize = 32;
+// Levels this far below the loudest band get an empty bar.
+const SPECTRUM_RANGE_DB: f32 = 60.0;
+const SPECTRUM_BAR_WIDTH: f32 = 50.0;
+
+// One bar per band of the averaged spectrum, on stderr so raw output on
+// stdout stays clean.
+fn print_spectrum(samples: &[f32], sample_rate: u32) {
+    let spectrum = average_spectrum(samples, SPECTRUM_WINDOW);
+    if spectrum.is_empty() {
+        eprintln!("Spectrum: fewer than {} samples.", SPECTRUM_WINDOW);
+        return;
+    }
+    let band_width = spectrum.len().div_ceil(SPECTRUM_BANDS);
+    let bands: Vec<f32> = spectrum
+        .chunks(band_width)
+        .map(|band| band.iter().sum::<f32>() / band.len() as f32)
+        .collect();
+    let loudest = bands.iter().copied().fold(f32::MIN_POSITIVE, f32::max);
+    let hz_per_bin = sample_rate as f32 / SPECTRUM_WINDOW as f32;
+
+    eprintln!("Spectrum (dB relative to the loudest band):");
+    for (i, level) in bands.iter().enumerate() {
+        let db = 20.0 * (level.max(f32::MIN_POSITIVE) / loudest).log10();
+        let bar = ((SPECTRUM_RANGE_DB + db) / SPECTRUM_RANGE_DB * SPECTRUM_BAR_WIDTH)
+            .clamp(0.0, SPECTRUM_BAR_WIDTH) as usize;
+        let low = (i * band_width) as f32 * hz_per_bin;
+        let high = ((i + 1) * band_width) as f32 * hz_per_bin;
+        eprintln!(
+            "{:>6.0}-{:<6.0} Hz {:>6.1} |{}",
+            low,
+            high,
+            db,
+            "#".repeat(bar)
+        );
     }
+}
 
-    random_data
+fn print_bias_report(report: &BiasReport) {
+    println!("{:<16}{:>14}{:>14}", "", "raw", "conditioned");
+    println!(
+        "{:<16}{:>14.4}{:>14.4}",
+        "Ones proportion", report.raw.ones_proportion, report.conditioned.ones_proportion
+    );
+    println!(
+        "{:<16}{:>14.4}{:>14.4}",
+        "Monobit p-value", report.raw.monobit_p_value, report.conditioned.monobit_p_value
+    );
+    println!(
+        "{:<16}{:>14.4}{:>14.4}",
+        "Min-entropy/B", report.raw.min_entropy_per_byte, report.conditioned.min_entropy_per_byte
+    );
 }
 
-fn print_random_data_as_hex(random_data: &[u8]) {
-    let hex_string = random_data
+// One line per byte: its value and every difference that supplied its bits.
+fn print_annotations(
+    samples: &[f32],
+    num_lsb: u32,
+    output_length: usize,
+) -> Result<(), Box<dyn Error>> {
+    for (index, byte) in annotate_random_data(samples, num_lsb, output_length)?
         .iter()
-        .map(|byte| format!("{:02x}", byte))
-        .collect::<String>();
-
-    println!("Random data (hex): {}", hex_string);
-}
-
-fn monobit_test(data: &[u8]) -> bool {
-    let bit_count = data.iter().map(|&byte| byte.count_ones()).sum::<u32>();
-    let total_bits = data.len() * 8;
-    let proportion = bit_count as f64 / total_bits as f64;
-    0.45 < proportion && proportion < 0.55
-}
-
-fn runs_test(data: &[u8]) -> bool {
-    let mut prev_bit = data[0] & 0x80;
-    let mut run_count = 0;
-    let mut run_lengths = vec![0; 6];
-    for &byte in data {
-        for i in 0..8 {
-            let bit = byte & (0x80 >> i);
-            if bit != prev_bit {
-                run_count += 1;
-                if run_count <= 6 {
-                    run_lengths[run_count - 1] += 1;
-                }
-                prev_bit = bit;
-            }
-        }
+        .enumerate()
+    {
+        let sources: Vec<String> = byte
+            .sources
+            .iter()
+            .map(|source| {
+                format!(
+                    "sample {} (difference {:e}, low bits {:#x})",
+                    source.sample_index, source.difference, source.lsb
+                )
+            })
+            .collect();
+        println!(
+            "Byte {}: {:#04x} from {}",
+            index,
+            byte.value,
+            sources.join(", ")
+        );
     }
-    let n = data.len() * 8;
-    let p_value = (2.0 * (run_lengths[0] as f64) - n as f64).abs() / (2.0 * ((n as f64).sqrt()));
-    p_value < 1.96
+    Ok(())
 }
 
-fn f32_to_u8(data: &[f32]) -> Vec<u8> {
-    let mut bytes = Vec::with_capacity(data.len() * 4);
-    for value in data {
-        let mut buffer = [0u8; 4];
-        LittleEndian::write_f32(&mut buffer, *value);
-        bytes.extend_from_slice(&buffer);
+// Expands `all` and drops duplicates, keeping the order given.
+fn selected_tests(selections: &[TestSelection]) -> Vec<TestKind> {
+    let mut kinds = Vec::new();
+    for selection in selections {
+        let expanded = match selection {
+            TestSelection::All => TestKind::ALL.to_vec(),
+            TestSelection::Test(kind) => vec![*kind],
+        };
+        for kind in expanded {
+            if !kinds.contains(&kind) {
+                kinds.push(kind);
+            }
+        }
     }
-    bytes
-}
\ No newline at end of file
+    kinds
+}
@@ -0,0 +1,130 @@
+use crate::statistical_tests::{
+    chi_square_from_histogram, monobit_from_counts, runs_from_counts, TestResult,
+    CHI_SQUARE_MIN_BYTES,
+};
+
+/// Running monobit, runs and chi-square byte statistics over a stream that
+/// arrives in chunks, without keeping the data.
+///
+/// [`snapshot`](Self::snapshot) reports the same results as the batch tests
+/// would on everything seen so far.
+///
+/// ```
+/// use randomize7::StreamingMonitor;
+///
+/// let mut monitor = StreamingMonitor::new();
+/// monitor.update(&[0x5a, 0x3c]);
+/// monitor.update(&[0xa5]);
+/// assert_eq!(monitor.snapshot().len(), 2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StreamingMonitor {
+    bytes: u64,
+    ones: u64,
+    runs: u64,
+    last_bit: Option<bool>,
+    histogram: [u64; 256],
+}
+
+impl StreamingMonitor {
+    pub fn new() -> Self {
+        StreamingMonitor {
+            bytes: 0,
+            ones: 0,
+            runs: 0,
+            last_bit: None,
+            histogram: [0; 256],
+        }
+    }
+
+    /// Adds the next chunk of the stream. Doesn't allocate.
+    pub fn update(&mut self, chunk: &[u8]) {
+        for &byte in chunk {
+            self.ones += byte.count_ones() as u64;
+            self.histogram[byte as usize] += 1;
+
+            // Bit changes inside the byte, most significant bit first, plus
+            // the one from the previous byte's last bit.
+            self.runs += ((byte ^ (byte >> 1)) & 0x7f).count_ones() as u64;
+            let first_bit = byte & 0x80 != 0;
+            if self.last_bit != Some(first_bit) {
+                self.runs += 1;
+            }
+            self.last_bit = Some(byte & 1 != 0);
+        }
+        self.bytes += chunk.len() as u64;
+    }
+
+    /// Bytes seen so far.
+    pub fn len(&self) -> u64 {
+        self.bytes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes == 0
+    }
+
+    /// Monobit and runs once any data has arrived, and the chi-square byte
+    /// test once there is enough for it to be reliable.
+    pub fn snapshot(&self) -> Vec<TestResult> {
+        if self.bytes == 0 {
+            return Vec::new();
+        }
+        let total_bits = self.bytes * 8;
+        let mut results = vec![
+            monobit_from_counts(self.ones, total_bits),
+            runs_from_counts(self.ones, total_bits, self.runs),
+        ];
+        if self.bytes >= CHI_SQUARE_MIN_BYTES as u64 {
+            results.push(chi_square_from_histogram(&self.histogram, self.bytes));
+        }
+        results
+    }
+}
+
+impl Default for StreamingMonitor {
+    fn default() -> Self {
+        StreamingMonitor::new()
+    }
+}
+
+/// The running fraction of one bits, for a live health readout that costs
+/// one `count_ones` per byte. [`StreamingMonitor`] runs real tests on the
+/// same stream.
+///
+/// ```
+/// use randomize7::BiasMeter;
+///
+/// let mut meter = BiasMeter::new();
+/// meter.update(&[0xff, 0x00, 0x0f]);
+/// assert_eq!(meter.ones_fraction(), 0.5);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BiasMeter {
+    ones: u64,
+    bits: u64,
+}
+
+impl BiasMeter {
+    pub fn new() -> Self {
+        BiasMeter::default()
+    }
+
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.ones += chunk
+            .iter()
+            .map(|byte| byte.count_ones() as u64)
+            .sum::<u64>();
+        self.bits += chunk.len() as u64 * 8;
+    }
+
+    /// Ones over all bits seen so far; `NaN` before any arrive.
+    pub fn ones_fraction(&self) -> f64 {
+        self.ones as f64 / self.bits as f64
+    }
+
+    /// Bits seen so far.
+    pub fn bits(&self) -> u64 {
+        self.bits
+    }
+}
@@ -0,0 +1,144 @@
+use std::io::{self, Read, Write};
+use std::str::FromStr;
+
+#[cfg(feature = "json")]
+use crate::statistical_tests::TestResult;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Hex,
+    Base64,
+    Raw,
+    /// Raw bytes prefixed with their length as a 4-byte little-endian
+    /// integer, so concatenated batches can be split with [`read_framed`].
+    Framed,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hex" => Ok(OutputFormat::Hex),
+            "base64" => Ok(OutputFormat::Base64),
+            "raw" => Ok(OutputFormat::Raw),
+            "framed" => Ok(OutputFormat::Framed),
+            _ => Err(format!(
+                "unknown output format {:?} (expected hex, base64, raw or framed)",
+                s
+            )),
+        }
+    }
+}
+
+/// How the binary prints test results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Human,
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(ReportFormat::Human),
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(format!(
+                "unknown report format {:?} (expected human or json)",
+                s
+            )),
+        }
+    }
+}
+
+/// Serializes the results as a JSON array. A `NaN` statistic becomes `null`.
+#[cfg(feature = "json")]
+pub fn report_json(results: &[TestResult]) -> String {
+    serde_json::to_string_pretty(results).expect("test results always serialize")
+}
+
+pub fn print_random_data_as_hex(random_data: &[u8]) {
+    println!("Random data (hex): {}", encode_hex(random_data));
+}
+
+pub fn encode_hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Standard base64 with `=` padding.
+pub fn encode_base64(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let buffer = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let group = u32::from_be_bytes([0, buffer[0], buffer[1], buffer[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (group >> (18 - 6 * i)) & 0x3f;
+                encoded.push(BASE64_ALPHABET[index as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Writes the bytes unchanged, without any trailing newline.
+pub fn write_raw(data: &[u8], w: &mut impl Write) -> io::Result<()> {
+    w.write_all(data)?;
+    w.flush()
+}
+
+/// Writes the bytes in `format`: hex and base64 as one line of text, raw
+/// bytes unchanged, framed ones after their length. Batches of 4 GiB or more
+/// can't be framed.
+pub fn write_formatted(data: &[u8], format: OutputFormat, w: &mut impl Write) -> io::Result<()> {
+    match format {
+        OutputFormat::Hex => writeln!(w, "{}", encode_hex(data))?,
+        OutputFormat::Base64 => writeln!(w, "{}", encode_base64(data))?,
+        OutputFormat::Raw => w.write_all(data)?,
+        OutputFormat::Framed => {
+            let length = u32::try_from(data.len()).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "batch too long for a 4-byte length prefix",
+                )
+            })?;
+            w.write_all(&length.to_le_bytes())?;
+            w.write_all(data)?;
+        }
+    }
+    w.flush()
+}
+
+/// Splits output written in [`OutputFormat::Framed`] back into its batches.
+/// A stream that ends inside a frame fails with
+/// [`io::ErrorKind::UnexpectedEof`].
+pub fn read_framed(mut r: impl Read) -> io::Result<Vec<Vec<u8>>> {
+    let mut batches = Vec::new();
+    loop {
+        let mut prefix = [0u8; 4];
+        let mut filled = 0;
+        while filled < prefix.len() {
+            match r.read(&mut prefix[filled..]) {
+                Ok(0) if filled == 0 => return Ok(batches),
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(read) => filled += read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        let mut batch = vec![0; u32::from_le_bytes(prefix) as usize];
+        r.read_exact(&mut batch)?;
+        batches.push(batch);
+    }
+}
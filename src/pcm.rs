@@ -0,0 +1,104 @@
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use std::io::Read;
+
+use crate::error::RandomizeError;
+use crate::extraction::{process_samples, Endianness, ExtractParams};
+use crate::processing::downmix;
+
+/// Encoding of a single headerless PCM sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PcmSampleType {
+    I16,
+    /// Packed into three bytes, as `arecord -f S24_3LE` writes it.
+    I24,
+    I32,
+    F32,
+}
+
+impl PcmSampleType {
+    /// Bytes one sample occupies.
+    pub fn width(self) -> usize {
+        match self {
+            PcmSampleType::I16 => 2,
+            PcmSampleType::I24 => 3,
+            PcmSampleType::I32 | PcmSampleType::F32 => 4,
+        }
+    }
+}
+
+/// Layout of raw PCM samples, e.g. `arecord -f S16_LE` is
+/// `PcmFormat::new(PcmSampleType::I16, Endianness::Little)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PcmFormat {
+    pub sample_type: PcmSampleType,
+    pub endianness: Endianness,
+}
+
+impl PcmFormat {
+    pub fn new(sample_type: PcmSampleType, endianness: Endianness) -> Self {
+        PcmFormat {
+            sample_type,
+            endianness,
+        }
+    }
+
+    // Integer samples are scaled to [-1, 1) like WAV ones.
+    fn decode(self, bytes: &[u8]) -> f32 {
+        match self.endianness {
+            Endianness::Little => self.decode_with::<LittleEndian>(bytes),
+            Endianness::Big => self.decode_with::<BigEndian>(bytes),
+        }
+    }
+
+    fn decode_with<B: ByteOrder>(self, bytes: &[u8]) -> f32 {
+        match self.sample_type {
+            PcmSampleType::I16 => B::read_i16(bytes) as f32 / 32768.0,
+            PcmSampleType::I24 => B::read_i24(bytes) as f32 / 8388608.0,
+            PcmSampleType::I32 => B::read_i32(bytes) as f32 / 2147483648.0,
+            PcmSampleType::F32 => B::read_f32(bytes),
+        }
+    }
+}
+
+/// Runs the extraction pipeline over headerless interleaved PCM, such as
+/// `arecord` output or a pipe, the way [`extract_from_wav`](crate::extract_from_wav)
+/// does over a WAV file.
+///
+/// `reader` is read to the end; a trailing partial frame is dropped, since
+/// an interrupted recording usually ends in one. Frames are downmixed to
+/// mono before extraction.
+pub fn extract_from_raw_pcm(
+    reader: impl Read,
+    format: PcmFormat,
+    channels: u16,
+    num_lsb: u32,
+    output_length: usize,
+) -> Result<Vec<u8>, RandomizeError> {
+    let samples = read_raw_pcm(reader, format, channels)?;
+    let samples = downmix(&samples, channels);
+    process_samples(&samples, ExtractParams::new(num_lsb, output_length))
+}
+
+/// Decodes headerless interleaved PCM to `f32` samples, dropping a trailing
+/// partial frame.
+pub fn read_raw_pcm(
+    mut reader: impl Read,
+    format: PcmFormat,
+    channels: u16,
+) -> Result<Vec<f32>, RandomizeError> {
+    if channels == 0 {
+        return Err(RandomizeError::InvalidParameter(
+            "raw PCM needs at least one channel".to_string(),
+        ));
+    }
+
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).map_err(RandomizeError::Io)?;
+    let width = format.sample_type.width();
+    let frame_bytes = width * channels as usize;
+    let whole_frames = bytes.len() / frame_bytes * frame_bytes;
+    Ok(bytes[..whole_frames]
+        .chunks_exact(width)
+        .map(|sample| format.decode(sample))
+        .collect())
+}
@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use crate::collector::EntropyCollector;
+use crate::conditioning::xor_fold;
+use crate::error::RandomizeError;
+use crate::extraction::{extract_random_data, ExtractStrategy};
+use crate::processing::center_and_normalize;
+use crate::recording::{record_samples, RECORD_DURATION_SECS};
+use crate::source::{Microphone, SampleSource};
+
+const DEFAULT_NUM_LSB: u32 = 8;
+
+/// Records `duration` of audio from the default device and runs the binary's
+/// pipeline over it: DC offset removal, normalization, then extraction.
+pub fn record_random_data(
+    duration: Duration,
+    num_lsb: u32,
+    output_length: usize,
+) -> Result<Vec<u8>, RandomizeError> {
+    let mut recording = record_samples(duration)?;
+    center_and_normalize(&mut recording, 1.0);
+    extract_random_data(
+        &recording,
+        num_lsb,
+        output_length,
+        ExtractStrategy::default(),
+        false,
+        false,
+    )
+}
+
+/// [`record_random_data`] on Tokio's blocking thread pool, so the recording
+/// doesn't stall the async runtime. Yields one byte per sample difference,
+/// as many as the recording supports.
+///
+/// Must be awaited inside a Tokio runtime; it is meant for the
+/// multi-threaded one, where other tasks keep running meanwhile.
+#[cfg(feature = "tokio")]
+pub async fn collect_async(duration: Duration) -> Result<Vec<u8>, RandomizeError> {
+    tokio::task::spawn_blocking(move || {
+        let mut recording = record_samples(duration)?;
+        center_and_normalize(&mut recording, 1.0);
+        let output_length = recording.len().saturating_sub(1);
+        extract_random_data(
+            &recording,
+            DEFAULT_NUM_LSB,
+            output_length,
+            ExtractStrategy::default(),
+            false,
+            false,
+        )
+    })
+    .await
+    .unwrap_or_else(|err| std::panic::resume_unwind(err.into_panic()))
+}
+
+/// A full-entropy `N`-byte seed from the default device, taking the low 8
+/// bits of each difference; see [`EntropyCollector::fold_into_seed`].
+/// Logs how many recordings it took.
+pub fn fold_into_seed<const N: usize>() -> Result<[u8; N], RandomizeError> {
+    let (seed, rounds) = EntropyCollector::new().fold_into_seed::<N>(8)?;
+    log::info!("a {}-byte seed took {} recordings", N, rounds);
+    Ok(seed)
+}
+
+/// Records `rounds` independent windows and XOR-folds their extracted bytes,
+/// a cheap way to reduce bias without full SHA-256 whitening.
+pub fn collect_xor_folded(rounds: usize, output_length: usize) -> Result<Vec<u8>, RandomizeError> {
+    let sources = (0..rounds)
+        .map(|_| {
+            record_random_data(
+                Duration::from_secs(RECORD_DURATION_SECS),
+                DEFAULT_NUM_LSB,
+                output_length,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(xor_fold(&sources))
+}
+
+/// Records `duration` from every device in `devices` at once and XOR-folds
+/// what each yields, so the output is at least as unpredictable as the best
+/// device. Names are matched as by [`Microphone::device`].
+///
+/// Each recording is normalized and extracted on its own, taking the low 8
+/// bits of each difference, so devices may run at different sample rates.
+/// The output is as long as the shortest device's bytes.
+pub fn collect_multi(devices: &[String], duration: Duration) -> Result<Vec<u8>, RandomizeError> {
+    if devices.is_empty() {
+        return Err(RandomizeError::InvalidParameter(
+            "at least one device is required".to_string(),
+        ));
+    }
+
+    let sources = std::thread::scope(|scope| {
+        let recordings: Vec<_> = devices
+            .iter()
+            .map(|name| scope.spawn(move || record_device(name, duration)))
+            .collect();
+        recordings
+            .into_iter()
+            .map(|recording| recording.join().expect("recording thread panicked"))
+            .collect::<Result<Vec<_>, _>>()
+    })?;
+    Ok(xor_fold(&sources))
+}
+
+// Every byte one device's recording supplies.
+fn record_device(name: &str, duration: Duration) -> Result<Vec<u8>, RandomizeError> {
+    let mut recording = Microphone::new().device(name).record(duration)?.samples;
+    center_and_normalize(&mut recording, 1.0);
+    let output_length = recording.len() * DEFAULT_NUM_LSB as usize / 8;
+    extract_random_data(
+        &recording,
+        DEFAULT_NUM_LSB,
+        output_length,
+        ExtractStrategy::default(),
+        false,
+        false,
+    )
+}
@@ -0,0 +1,225 @@
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+use crate::entropy::min_entropy_mcv;
+use crate::error::RandomizeError;
+#[cfg(feature = "microphone")]
+use crate::pipeline::record_random_data;
+use crate::secure::Sensitive;
+
+/// Min-entropy the pool collects before it reseeds the output key.
+pub const DEFAULT_RESEED_THRESHOLD_BITS: f64 = 256.0;
+
+/// Length of the seed [`EntropyPool::save`] writes.
+pub const SEED_FILE_BYTES: usize = 32;
+
+const SEED_FILE_MAGIC: &[u8; 8] = b"R7SEED1\0";
+
+// Fresh audio mixed in by `EntropyPool::load`.
+#[cfg(feature = "microphone")]
+const LOAD_FRESH_DURATION: std::time::Duration = std::time::Duration::from_millis(100);
+#[cfg(feature = "microphone")]
+const LOAD_FRESH_BYTES: usize = 1024;
+
+/// Persistent entropy pool in the style of Fortuna.
+///
+/// [`stir`](Self::stir) hashes new entropy into the pool and credits its
+/// [MCV min-entropy](min_entropy_mcv) estimate. Once the credited entropy
+/// reaches the reseed threshold, the pool is folded into the key used by
+/// [`extract`](Self::extract), so output stays fresh without every caller
+/// having to record audio.
+///
+/// ```
+/// use randomize7::EntropyPool;
+///
+/// let mut pool = EntropyPool::new();
+/// pool.stir(&(0..=255).collect::<Vec<u8>>());
+/// assert!(pool.is_seeded());
+/// let key = pool.extract(32);
+/// ```
+pub struct EntropyPool {
+    pool: Sha256,
+    key: [u8; 32],
+    pending_entropy_bits: f64,
+    reseed_threshold_bits: f64,
+    reseed_count: u64,
+}
+
+impl EntropyPool {
+    pub fn new() -> Self {
+        EntropyPool::with_reseed_threshold(DEFAULT_RESEED_THRESHOLD_BITS)
+    }
+
+    pub fn with_reseed_threshold(reseed_threshold_bits: f64) -> Self {
+        EntropyPool {
+            pool: Sha256::new(),
+            key: [0; 32],
+            pending_entropy_bits: 0.0,
+            reseed_threshold_bits,
+            reseed_count: 0,
+        }
+    }
+
+    /// Mixes `new_bytes` into the pool, reseeding once enough min-entropy
+    /// has accumulated.
+    pub fn stir(&mut self, new_bytes: &[u8]) {
+        self.pool.update(new_bytes);
+        self.pending_entropy_bits += min_entropy_mcv(new_bytes) * new_bytes.len() as f64;
+        if self.pending_entropy_bits >= self.reseed_threshold_bits {
+            self.reseed();
+        }
+    }
+
+    /// Returns `n` bytes derived from the current key, then replaces the key
+    /// so earlier output can't be reconstructed from the pool state.
+    ///
+    /// Until the pool has reseeded once (see [`is_seeded`](Self::is_seeded))
+    /// the output carries no entropy.
+    pub fn extract(&mut self, n: usize) -> Vec<u8> {
+        let mut output = Vec::with_capacity(n);
+        let mut counter: u64 = 0;
+        while output.len() < n {
+            output.extend_from_slice(&self.block(counter));
+            counter += 1;
+        }
+        output.truncate(n);
+
+        self.key = self.block(counter).into();
+        output
+    }
+
+    /// Forces the pending pool contents into the key regardless of the
+    /// threshold.
+    pub fn reseed(&mut self) {
+        let pool = self.pool.finalize_reset();
+        let mut hasher = Sha256::new();
+        hasher.update(self.key);
+        hasher.update(pool);
+        self.key = hasher.finalize().into();
+        self.pending_entropy_bits = 0.0;
+        self.reseed_count += 1;
+    }
+
+    pub fn is_seeded(&self) -> bool {
+        self.reseed_count > 0
+    }
+
+    /// Number of reseeds so far.
+    pub fn reseed_count(&self) -> u64 {
+        self.reseed_count
+    }
+
+    /// Min-entropy credited since the last reseed, in bits.
+    pub fn pending_entropy_bits(&self) -> f64 {
+        self.pending_entropy_bits
+    }
+
+    /// Writes a seed for the next run to `path`, like the Linux random seed
+    /// file.
+    ///
+    /// The seed is [extracted](Self::extract) rather than copied from the
+    /// pool, so it can't reveal earlier output and this pool never hands out
+    /// the same bytes. On Unix the file is created readable only by its owner.
+    ///
+    /// Anyone who reads the seed file can predict the state it seeds until
+    /// fresh entropy is mixed in, so keep it somewhere only this process can
+    /// read, and don't copy it between machines.
+    pub fn save(&mut self, path: impl AsRef<Path>) -> Result<(), RandomizeError> {
+        let seed = Sensitive::new(self.extract(SEED_FILE_BYTES));
+        write_seed_file(path.as_ref(), &seed)
+    }
+
+    /// Resumes from a seed file written by [`save`](Self::save), mixing in a
+    /// short fresh recording before anything can be extracted.
+    ///
+    /// See [`load_with`](Self::load_with).
+    #[cfg(feature = "microphone")]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RandomizeError> {
+        let fresh = record_random_data(LOAD_FRESH_DURATION, 8, LOAD_FRESH_BYTES)?;
+        EntropyPool::load_with(path, &fresh)
+    }
+
+    /// Resumes from a seed file written by [`save`](Self::save), mixing in
+    /// `fresh` entropy. A `fresh` with no [MCV min-entropy](min_entropy_mcv),
+    /// such as an empty or constant one, is rejected, since the seed alone
+    /// would replay the same state on every run that loads it.
+    ///
+    /// The seed and `fresh` are folded into the key at once, so the pool is
+    /// [seeded](Self::is_seeded) even if `fresh` is short; only `fresh`
+    /// counts as new entropy. The file is then overwritten with a new seed,
+    /// so a crash before the next [`save`](Self::save) can't replay it.
+    pub fn load_with(path: impl AsRef<Path>, fresh: &[u8]) -> Result<Self, RandomizeError> {
+        if min_entropy_mcv(fresh) == 0.0 {
+            return Err(RandomizeError::InvalidParameter(
+                "a seed file can't be resumed without fresh entropy".to_string(),
+            ));
+        }
+
+        let path = path.as_ref();
+        let contents = Sensitive::new(std::fs::read(path).map_err(RandomizeError::Io)?);
+        let seed = contents
+            .strip_prefix(SEED_FILE_MAGIC.as_slice())
+            .filter(|seed| seed.len() == SEED_FILE_BYTES)
+            .ok_or_else(|| {
+                RandomizeError::InvalidParameter(format!("{} is not a seed file", path.display()))
+            })?;
+
+        let mut pool = EntropyPool::new();
+        pool.pool.update(seed);
+        pool.stir(fresh);
+        if !pool.is_seeded() {
+            pool.reseed();
+        }
+        pool.save(path)?;
+        Ok(pool)
+    }
+
+    fn block(&self, counter: u64) -> sha2::digest::Output<Sha256> {
+        let mut hasher = Sha256::new();
+        hasher.update(self.key);
+        hasher.update(counter.to_be_bytes());
+        hasher.finalize()
+    }
+}
+
+fn write_seed_file(path: &Path, seed: &[u8]) -> Result<(), RandomizeError> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path).map_err(RandomizeError::Io)?;
+    file.write_all(SEED_FILE_MAGIC)
+        .and_then(|()| file.write_all(seed))
+        .and_then(|()| file.sync_all())
+        .map_err(RandomizeError::Io)
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for EntropyPool {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.key);
+    }
+}
+
+/// Shows the reseed state, never the key.
+impl fmt::Debug for EntropyPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EntropyPool")
+            .field("pending_entropy_bits", &self.pending_entropy_bits)
+            .field("reseed_threshold_bits", &self.reseed_threshold_bits)
+            .field("reseed_count", &self.reseed_count)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for EntropyPool {
+    fn default() -> Self {
+        EntropyPool::new()
+    }
+}
@@ -0,0 +1,620 @@
+use num_complex::Complex;
+
+use crate::fft::{forward_fft, inverse_fft};
+
+/// Subtracts the mean from every sample. An empty buffer is left alone
+/// with a warning.
+pub fn remove_dc_offset(samples: &mut [f32]) {
+    if samples.is_empty() {
+        log::warn!("no samples, skipping DC offset removal");
+        return;
+    }
+    let mean: f32 = samples.iter().sum::<f32>() / samples.len() as f32;
+    samples.iter_mut().for_each(|sample| *sample -= mean);
+}
+
+// Peaks below this are treated as silence (e.g. a muted or blocked microphone).
+const SILENCE_THRESHOLD: f32 = 1e-9;
+
+/// Scales the samples so the largest magnitude equals `max_level`.
+///
+/// A near-silent or empty buffer is left untouched with a warning, since
+/// scaling it would turn every sample into `inf`/`NaN`.
+pub fn normalize_audio(samples: &mut [f32], max_level: f32) {
+    normalize_audio_to(samples, max_level, NormalizeMode::Peak);
+}
+
+/// The level [`normalize_audio_to`] sets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NormalizeMode {
+    /// The largest magnitude.
+    #[default]
+    Peak,
+    /// The root mean square. A few loud transients then don't squash the
+    /// rest of the signal, but peaks can exceed the target.
+    Rms,
+}
+
+/// Scales the samples so their level, as measured by `mode`, equals
+/// `target`. Silence is handled as in [`normalize_audio`].
+pub fn normalize_audio_to(samples: &mut [f32], target: f32, mode: NormalizeMode) {
+    if samples.is_empty() {
+        log::warn!("no samples, skipping normalization");
+        return;
+    }
+    let level = match mode {
+        NormalizeMode::Peak => samples
+            .iter()
+            .cloned()
+            .map(f32::abs)
+            .fold(f32::MIN, f32::max),
+        NormalizeMode::Rms => {
+            let sum_of_squares: f64 = samples.iter().map(|&sample| (sample as f64).powi(2)).sum();
+            (sum_of_squares / samples.len() as f64).sqrt() as f32
+        }
+    };
+    if level.is_nan() || level < SILENCE_THRESHOLD {
+        log::warn!("recording is silent, skipping normalization (check microphone permissions)");
+        return;
+    }
+    let normalization_factor = target / level;
+    samples
+        .iter_mut()
+        .for_each(|sample| *sample *= normalization_factor);
+}
+
+/// [`remove_dc_offset`] followed by [`normalize_audio`], with the mean and
+/// peak gathered in one pass and both corrections applied in a second.
+pub fn center_and_normalize(samples: &mut [f32], max_level: f32) {
+    if samples.is_empty() {
+        log::warn!("no samples, skipping normalization");
+        return;
+    }
+    let (sum, min, max) = sum_min_max(samples);
+    let mean = sum / samples.len() as f32;
+    let max_sample = (max - mean).abs().max((min - mean).abs());
+    if max_sample < SILENCE_THRESHOLD {
+        log::warn!("recording is silent, skipping normalization (check microphone permissions)");
+        samples.iter_mut().for_each(|sample| *sample -= mean);
+        return;
+    }
+    offset_and_scale(samples, mean, max_level / max_sample);
+}
+
+// Independent accumulators per lane let the compiler vectorize the pass.
+const LANES: usize = 16;
+
+#[cfg(not(feature = "simd"))]
+fn sum_min_max(samples: &[f32]) -> (f32, f32, f32) {
+    let mut sums = [0.0f32; LANES];
+    let mut mins = [f32::MAX; LANES];
+    let mut maxs = [f32::MIN; LANES];
+    let mut chunks = samples.chunks_exact(LANES);
+    for chunk in &mut chunks {
+        let chunk: &[f32; LANES] = chunk.try_into().unwrap();
+        for lane in 0..LANES {
+            let sample = chunk[lane];
+            sums[lane] += sample;
+            mins[lane] = if sample < mins[lane] {
+                sample
+            } else {
+                mins[lane]
+            };
+            maxs[lane] = if sample > maxs[lane] {
+                sample
+            } else {
+                maxs[lane]
+            };
+        }
+    }
+    combine_lanes(&sums, &mins, &maxs, chunks.remainder())
+}
+
+#[cfg(feature = "simd")]
+fn sum_min_max(samples: &[f32]) -> (f32, f32, f32) {
+    use wide::f32x8;
+
+    let mut sums = [f32x8::ZERO; LANES / 8];
+    let mut mins = [f32x8::splat(f32::MAX); LANES / 8];
+    let mut maxs = [f32x8::splat(f32::MIN); LANES / 8];
+    let mut chunks = samples.chunks_exact(LANES);
+    for chunk in &mut chunks {
+        for (i, lanes) in chunk.chunks_exact(8).enumerate() {
+            let lanes = f32x8::from(lanes);
+            sums[i] += lanes;
+            mins[i] = mins[i].fast_min(lanes);
+            maxs[i] = maxs[i].fast_max(lanes);
+        }
+    }
+    let flatten = |vectors: &[f32x8]| -> Vec<f32> {
+        vectors
+            .iter()
+            .flat_map(|vector| vector.to_array())
+            .collect()
+    };
+    combine_lanes(
+        &flatten(&sums),
+        &flatten(&mins),
+        &flatten(&maxs),
+        chunks.remainder(),
+    )
+}
+
+fn combine_lanes(sums: &[f32], mins: &[f32], maxs: &[f32], remainder: &[f32]) -> (f32, f32, f32) {
+    let sum = sums.iter().chain(remainder).sum();
+    let min = mins
+        .iter()
+        .chain(remainder)
+        .copied()
+        .fold(f32::MAX, f32::min);
+    let max = maxs
+        .iter()
+        .chain(remainder)
+        .copied()
+        .fold(f32::MIN, f32::max);
+    (sum, min, max)
+}
+
+#[cfg(not(feature = "simd"))]
+fn offset_and_scale(samples: &mut [f32], offset: f32, factor: f32) {
+    samples
+        .iter_mut()
+        .for_each(|sample| *sample = (*sample - offset) * factor);
+}
+
+#[cfg(feature = "simd")]
+fn offset_and_scale(samples: &mut [f32], offset: f32, factor: f32) {
+    use wide::f32x8;
+
+    let (offset_lanes, factor_lanes) = (f32x8::splat(offset), f32x8::splat(factor));
+    let mut chunks = samples.chunks_exact_mut(8);
+    for chunk in &mut chunks {
+        let lanes = f32x8::from(&*chunk);
+        let scaled: [f32; 8] = ((lanes - offset_lanes) * factor_lanes).into();
+        chunk.copy_from_slice(&scaled);
+    }
+    chunks
+        .into_remainder()
+        .iter_mut()
+        .for_each(|sample| *sample = (*sample - offset) * factor);
+}
+
+// Samples at or above this magnitude count as clipped; converters rarely
+// reach exactly ±1.0 when they saturate.
+const CLIPPING_LEVEL: f32 = 0.99;
+
+/// Fraction of samples at or near full scale (±1.0). Clipped samples have
+/// saturated LSBs that carry almost no entropy.
+pub fn clipping_ratio(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let clipped = samples
+        .iter()
+        .filter(|sample| sample.abs() >= CLIPPING_LEVEL)
+        .count();
+    clipped as f32 / samples.len() as f32
+}
+
+/// Moments of the sample distribution, see [`noise_shape_metrics`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoiseMetrics {
+    pub mean: f64,
+    pub variance: f64,
+    pub skewness: f64,
+    /// Fourth standardized moment: 3 for Gaussian noise, 1.5 for a pure
+    /// tone, large for impulsive interference.
+    pub kurtosis: f64,
+}
+
+impl NoiseMetrics {
+    /// Whether the kurtosis is more than `max_deviation` from a Gaussian's,
+    /// suggesting the input isn't dominated by thermal noise.
+    pub fn looks_non_thermal(&self, max_deviation: f64) -> bool {
+        (self.kurtosis - 3.0).abs() > max_deviation
+    }
+}
+
+/// Mean, variance and the standardized skewness and kurtosis of `samples`.
+/// Thermal noise is close to Gaussian; a tone or clipping is not. The shape
+/// moments are `NaN` for constant input, and everything is `NaN` for empty
+/// input.
+pub fn noise_shape_metrics(samples: &[f32]) -> NoiseMetrics {
+    let n = samples.len() as f64;
+    let mean = samples.iter().map(|&sample| sample as f64).sum::<f64>() / n;
+    let moment = |power: i32| {
+        samples
+            .iter()
+            .map(|&sample| (sample as f64 - mean).powi(power))
+            .sum::<f64>()
+            / n
+    };
+    let variance = moment(2);
+    NoiseMetrics {
+        mean,
+        variance,
+        skewness: moment(3) / variance.powf(1.5),
+        kurtosis: moment(4) / (variance * variance),
+    }
+}
+
+/// Window of [`detect_agc_drift`] and [`sliding_normalize`], in samples:
+/// 50 ms at 44.1 kHz, short next to the second or more an AGC takes to
+/// adjust.
+pub const DEFAULT_AGC_WINDOW: usize = 2205;
+
+/// Ratio of the loudest to the quietest smoothed window level above which
+/// [`detect_agc_drift`] reports drift, about 6 dB.
+pub const AGC_DRIFT_RATIO: f64 = 2.0;
+
+// Window levels averaged into each point of the envelope `detect_agc_drift`
+// compares, so that only slow changes count.
+const AGC_SMOOTHING_WINDOWS: usize = 8;
+
+/// RMS level of each block of `window` samples. A trailing partial block is
+/// dropped.
+pub fn window_rms(samples: &[f32], window: usize) -> Vec<f64> {
+    samples
+        .chunks_exact(window.max(1))
+        .map(|block| {
+            let energy = block
+                .iter()
+                .map(|&sample| sample as f64 * sample as f64)
+                .sum::<f64>();
+            (energy / block.len() as f64).sqrt()
+        })
+        .collect()
+}
+
+/// Slow level changes found by [`detect_agc_drift`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AgcDrift {
+    /// Lowest and highest smoothed window RMS.
+    pub min_rms: f64,
+    pub max_rms: f64,
+    /// Whether `max_rms / min_rms` exceeds [`AGC_DRIFT_RATIO`].
+    pub detected: bool,
+}
+
+impl AgcDrift {
+    pub fn ratio(&self) -> f64 {
+        self.max_rms / self.min_rms
+    }
+}
+
+/// Looks for the slow level changes automatic gain control leaves in a
+/// recording: the [`window_rms`] levels are averaged over 8 windows at a
+/// time, and the spread of that envelope is compared with
+/// [`AGC_DRIFT_RATIO`]. Noise fluctuates too little from window to window
+/// to trip it. Recordings shorter than 8 windows report no drift.
+pub fn detect_agc_drift(samples: &[f32], window: usize) -> AgcDrift {
+    let levels = window_rms(samples, window);
+    let (min_rms, max_rms) = levels
+        .windows(AGC_SMOOTHING_WINDOWS)
+        .map(|levels| levels.iter().sum::<f64>() / levels.len() as f64)
+        .fold((f64::INFINITY, 0.0f64), |(min, max), level| {
+            (min.min(level), max.max(level))
+        });
+    if min_rms.is_infinite() {
+        return AgcDrift {
+            min_rms: 0.0,
+            max_rms: 0.0,
+            detected: false,
+        };
+    }
+    AgcDrift {
+        min_rms,
+        max_rms,
+        // A silent stretch between louder ones counts as drift, silence
+        // throughout doesn't.
+        detected: max_rms > AGC_DRIFT_RATIO * min_rms,
+    }
+}
+
+/// Divides every sample by the RMS level of the `window` samples centred on
+/// it, undoing slow gain changes such as [`detect_agc_drift`] finds, where
+/// [`normalize_audio`] would only scale the whole buffer. Near the ends the
+/// window is cut short. Silent stretches are left untouched.
+pub fn sliding_normalize(samples: &mut [f32], window: usize) {
+    let mut energy = Vec::with_capacity(samples.len() + 1);
+    energy.push(0.0f64);
+    for &sample in samples.iter() {
+        energy.push(energy[energy.len() - 1] + sample as f64 * sample as f64);
+    }
+
+    let half = window.max(1) / 2;
+    for (i, sample) in samples.iter_mut().enumerate() {
+        let start = i.saturating_sub(half);
+        let end = (i + half + 1).min(energy.len() - 1);
+        let rms = ((energy[end] - energy[start]) / (end - start) as f64).sqrt();
+        if rms > SILENCE_THRESHOLD as f64 {
+            *sample = (*sample as f64 / rms) as f32;
+        }
+    }
+}
+
+/// Cutoff that keeps most of the noise band while cutting into mains hum.
+pub const DEFAULT_HIGH_PASS_CUTOFF_HZ: f32 = 80.0;
+
+// Q factors of the two second-order sections of a 4th-order Butterworth filter.
+const BUTTERWORTH_Q: [f64; 2] = [0.541_196_1, 1.306_563];
+
+/// 4th-order Butterworth high-pass filter, applied in place.
+///
+/// Removes DC and attenuates 50/60 Hz hum, which would otherwise leave
+/// periodic structure in the extracted LSBs.
+pub fn high_pass_filter(samples: &mut [f32], sample_rate: u32, cutoff_hz: f32) {
+    let w0 = 2.0 * std::f64::consts::PI * cutoff_hz as f64 / sample_rate as f64;
+    let (sin_w0, cos_w0) = w0.sin_cos();
+
+    for q in BUTTERWORTH_Q {
+        let alpha = sin_w0 / (2.0 * q);
+        let a0 = 1.0 + alpha;
+        let b0 = (1.0 + cos_w0) / 2.0 / a0;
+        let b1 = -(1.0 + cos_w0) / a0;
+        let b2 = b0;
+        let a1 = -2.0 * cos_w0 / a0;
+        let a2 = (1.0 - alpha) / a0;
+
+        let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+        for sample in samples.iter_mut() {
+            let x0 = *sample as f64;
+            let y0 = b0 * x0 + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
+            x2 = x1;
+            x1 = x0;
+            y2 = y1;
+            y1 = y0;
+            *sample = y0 as f32;
+        }
+    }
+}
+
+/// Window size that resolves the spectrum finely enough at 44.1 kHz while
+/// still adapting to changes in the noise floor.
+pub const DEFAULT_SPECTRAL_WHITEN_WINDOW: usize = 1024;
+
+/// Flattens the spectrum in place: every frequency bin is scaled to unit
+/// magnitude while its phase is kept, which removes the coloring that
+/// audio noise otherwise leaves in the extracted bits.
+///
+/// Frames of `window_size` samples overlap by half and are weighted with a
+/// square-root Hann window on both analysis and synthesis, so the frames
+/// add back up to the whole signal. `window_size` should be even; a power
+/// of two keeps the FFTs fast. The result is rescaled to the input's RMS
+/// level. Buffers shorter than one window are left untouched.
+pub fn spectral_whiten(samples: &mut [f32], window_size: usize) {
+    if window_size < 2 || samples.len() < window_size {
+        return;
+    }
+    let hop = window_size / 2;
+    let window: Vec<f64> = (0..window_size)
+        .map(|i| (std::f64::consts::PI * i as f64 / window_size as f64).sin())
+        .collect();
+
+    // Padding by a hop in front makes every sample fall into two frames.
+    let mut padded = vec![0.0f64; hop + samples.len() + window_size];
+    for (padded, &sample) in padded[hop..].iter_mut().zip(samples.iter()) {
+        *padded = sample as f64;
+    }
+    let mut whitened = vec![0.0f64; padded.len()];
+    let mut frame = vec![Complex::new(0.0f64, 0.0); window_size];
+
+    for start in (0..hop + samples.len()).step_by(hop) {
+        for ((bin, &sample), &weight) in frame.iter_mut().zip(&padded[start..]).zip(&window) {
+            *bin = Complex::new(sample * weight, 0.0);
+        }
+        forward_fft(&mut frame);
+        for bin in frame.iter_mut() {
+            let magnitude = bin.norm();
+            *bin = if magnitude > SILENCE_THRESHOLD as f64 {
+                *bin / magnitude
+            } else {
+                Complex::new(0.0, 0.0)
+            };
+        }
+        inverse_fft(&mut frame);
+        for ((output, bin), &weight) in whitened[start..].iter_mut().zip(&frame).zip(&window) {
+            *output += bin.re / window_size as f64 * weight;
+        }
+    }
+
+    let whitened = &whitened[hop..hop + samples.len()];
+    let input_rms = rms(samples.iter().map(|&sample| sample as f64));
+    let output_rms = rms(whitened.iter().copied());
+    let gain = if output_rms > 0.0 {
+        input_rms / output_rms
+    } else {
+        0.0
+    };
+    for (sample, &output) in samples.iter_mut().zip(whitened) {
+        *sample = (output * gain) as f32;
+    }
+}
+
+/// Magnitudes of the `window.len() / 2 + 1` frequency bins of `window`,
+/// from DC to Nyquist, after a Hann window. Bin `k` is centred on
+/// `k * sample_rate / window.len()` Hz, and a sine of amplitude `a` on a
+/// bin's centre reads `a` there.
+pub fn magnitude_spectrum(window: &[f32]) -> Vec<f32> {
+    let n = window.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    let mut bins: Vec<Complex<f64>> = window
+        .iter()
+        .enumerate()
+        .map(|(i, &sample)| {
+            let weight = 0.5 - 0.5 * (2.0 * std::f64::consts::PI * i as f64 / n as f64).cos();
+            Complex::new(sample as f64 * weight, 0.0)
+        })
+        .collect();
+    forward_fft(&mut bins);
+    // The Hann window halves a sine's amplitude and the sine splits it
+    // between two mirrored bins.
+    bins[..n / 2 + 1]
+        .iter()
+        .map(|bin| (bin.norm() * 4.0 / n as f64) as f32)
+        .collect()
+}
+
+/// [`magnitude_spectrum`] averaged over consecutive windows of
+/// `window_size` samples; empty if `samples` is shorter than one window.
+pub fn average_spectrum(samples: &[f32], window_size: usize) -> Vec<f32> {
+    let windows = samples.len().checked_div(window_size).unwrap_or(0);
+    if windows == 0 {
+        return Vec::new();
+    }
+    let mut average = vec![0.0; window_size / 2 + 1];
+    for window in samples.chunks_exact(window_size) {
+        for (average, magnitude) in average.iter_mut().zip(magnitude_spectrum(window)) {
+            *average += magnitude / windows as f32;
+        }
+    }
+    average
+}
+
+fn rms(values: impl ExactSizeIterator<Item = f64>) -> f64 {
+    let count = values.len();
+    (values.map(|value| value * value).sum::<f64>() / count as f64).sqrt()
+}
+
+/// Order of the linear predictor [`decorrelate`] fits.
+pub const DECORRELATION_ORDER: usize = 4;
+
+/// Replaces the samples with the residual of a linear predictor fitted to
+/// them, removing the correlation between neighbouring samples.
+///
+/// The [`DECORRELATION_ORDER`] coefficients come from the signal's own
+/// autocorrelation via the Levinson-Durbin recursion, and each output is the
+/// sample minus its prediction from the preceding ones. The first samples
+/// have no full history and are dropped, so the buffer shrinks by the order.
+/// Silent buffers are left untouched.
+pub fn decorrelate(samples: &mut Vec<f32>) {
+    let order = DECORRELATION_ORDER.min(samples.len().saturating_sub(1));
+    let autocorrelation: Vec<f64> = (0..=order)
+        .map(|lag| {
+            samples
+                .iter()
+                .zip(&samples[lag..])
+                .map(|(&a, &b)| a as f64 * b as f64)
+                .sum()
+        })
+        .collect();
+    if order == 0 || autocorrelation[0] <= 0.0 {
+        return;
+    }
+
+    // coefficients[j - 1] weighs the sample j steps back.
+    let mut coefficients = vec![0.0f64; order];
+    let mut error = autocorrelation[0];
+    for i in 1..=order {
+        let predicted: f64 = (1..i)
+            .map(|j| coefficients[j - 1] * autocorrelation[i - j])
+            .sum();
+        let reflection = (autocorrelation[i] - predicted) / error;
+        let previous = coefficients.clone();
+        coefficients[i - 1] = reflection;
+        for j in 1..i {
+            coefficients[j - 1] = previous[j - 1] - reflection * previous[i - j - 1];
+        }
+        error *= 1.0 - reflection * reflection;
+        if error <= 0.0 {
+            break;
+        }
+    }
+
+    // Each residual overwrites the oldest sample its prediction used.
+    for n in order..samples.len() {
+        let prediction: f64 = coefficients
+            .iter()
+            .enumerate()
+            .map(|(j, &coefficient)| coefficient * samples[n - j - 1] as f64)
+            .sum();
+        samples[n - order] = (samples[n] as f64 - prediction) as f32;
+    }
+    samples.truncate(samples.len() - order);
+}
+
+/// Splits interleaved samples into one buffer per channel. A trailing
+/// partial frame is dropped.
+pub fn deinterleave(samples: &[f32], channels: u16) -> Vec<Vec<f32>> {
+    let channels = channels.max(1) as usize;
+    (0..channels)
+        .map(|channel| {
+            samples
+                .chunks_exact(channels)
+                .map(|frame| frame[channel])
+                .collect()
+        })
+        .collect()
+}
+
+/// Averages each frame of interleaved samples into one mono sample. A
+/// trailing partial frame is dropped.
+pub fn downmix(samples: &[f32], channels: u16) -> Vec<f32> {
+    let channels = channels.max(1) as usize;
+    samples
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect()
+}
+
+/// Per-frame difference `first - second` between two channels.
+pub fn channel_difference(first: &[f32], second: &[f32]) -> Vec<f32> {
+    first.iter().zip(second).map(|(a, b)| a - b).collect()
+}
+
+/// Absolute [`channel_cross_correlation`] above which the collector's
+/// channel difference rejects both channels as coming from one ADC.
+pub const MAX_CHANNEL_CORRELATION: f32 = 0.9;
+
+/// Pearson correlation between two channels over the frames they share,
+/// from -1 to 1. Independent ADCs give about 0; a device that copies one
+/// ADC to both channels gives 1, and so does a pair in which either
+/// channel is constant.
+pub fn channel_cross_correlation(left: &[f32], right: &[f32]) -> f32 {
+    let length = left.len().min(right.len());
+    if length == 0 {
+        return 1.0;
+    }
+    let (left, right) = (&left[..length], &right[..length]);
+    let mean = |channel: &[f32]| channel.iter().map(|&x| x as f64).sum::<f64>() / length as f64;
+    let (mean_left, mean_right) = (mean(left), mean(right));
+
+    let (mut covariance, mut variance_left, mut variance_right) = (0.0, 0.0, 0.0);
+    for (&l, &r) in left.iter().zip(right) {
+        let (l, r) = (l as f64 - mean_left, r as f64 - mean_right);
+        covariance += l * r;
+        variance_left += l * l;
+        variance_right += r * r;
+    }
+    let variance = variance_left * variance_right;
+    if variance == 0.0 {
+        return 1.0;
+    }
+    (covariance / variance.sqrt()) as f32
+}
+
+/// Scales signed 16-bit samples to [-1, 1).
+pub fn i16_to_f32(samples: &[i16]) -> Vec<f32> {
+    samples
+        .iter()
+        .map(|&sample| i16_sample_to_f32(sample))
+        .collect()
+}
+
+/// Scales unsigned 16-bit samples, centred on 32768, to [-1, 1).
+pub fn u16_to_f32(samples: &[u16]) -> Vec<f32> {
+    samples
+        .iter()
+        .map(|&sample| u16_sample_to_f32(sample))
+        .collect()
+}
+
+pub(crate) fn i16_sample_to_f32(sample: i16) -> f32 {
+    sample as f32 / 32768.0
+}
+
+pub(crate) fn u16_sample_to_f32(sample: u16) -> f32 {
+    (sample as f32 - 32768.0) / 32768.0
+}
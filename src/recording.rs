@@ -0,0 +1,318 @@
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use ringbuf::traits::{Consumer, Observer, Producer, Split};
+use ringbuf::HeapRb;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::error::RandomizeError;
+use crate::processing::{i16_sample_to_f32, u16_sample_to_f32};
+use crate::source::Audio;
+
+pub const SAMPLE_RATE: u32 = 44100;
+// const CHANNELS: u16 = 1;
+pub const RECORD_DURATION_SECS: u64 = 2;
+/// Leading audio the collector drops from each recording, long enough for the
+/// DC settling and AGC ramp of typical input devices.
+pub const DEFAULT_WARMUP: Duration = Duration::from_millis(50);
+// const RECORDING_FILE: &str = "recording.wav";
+
+// How long past the requested duration a recording waits for its buffer to
+// fill, covering the stream's start-up latency.
+pub(crate) const FILL_GRACE: Duration = Duration::from_millis(500);
+
+enum RecordingEvent {
+    Full,
+    Failed(cpal::StreamError),
+}
+
+/// Names of all input devices on the default host, in enumeration order.
+pub fn list_input_devices() -> Vec<String> {
+    cpal::default_host()
+        .input_devices()
+        .map(|devices| devices.filter_map(|device| device.name().ok()).collect())
+        .unwrap_or_default()
+}
+
+/// Finds the first input device whose name contains `name`, ignoring case.
+pub(crate) fn find_input_device(name: &str) -> Result<cpal::Device, RandomizeError> {
+    let needle = name.to_lowercase();
+    cpal::default_host()
+        .input_devices()
+        .map_err(RandomizeError::Devices)?
+        .find(|device| {
+            device
+                .name()
+                .is_ok_and(|device_name| device_name.to_lowercase().contains(&needle))
+        })
+        .ok_or_else(|| RandomizeError::DeviceNotFound {
+            requested: name.to_string(),
+            available: list_input_devices(),
+        })
+}
+
+/// The device that captures what the system plays, and how to record it.
+///
+/// On Windows, WASAPI records the default output device in loopback mode
+/// with its output config. On Linux, the first input device with "monitor"
+/// in its name is used, as PulseAudio and PipeWire name the monitor sources
+/// they expose to ALSA. Other platforms have no loopback capture.
+#[cfg(target_os = "windows")]
+pub(crate) fn loopback_device(
+) -> Result<(cpal::Device, Option<cpal::SupportedStreamConfig>), RandomizeError> {
+    let output_device = cpal::default_host()
+        .default_output_device()
+        .ok_or_else(|| RandomizeError::LoopbackUnavailable("no output device".to_string()))?;
+    let output_config = output_device
+        .default_output_config()
+        .map_err(RandomizeError::DefaultConfig)?;
+    Ok((output_device, Some(output_config)))
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn loopback_device(
+) -> Result<(cpal::Device, Option<cpal::SupportedStreamConfig>), RandomizeError> {
+    find_input_device("monitor")
+        .map(|device| (device, None))
+        .map_err(|_| {
+            RandomizeError::LoopbackUnavailable(
+                "no monitor input device; expose one through PulseAudio or PipeWire".to_string(),
+            )
+        })
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub(crate) fn loopback_device(
+) -> Result<(cpal::Device, Option<cpal::SupportedStreamConfig>), RandomizeError> {
+    Err(RandomizeError::LoopbackUnavailable(
+        "not supported on this platform".to_string(),
+    ))
+}
+
+/// Returns the input device at `index` in [`list_input_devices`] order.
+pub(crate) fn input_device_at(index: usize) -> Result<cpal::Device, RandomizeError> {
+    cpal::default_host()
+        .input_devices()
+        .map_err(RandomizeError::Devices)?
+        .nth(index)
+        .ok_or_else(|| RandomizeError::DeviceNotFound {
+            requested: format!("#{}", index),
+            available: list_input_devices(),
+        })
+}
+
+/// Records `duration` worth of samples from the default input device.
+pub fn record_samples(duration: Duration) -> Result<Vec<f32>, RandomizeError> {
+    let host = cpal::default_host();
+    let input_device = host
+        .default_input_device()
+        .ok_or(RandomizeError::NoInputDevice)?;
+
+    let default_config = input_device
+        .default_input_config()
+        .map_err(RandomizeError::DefaultConfig);
+    with_usable_config(&input_device, default_config, |input_config| {
+        record_from_device(
+            &input_device,
+            input_config,
+            cpal::BufferSize::Default,
+            duration,
+            None,
+        )
+    })
+    .map(|(audio, _)| audio.samples)
+}
+
+/// Runs `start` with `preferred`, or, if there is no such config or no
+/// stream can be built with it, with each config `input_device` supports
+/// until one builds: f32 first, then i16, then u16, at 44.1 kHz where the
+/// range allows it. Returns what `start` did and the config it used; if
+/// nothing builds, the error from `preferred`.
+pub(crate) fn with_usable_config<T>(
+    input_device: &cpal::Device,
+    preferred: Result<cpal::SupportedStreamConfig, RandomizeError>,
+    mut start: impl FnMut(&cpal::SupportedStreamConfig) -> Result<T, RandomizeError>,
+) -> Result<(T, cpal::SupportedStreamConfig), RandomizeError> {
+    let first_error = match preferred {
+        Ok(config) => match start(&config) {
+            Err(err @ RandomizeError::StreamBuild(_)) => err,
+            result => return result.map(|value| (value, config)),
+        },
+        Err(err) => err,
+    };
+
+    let mut ranges = list_supported_configs(input_device);
+    ranges.sort_by_key(|range| match range.sample_format() {
+        cpal::SampleFormat::F32 => 0,
+        cpal::SampleFormat::I16 => 1,
+        cpal::SampleFormat::U16 => 2,
+    });
+    for range in ranges {
+        let sample_rate = cpal::SampleRate(SAMPLE_RATE);
+        let config =
+            if range.min_sample_rate() <= sample_rate && sample_rate <= range.max_sample_rate() {
+                range.with_sample_rate(sample_rate)
+            } else {
+                range.with_max_sample_rate()
+            };
+        match start(&config) {
+            Err(RandomizeError::StreamBuild(_)) => continue,
+            result => {
+                log::warn!(
+                    "the default input config is unusable ({}), using {} channels of {:?} at {} Hz",
+                    first_error,
+                    config.channels(),
+                    config.sample_format(),
+                    config.sample_rate().0
+                );
+                return result.map(|value| (value, config));
+            }
+        }
+    }
+    Err(first_error)
+}
+
+/// Every stream config range `device` supports for input; empty if they
+/// can't be queried.
+pub fn list_supported_configs(device: &cpal::Device) -> Vec<cpal::SupportedStreamConfigRange> {
+    device
+        .supported_input_configs()
+        .map(|configs| configs.collect())
+        .unwrap_or_default()
+}
+
+/// Builds an input stream in the device's native sample format, handing
+/// `on_samples` the interleaved samples converted to `f32`.
+pub(crate) fn build_input_stream(
+    input_device: &cpal::Device,
+    input_config: &cpal::SupportedStreamConfig,
+    buffer_size: cpal::BufferSize,
+    on_samples: impl FnMut(&[f32]) + Send + 'static,
+    on_error: impl FnMut(cpal::StreamError) + Send + 'static,
+) -> Result<cpal::Stream, RandomizeError> {
+    let config = cpal::StreamConfig {
+        buffer_size,
+        ..input_config.config()
+    };
+    match input_config.sample_format() {
+        cpal::SampleFormat::F32 => {
+            let mut on_samples = on_samples;
+            input_device.build_input_stream(
+                &config,
+                move |data: &[f32], _: &cpal::InputCallbackInfo| on_samples(data),
+                on_error,
+            )
+        }
+        cpal::SampleFormat::I16 => input_device.build_input_stream(
+            &config,
+            converting(on_samples, i16_sample_to_f32),
+            on_error,
+        ),
+        cpal::SampleFormat::U16 => input_device.build_input_stream(
+            &config,
+            converting(on_samples, u16_sample_to_f32),
+            on_error,
+        ),
+    }
+    .map_err(RandomizeError::StreamBuild)
+}
+
+// Wraps `on_samples` in a data callback for integer samples, converting
+// through a buffer that is reused across callbacks.
+fn converting<T: Copy>(
+    mut on_samples: impl FnMut(&[f32]),
+    convert: fn(T) -> f32,
+) -> impl FnMut(&[T], &cpal::InputCallbackInfo) {
+    let mut buffer = Vec::new();
+    move |data: &[T], _: &cpal::InputCallbackInfo| {
+        buffer.clear();
+        buffer.extend(data.iter().map(|&sample| convert(sample)));
+        on_samples(&buffer);
+    }
+}
+
+// Returns the samples with the lengths of the callback buffers they arrived
+// in and the time between those callbacks. The callback pushes into
+// lock-free rings sized for the request and signals when they are full; this
+// returns then, or with what arrived if they don't fill within `FILL_GRACE`
+// of `duration`. A stream error, such as the device disconnecting, aborts
+// the recording.
+pub(crate) fn record_from_device(
+    input_device: &cpal::Device,
+    input_config: &cpal::SupportedStreamConfig,
+    buffer_size: cpal::BufferSize,
+    duration: Duration,
+    max_samples: Option<usize>,
+) -> Result<Audio, RandomizeError> {
+    // Samples arrive interleaved, one per channel for every frame.
+    let sample_rate = input_config.sample_rate().0;
+    let channels = input_config.channels() as u32;
+    let record_duration_samples = (sample_rate as f64 * duration.as_secs_f64()) as u32 * channels;
+    let target = max_samples.map_or(record_duration_samples as usize, |max_samples| {
+        max_samples.min(record_duration_samples as usize)
+    });
+    if target == 0 {
+        return Err(RandomizeError::EmptyRecording);
+    }
+
+    let (mut samples_in, mut samples_out) = HeapRb::<f32>::new(target).split();
+    // Every buffer adds at least one sample, so `target` lengths always fit.
+    let (mut lengths_in, mut lengths_out) = HeapRb::<u32>::new(target).split();
+    let (mut intervals_in, mut intervals_out) = HeapRb::<Duration>::new(target).split();
+    let mut last_callback: Option<Instant> = None;
+    let (event_sender, events) = mpsc::channel();
+    let error_sender = event_sender.clone();
+
+    let input_stream = build_input_stream(
+        input_device,
+        input_config,
+        buffer_size,
+        move |data: &[f32]| {
+            if samples_in.is_full() {
+                return;
+            }
+            let now = Instant::now();
+            if let Some(last_callback) = last_callback {
+                let _ = intervals_in.try_push(now - last_callback);
+            }
+            last_callback = Some(now);
+            // Whatever the buffer size, only what still fits is taken; the
+            // rest of the buffer that fills the ring is dropped, and `Full`
+            // is sent once, by that callback.
+            let taken = samples_in.push_slice(data);
+            if taken > 0 {
+                let _ = lengths_in.try_push(taken as u32);
+            }
+            if samples_in.is_full() {
+                let _ = event_sender.send(RecordingEvent::Full);
+            }
+        },
+        move |err| {
+            // Only the first error is reported; the recording is over by then.
+            let _ = error_sender.send(RecordingEvent::Failed(err));
+        },
+    )?;
+
+    input_stream.play().map_err(RandomizeError::StreamPlay)?;
+    if let Ok(RecordingEvent::Failed(err)) = events.recv_timeout(duration + FILL_GRACE) {
+        return Err(RandomizeError::StreamFailed(err));
+    }
+    drop(input_stream);
+
+    let mut recording = vec![0.0; samples_out.occupied_len()];
+    samples_out.pop_slice(&mut recording);
+    let buffers = lengths_out
+        .pop_iter()
+        .map(|length| length as usize)
+        .collect();
+    if recording.is_empty() {
+        return Err(RandomizeError::EmptyRecording);
+    }
+    Ok(Audio {
+        samples: recording,
+        sample_rate,
+        channels: channels as u16,
+        buffers,
+        dropped_callbacks: 0,
+        callback_intervals: intervals_out.pop_iter().collect(),
+    })
+}
@@ -0,0 +1,282 @@
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use rand_chacha::ChaCha20Rng;
+use rand_core::{impls, CryptoRng, Error, RngCore, SeedableRng};
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::conditioning::whiten_sha256;
+use crate::entropy::entropy_capped_length;
+use crate::error::RandomizeError;
+use crate::extraction::Endianness;
+use crate::pipeline::record_random_data;
+use crate::recording::RECORD_DURATION_SECS;
+use crate::secure::Sensitive;
+
+const DEFAULT_CHUNK_DURATION_SECS: u64 = 1;
+const DEFAULT_NUM_LSB: u32 = 8;
+const DEFAULT_CHUNK_LENGTH: usize = 1024;
+// Raw bytes condensed into each thread's seed by `audio_rng`.
+const THREAD_SEED_LENGTH: usize = 256;
+// Fewest raw bytes the seeding functions extract: eight per byte of a
+// 32-byte seed.
+const MIN_SEED_RAW_LENGTH: usize = 256;
+
+thread_local! {
+    static THREAD_AUDIO_RNG: RefCell<Option<Rc<RefCell<ChaCha20Rng>>>> = const { RefCell::new(None) };
+}
+
+/// A random number generator backed by microphone noise.
+///
+/// Extracted bytes are buffered; once the buffer runs dry another chunk of
+/// audio is recorded and run through the same pipeline as the binary.
+pub struct AudioRng {
+    buffer: Vec<u8>,
+    chunk_duration: Duration,
+    num_lsb: u32,
+    chunk_length: usize,
+}
+
+impl AudioRng {
+    pub fn new() -> Self {
+        AudioRng::with_config(
+            Duration::from_secs(DEFAULT_CHUNK_DURATION_SECS),
+            DEFAULT_NUM_LSB,
+            DEFAULT_CHUNK_LENGTH,
+        )
+    }
+
+    /// `chunk_length` is the number of bytes extracted from each `chunk_duration` recording.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_length` is 0.
+    pub fn with_config(chunk_duration: Duration, num_lsb: u32, chunk_length: usize) -> Self {
+        assert!(chunk_length > 0, "the chunk length must be positive");
+        AudioRng {
+            buffer: Vec::new(),
+            chunk_duration,
+            num_lsb,
+            chunk_length,
+        }
+    }
+
+    fn refill(&mut self) -> Result<(), RandomizeError> {
+        let mut random_data =
+            record_random_data(self.chunk_duration, self.num_lsb, self.chunk_length)?;
+        // An empty chunk would have `try_fill_bytes` record forever.
+        if random_data.is_empty() {
+            return Err(RandomizeError::InsufficientData {
+                required_bits: self.chunk_length * 8,
+                available_bits: 0,
+            });
+        }
+
+        // Bytes are served from the back of the buffer.
+        random_data.reverse();
+        self.buffer = random_data;
+        Ok(())
+    }
+}
+
+/// Shows the configuration and how many bytes are buffered, never the
+/// bytes themselves.
+impl fmt::Debug for AudioRng {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AudioRng")
+            .field("chunk_duration", &self.chunk_duration)
+            .field("num_lsb", &self.num_lsb)
+            .field("chunk_length", &self.chunk_length)
+            .field("buffered", &format_args!("{} bytes", self.buffer.len()))
+            .finish()
+    }
+}
+
+impl Default for AudioRng {
+    fn default() -> Self {
+        AudioRng::new()
+    }
+}
+
+impl RngCore for AudioRng {
+    fn next_u32(&mut self) -> u32 {
+        impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.try_fill_bytes(dest).unwrap()
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        for byte in dest.iter_mut() {
+            while self.buffer.is_empty() {
+                self.refill().map_err(Error::new)?;
+            }
+            *byte = self.buffer.pop().unwrap();
+        }
+        Ok(())
+    }
+}
+
+/// Assembles a little-endian `u32` from the next 4 buffered bytes.
+pub fn random_u32(rng: &mut AudioRng) -> u32 {
+    random_u32_with(rng, Endianness::Little)
+}
+
+/// Assembles a `u32` in the given byte order from the next 4 buffered bytes.
+pub fn random_u32_with(rng: &mut AudioRng, endianness: Endianness) -> u32 {
+    let mut bytes = [0u8; 4];
+    rng.fill_bytes(&mut bytes);
+    match endianness {
+        Endianness::Little => LittleEndian::read_u32(&bytes),
+        Endianness::Big => BigEndian::read_u32(&bytes),
+    }
+}
+
+/// Assembles a little-endian `u64` from the next 8 buffered bytes.
+pub fn random_u64(rng: &mut AudioRng) -> u64 {
+    random_u64_with(rng, Endianness::Little)
+}
+
+/// Assembles a `u64` in the given byte order from the next 8 buffered bytes.
+pub fn random_u64_with(rng: &mut AudioRng, endianness: Endianness) -> u64 {
+    let mut bytes = [0u8; 8];
+    rng.fill_bytes(&mut bytes);
+    match endianness {
+        Endianness::Little => LittleEndian::read_u64(&bytes),
+        Endianness::Big => BigEndian::read_u64(&bytes),
+    }
+}
+
+/// Uniform double in [0, 1) built from 53 random mantissa bits.
+pub fn random_f64_unit(rng: &mut AudioRng) -> f64 {
+    (random_u64(rng) >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Seeds a ChaCha20 CSPRNG from one recording.
+///
+/// `output_length` raw bytes, and at least 256, are extracted from the audio
+/// and condensed by [`whiten_sha256`] into the 32-byte seed. Fails with
+/// [`RandomizeError::InsufficientData`] if their min-entropy doesn't justify
+/// the whole seed. The generator is hardware-seeded but fully deterministic
+/// afterwards, so it is fast enough for bulk output.
+pub fn seed_chacha20(output_length: usize) -> Result<ChaCha20Rng, RandomizeError> {
+    let raw = Sensitive::new(record_random_data(
+        Duration::from_secs(RECORD_DURATION_SECS),
+        DEFAULT_NUM_LSB,
+        MIN_SEED_RAW_LENGTH.max(output_length),
+    )?);
+    let mut seed = Sensitive::new(<ChaCha20Rng as SeedableRng>::Seed::default());
+    let justified = entropy_capped_length(&raw, seed.len());
+    if justified < seed.len() {
+        return Err(RandomizeError::InsufficientData {
+            required_bits: seed.len() * 8,
+            available_bits: justified * 8,
+        });
+    }
+    let conditioned = Sensitive::new(whiten_sha256(&raw, seed.len()));
+    seed.copy_from_slice(&conditioned);
+    Ok(ChaCha20Rng::from_seed(*seed))
+}
+
+/// Seeds any `rand` generator from one recording, like
+/// `SeedableRng::from_entropy`.
+///
+/// At least 256 raw bytes, and eight per seed byte for long seeds, are
+/// extracted and condensed by [`whiten_sha256`], whose counter mode
+/// stretches to seeds of any length. Fails with
+/// [`RandomizeError::InsufficientData`] if their min-entropy doesn't justify
+/// the whole seed.
+///
+/// ```no_run
+/// use rand::rngs::StdRng;
+///
+/// let rng: StdRng = randomize7::seed_std_rng()?;
+/// # Ok::<(), randomize7::RandomizeError>(())
+/// ```
+pub fn seed_std_rng<R: SeedableRng>() -> Result<R, RandomizeError> {
+    let mut seed = R::Seed::default();
+    let seed_length = seed.as_mut().len();
+    let raw = Sensitive::new(record_random_data(
+        Duration::from_secs(RECORD_DURATION_SECS),
+        DEFAULT_NUM_LSB,
+        MIN_SEED_RAW_LENGTH.max(seed_length * 8),
+    )?);
+    let justified = entropy_capped_length(&raw, seed_length);
+    if justified < seed_length {
+        return Err(RandomizeError::InsufficientData {
+            required_bits: seed_length * 8,
+            available_bits: justified * 8,
+        });
+    }
+    let conditioned = Sensitive::new(whiten_sha256(&raw, seed_length));
+    seed.as_mut().copy_from_slice(&conditioned);
+    Ok(R::from_seed(seed))
+}
+
+/// Handle to this thread's audio-seeded generator, like `rand::thread_rng()`.
+///
+/// The first call on a thread records audio once (about
+/// [`RECORD_DURATION_SECS`] seconds) to seed a ChaCha20 generator with
+/// [`seed_chacha20`]; later calls on the same thread return handles to the
+/// same generator without recording again. A failed recording is returned as
+/// an error and retried on the next call.
+///
+/// ```no_run
+/// use rand_core::RngCore;
+///
+/// let mut rng = randomize7::audio_rng()?;
+/// let value = rng.next_u64();
+/// # Ok::<(), randomize7::RandomizeError>(())
+/// ```
+pub fn audio_rng() -> Result<ThreadAudioRng, RandomizeError> {
+    THREAD_AUDIO_RNG.with(|slot| {
+        let mut slot = slot.borrow_mut();
+        let rng = match &*slot {
+            Some(rng) => rng.clone(),
+            None => slot
+                .insert(Rc::new(RefCell::new(seed_chacha20(THREAD_SEED_LENGTH)?)))
+                .clone(),
+        };
+        Ok(ThreadAudioRng { rng })
+    })
+}
+
+/// The handle [`audio_rng`] returns. It is tied to its thread and cannot be
+/// sent to another.
+#[derive(Clone)]
+pub struct ThreadAudioRng {
+    rng: Rc<RefCell<ChaCha20Rng>>,
+}
+
+/// Opaque: the ChaCha20 state is the key to all further output.
+impl fmt::Debug for ThreadAudioRng {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThreadAudioRng").finish_non_exhaustive()
+    }
+}
+
+impl RngCore for ThreadAudioRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.borrow_mut().next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.borrow_mut().next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.borrow_mut().fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.rng.borrow_mut().try_fill_bytes(dest)
+    }
+}
+
+impl CryptoRng for ThreadAudioRng {}
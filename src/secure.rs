@@ -0,0 +1,47 @@
+// What the `zeroize` feature does and doesn't protect is described in the
+// README's "Sensitive buffers" section.
+
+/// Compares two byte strings in time that depends only on their length, so
+/// a mismatch doesn't reveal where it occurs. Strings of different lengths
+/// are unequal at once; the length is not treated as secret.
+pub fn secure_compare(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let difference = a
+        .iter()
+        .zip(b)
+        .fold(0u8, |difference, (x, y)| difference | (x ^ y));
+    std::hint::black_box(difference) == 0
+}
+
+/// A buffer wiped on drop with the `zeroize` feature, and a plain wrapper
+/// without it.
+#[cfg(feature = "zeroize")]
+pub(crate) type Sensitive<T> = zeroize::Zeroizing<T>;
+
+#[cfg(not(feature = "zeroize"))]
+pub(crate) struct Sensitive<T>(T);
+
+#[cfg(not(feature = "zeroize"))]
+impl<T> Sensitive<T> {
+    pub(crate) fn new(value: T) -> Self {
+        Sensitive(value)
+    }
+}
+
+#[cfg(not(feature = "zeroize"))]
+impl<T> std::ops::Deref for Sensitive<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+#[cfg(not(feature = "zeroize"))]
+impl<T> std::ops::DerefMut for Sensitive<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
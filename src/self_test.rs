@@ -0,0 +1,122 @@
+use bitvec::prelude::*;
+
+use crate::statistical_tests::{
+    approximate_entropy_result_bits, berlekamp_massey, block_frequency_result_bits,
+    cumulative_sums_results_bits, longest_run_result_bits, monobit_result_bits,
+    non_overlapping_template_test_bits, runs_result_bits, serial_test_bits,
+};
+
+// The 100-bit example stream used throughout SP800-22 section 2.
+const EXAMPLE_100: &str = "1100100100001111110110101010001000100001011010001100001000110100110001001100011001100010100010111000";
+
+// SP800-22 section 2.4.4.
+const LONGEST_RUN_EXAMPLE: &str = "11001100000101010110110001001100111000000000001001001101010100010001001111010110100000001101011111001100111001101101100010110010";
+
+/// Reported values may differ from the published ones in the last digit.
+const TOLERANCE: f64 = 1e-6;
+
+/// One statistical test run against a published worked example.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelfTestResult {
+    pub name: &'static str,
+    /// Where the example and its expected value are published.
+    pub reference: &'static str,
+    pub expected: f64,
+    pub actual: f64,
+    pub passed: bool,
+}
+
+/// Runs the statistical tests themselves against the worked examples of
+/// NIST SP800-22, so a broken implementation is caught before it is
+/// trusted to judge a recording.
+///
+/// The spectral test is left out: the section 2.6 examples report peak
+/// counts that don't follow from their own spectra.
+pub fn self_test() -> Vec<SelfTestResult> {
+    let example = bits(EXAMPLE_100);
+    let [cusum_forward, cusum_backward] = cumulative_sums_results_bits(&example);
+    let (serial_1, serial_2) = serial_test_bits(&bits("0011011101"), 3);
+
+    vec![
+        check(
+            "monobit",
+            "SP800-22 2.1.8",
+            0.109599,
+            monobit_result_bits(&example).p_value,
+        ),
+        check(
+            "block_frequency",
+            "SP800-22 2.2.8",
+            0.706438,
+            block_frequency_result_bits(&example, 10).p_value,
+        ),
+        check(
+            "runs",
+            "SP800-22 2.3.8",
+            0.500798,
+            runs_result_bits(&example).map_or(f64::NAN, |result| result.p_value),
+        ),
+        check(
+            "longest_run",
+            "SP800-22 2.4.4",
+            0.180598,
+            longest_run_result_bits(&bits(LONGEST_RUN_EXAMPLE))
+                .map_or(f64::NAN, |result| result.p_value),
+        ),
+        check(
+            "non_overlapping_template",
+            "SP800-22 2.7.4",
+            0.344154,
+            non_overlapping_template_test_bits(
+                &bits("10100100101110010110"),
+                &[false, false, true],
+                10,
+            ),
+        ),
+        check(
+            "linear_complexity",
+            "SP800-22 2.10.4",
+            4.0,
+            berlekamp_massey(&bits("1101011110001")) as f64,
+        ),
+        check("serial_1", "SP800-22 2.11.4", 0.808792, serial_1),
+        check("serial_2", "SP800-22 2.11.4", 0.670320, serial_2),
+        check(
+            "approximate_entropy",
+            "SP800-22 2.12.8",
+            0.235301,
+            approximate_entropy_result_bits(&example, 2).p_value,
+        ),
+        check(
+            "cumulative_sums_forward",
+            "SP800-22 2.13.8",
+            0.219194,
+            cusum_forward.p_value,
+        ),
+        check(
+            "cumulative_sums_backward",
+            "SP800-22 2.13.8",
+            0.114866,
+            cusum_backward.p_value,
+        ),
+    ]
+}
+
+fn check(
+    name: &'static str,
+    reference: &'static str,
+    expected: f64,
+    actual: f64,
+) -> SelfTestResult {
+    SelfTestResult {
+        name,
+        reference,
+        expected,
+        actual,
+        passed: (actual - expected).abs() < TOLERANCE,
+    }
+}
+
+fn bits(s: &str) -> BitVec<u8, Msb0> {
+    s.chars().map(|c| c == '1').collect()
+}
@@ -0,0 +1,544 @@
+use rand_chacha::ChaCha8Rng;
+use rand_core::{RngCore, SeedableRng};
+#[cfg(feature = "microphone")]
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::RandomizeError;
+#[cfg(feature = "microphone")]
+use crate::recording::{
+    find_input_device, input_device_at, list_supported_configs, loopback_device,
+    record_from_device, with_usable_config, SAMPLE_RATE,
+};
+#[cfg(not(feature = "microphone"))]
+const SAMPLE_RATE: u32 = 44100;
+
+/// Interleaved samples together with their format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Audio {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Lengths of the buffers the samples were delivered in, in order, for
+    /// [`HealthPolicy::check_repeated_buffers`](crate::HealthPolicy::check_repeated_buffers).
+    /// Empty when the source has no such structure.
+    pub buffers: Vec<usize>,
+    /// Callbacks whose samples were discarded, in whole or in part, before
+    /// the recording was complete. The microphone buffers the whole
+    /// recording up front and never drops any; a device that delivers too
+    /// slowly just yields fewer samples.
+    pub dropped_callbacks: usize,
+    /// Time between consecutive callbacks, in order, for
+    /// [`EntropyCollector::callback_timing`](crate::EntropyCollector::callback_timing).
+    /// Empty when the source doesn't measure it.
+    pub callback_intervals: Vec<Duration>,
+}
+
+/// Anything that can record audio for an [`EntropyCollector`](crate::EntropyCollector).
+pub trait SampleSource: Send + Sync {
+    /// Records `duration` worth of interleaved samples.
+    fn record(&self, duration: Duration) -> Result<Audio, RandomizeError>;
+}
+
+/// The stream a [`Microphone`] recording ran with, which the device's
+/// defaults and config fallbacks may make differ from what was requested.
+#[cfg(feature = "microphone")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureConfig {
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// The device's native format, before conversion to `f32`.
+    pub sample_format: cpal::SampleFormat,
+    /// Frames per callback buffer requested from the host; `None` left it
+    /// to the host.
+    pub buffer_size: Option<u32>,
+}
+
+#[cfg(feature = "microphone")]
+impl fmt::Display for CaptureConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} channels of {:?} at {} Hz",
+            self.channels, self.sample_format, self.sample_rate
+        )?;
+        match self.buffer_size {
+            Some(frames) => write!(f, " in buffers of {} frames", frames),
+            None => write!(f, " in the host's default buffers"),
+        }
+    }
+}
+
+#[cfg(feature = "microphone")]
+#[derive(Debug, Clone)]
+enum DeviceSelector {
+    Name(String),
+    Index(usize),
+    Loopback,
+}
+
+/// An input device, the default one unless [`device`](Self::device) or
+/// [`device_index`](Self::device_index) selects another.
+#[cfg(feature = "microphone")]
+#[derive(Debug, Clone, Default)]
+pub struct Microphone {
+    device: Option<DeviceSelector>,
+    sample_rate: Option<u32>,
+    config: Option<cpal::SupportedStreamConfig>,
+    buffer_size: Option<u32>,
+}
+
+#[cfg(feature = "microphone")]
+impl Microphone {
+    pub fn new() -> Self {
+        Microphone::default()
+    }
+
+    /// Records from the first input device whose name contains `name`
+    /// (case-insensitive).
+    pub fn device(mut self, name: &str) -> Self {
+        self.device = Some(DeviceSelector::Name(name.to_string()));
+        self
+    }
+
+    /// Records from the input device at `index` in [`list_input_devices`](crate::list_input_devices) order.
+    pub fn device_index(mut self, index: usize) -> Self {
+        self.device = Some(DeviceSelector::Index(index));
+        self
+    }
+
+    /// Records what the system plays instead of an input, where the platform
+    /// allows it; it's not portable. Windows records the default output
+    /// device in loopback mode at its own config, ignoring
+    /// [`sample_rate`](Self::sample_rate). Linux uses the first input device
+    /// named like a PulseAudio or PipeWire monitor source. Elsewhere
+    /// recording fails with [`RandomizeError::LoopbackUnavailable`].
+    ///
+    /// Playback is mostly deterministic; the entropy comes from the ADC
+    /// noise and ambient sound mixed into it, if any.
+    pub fn loopback(mut self) -> Self {
+        self.device = Some(DeviceSelector::Loopback);
+        self
+    }
+
+    /// Requests a sample rate. If the device doesn't support it, its default
+    /// config is used instead and a warning is logged.
+    pub fn sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = Some(sample_rate);
+        self
+    }
+
+    /// Records with exactly `config` instead of picking one; it overrides
+    /// [`sample_rate`](Self::sample_rate). Recording fails with
+    /// [`RandomizeError::UnsupportedConfig`] if the device doesn't support it.
+    pub fn stream_config(mut self, config: cpal::SupportedStreamConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Requests callback buffers of `frames` frames instead of the host's
+    /// default. Recording fails with [`RandomizeError::UnsupportedConfig`]
+    /// if the device reports a buffer size range without it.
+    pub fn buffer_size(mut self, frames: u32) -> Self {
+        self.buffer_size = Some(frames);
+        self
+    }
+
+    pub(crate) fn requested_sample_rate(&self) -> Option<u32> {
+        self.config
+            .as_ref()
+            .map(|config| config.sample_rate().0)
+            .or(self.sample_rate)
+    }
+
+    pub(crate) fn stream_buffer_size(&self) -> cpal::BufferSize {
+        self.buffer_size
+            .map_or(cpal::BufferSize::Default, cpal::BufferSize::Fixed)
+    }
+
+    /// Opens the selected device, picks its stream config and runs `start`
+    /// with them. A picked config the device can't build a stream with is
+    /// replaced by any other it supports; one given with
+    /// [`stream_config`](Self::stream_config) is used or fails as is.
+    pub(crate) fn open<T>(
+        &self,
+        mut start: impl FnMut(&cpal::Device, &cpal::SupportedStreamConfig) -> Result<T, RandomizeError>,
+    ) -> Result<(T, cpal::SupportedStreamConfig), RandomizeError> {
+        let (input_device, loopback_config) = match &self.device {
+            Some(DeviceSelector::Loopback) => loopback_device()?,
+            _ => (self.input_device()?, None),
+        };
+        let (input_config, fixed) = match (&self.config, loopback_config) {
+            (Some(config), _) => (Ok(config.clone()), true),
+            (None, Some(loopback_config)) => (Ok(loopback_config), true),
+            (None, None) => (self.input_config(&input_device), false),
+        };
+        if let Ok(input_config) = &input_config {
+            if self.config.is_some() || self.buffer_size.is_some() {
+                self.check_supported(&input_device, input_config)?;
+            }
+        }
+        if fixed {
+            let input_config = input_config?;
+            return start(&input_device, &input_config).map(|value| (value, input_config));
+        }
+        with_usable_config(&input_device, input_config, |input_config| {
+            start(&input_device, input_config)
+        })
+    }
+
+    // Checks the config and buffer size against the device's supported
+    // ranges. A range without a known buffer size accepts any.
+    fn check_supported(
+        &self,
+        input_device: &cpal::Device,
+        input_config: &cpal::SupportedStreamConfig,
+    ) -> Result<(), RandomizeError> {
+        let ranges = list_supported_configs(input_device);
+        let sample_rate = input_config.sample_rate();
+        let supported = ranges.iter().any(|range| {
+            range.channels() == input_config.channels()
+                && range.sample_format() == input_config.sample_format()
+                && range.min_sample_rate() <= sample_rate
+                && sample_rate <= range.max_sample_rate()
+                && match (self.buffer_size, range.buffer_size()) {
+                    (Some(frames), cpal::SupportedBufferSize::Range { min, max }) => {
+                        (*min..=*max).contains(&frames)
+                    }
+                    _ => true,
+                }
+        });
+        if supported {
+            return Ok(());
+        }
+
+        let mut requested = format!(
+            "{} channels of {:?} at {} Hz",
+            input_config.channels(),
+            input_config.sample_format(),
+            sample_rate.0
+        );
+        if let Some(frames) = self.buffer_size {
+            requested += &format!(" in buffers of {} frames", frames);
+        }
+        Err(RandomizeError::UnsupportedConfig {
+            requested,
+            supported: ranges.iter().map(describe_range).collect(),
+        })
+    }
+
+    fn input_device(&self) -> Result<cpal::Device, RandomizeError> {
+        use cpal::traits::HostTrait;
+
+        match &self.device {
+            None => cpal::default_host()
+                .default_input_device()
+                .ok_or(RandomizeError::NoInputDevice),
+            Some(DeviceSelector::Name(name)) => find_input_device(name),
+            Some(DeviceSelector::Index(index)) => input_device_at(*index),
+            Some(DeviceSelector::Loopback) => loopback_device().map(|(device, _)| device),
+        }
+    }
+
+    fn input_config(
+        &self,
+        input_device: &cpal::Device,
+    ) -> Result<cpal::SupportedStreamConfig, RandomizeError> {
+        use cpal::traits::DeviceTrait;
+
+        let default_config = input_device
+            .default_input_config()
+            .map_err(RandomizeError::DefaultConfig)?;
+        let Some(sample_rate) = self.sample_rate else {
+            return Ok(default_config);
+        };
+
+        // Any format will do, but native f32 saves a conversion.
+        let supported = input_device
+            .supported_input_configs()
+            .map_err(RandomizeError::SupportedConfigs)?
+            .filter(|range| {
+                range.min_sample_rate().0 <= sample_rate && sample_rate <= range.max_sample_rate().0
+            })
+            .min_by_key(|range| range.sample_format() != cpal::SampleFormat::F32);
+
+        match supported {
+            Some(range) => Ok(range.with_sample_rate(cpal::SampleRate(sample_rate))),
+            None => {
+                log::warn!(
+                    "sample rate {} Hz is not supported, falling back to {} Hz",
+                    sample_rate,
+                    default_config.sample_rate().0
+                );
+                Ok(default_config)
+            }
+        }
+    }
+
+    /// Records like [`SampleSource::record`], but stops as soon as `skip`
+    /// worth of samples and `max_samples` more have arrived. Also returns
+    /// the config the stream ran with.
+    pub(crate) fn record_at_most(
+        &self,
+        duration: Duration,
+        skip: Duration,
+        max_samples: Option<usize>,
+    ) -> Result<(Audio, CaptureConfig), RandomizeError> {
+        self.open(|input_device, input_config| {
+            let max_samples = max_samples.map(|max_samples| {
+                samples_in(skip, input_config.sample_rate().0, input_config.channels())
+                    .saturating_add(max_samples)
+            });
+            record_from_device(
+                input_device,
+                input_config,
+                self.stream_buffer_size(),
+                duration,
+                max_samples,
+            )
+        })
+        .map(|(audio, config)| {
+            let config = CaptureConfig {
+                sample_rate: config.sample_rate().0,
+                channels: config.channels(),
+                sample_format: config.sample_format(),
+                buffer_size: self.buffer_size,
+            };
+            (audio, config)
+        })
+    }
+}
+
+#[cfg(feature = "microphone")]
+impl SampleSource for Microphone {
+    fn record(&self, duration: Duration) -> Result<Audio, RandomizeError> {
+        self.record_at_most(duration, Duration::ZERO, None)
+            .map(|(audio, _)| audio)
+    }
+}
+
+/// Interleaved samples in `duration` of audio.
+pub(crate) fn samples_in(duration: Duration, sample_rate: u32, channels: u16) -> usize {
+    (sample_rate as f64 * duration.as_secs_f64()) as usize * channels as usize
+}
+
+#[cfg(feature = "microphone")]
+fn describe_range(range: &cpal::SupportedStreamConfigRange) -> String {
+    let mut description = format!(
+        "{} channels of {:?} at {}-{} Hz",
+        range.channels(),
+        range.sample_format(),
+        range.min_sample_rate().0,
+        range.max_sample_rate().0
+    );
+    if let cpal::SupportedBufferSize::Range { min, max } = range.buffer_size() {
+        description += &format!(" in buffers of {}-{} frames", min, max);
+    }
+    description
+}
+
+/// A deterministic signal generator for exercising the pipeline without
+/// hardware.
+///
+/// Each sample is `gain * (noise + tone)`, clamped to [-1, 1] like an ADC,
+/// so a large gain produces clipping. The noise is uniform in
+/// `[-amplitude, amplitude]` from a ChaCha8 stream seeded with `seed`, and
+/// the same tone is played on every channel. Consecutive recordings continue
+/// the noise stream and the tone's phase; a fresh source with the same seed
+/// and settings repeats them exactly.
+///
+/// ```
+/// use randomize7::{SampleSource, Synthetic};
+/// use std::time::Duration;
+///
+/// let source = Synthetic::new(7).tone(440.0, 0.2).gain(4.0);
+/// let audio = source.record(Duration::from_millis(100))?;
+/// assert_eq!(audio.samples.len(), 4410);
+/// # Ok::<(), randomize7::RandomizeError>(())
+/// ```
+#[derive(Debug)]
+pub struct Synthetic {
+    sample_rate: u32,
+    channels: u16,
+    noise_amplitude: f32,
+    tone: Option<(f32, f32)>,
+    gain: f32,
+    stall_after: Option<Duration>,
+    buffer_size: Option<u32>,
+    state: Mutex<SyntheticState>,
+}
+
+#[derive(Debug)]
+struct SyntheticState {
+    rng: ChaCha8Rng,
+    frame: u64,
+}
+
+impl Synthetic {
+    /// Mono white noise of amplitude 0.1 at 44.1 kHz.
+    pub fn new(seed: u64) -> Self {
+        Synthetic {
+            sample_rate: SAMPLE_RATE,
+            channels: 1,
+            noise_amplitude: 0.1,
+            tone: None,
+            gain: 1.0,
+            stall_after: None,
+            buffer_size: None,
+            state: Mutex::new(SyntheticState {
+                rng: ChaCha8Rng::seed_from_u64(seed),
+                frame: 0,
+            }),
+        }
+    }
+
+    /// A source that records nothing but zeros.
+    pub fn silence() -> Self {
+        Synthetic::new(0).noise(0.0)
+    }
+
+    pub fn sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = sample_rate;
+        self
+    }
+
+    pub fn channels(mut self, channels: u16) -> Self {
+        self.channels = channels;
+        self
+    }
+
+    /// Peak amplitude of the white noise; 0 disables it.
+    pub fn noise(mut self, amplitude: f32) -> Self {
+        self.noise_amplitude = amplitude;
+        self
+    }
+
+    /// Adds a sine tone at `frequency_hz` with peak `amplitude`.
+    pub fn tone(mut self, frequency_hz: f32, amplitude: f32) -> Self {
+        self.tone = Some((frequency_hz, amplitude));
+        self
+    }
+
+    /// Scales the mix before clamping; above 1/peak the signal clips.
+    pub fn gain(mut self, gain: f32) -> Self {
+        self.gain = gain;
+        self
+    }
+
+    /// Stops every recording after `delivered`, like a device whose
+    /// callbacks stop firing; `Duration::ZERO` records nothing at all.
+    pub fn stall_after(mut self, delivered: Duration) -> Self {
+        self.stall_after = Some(delivered);
+        self
+    }
+
+    /// Reports the recording as delivered in callback buffers of `frames`
+    /// frames, the last one shorter if they don't divide it, instead of
+    /// in one piece.
+    pub fn buffer_size(mut self, frames: u32) -> Self {
+        self.buffer_size = Some(frames);
+        self
+    }
+}
+
+impl SampleSource for Synthetic {
+    fn record(&self, duration: Duration) -> Result<Audio, RandomizeError> {
+        if self.channels == 0 || self.sample_rate == 0 {
+            return Err(RandomizeError::InvalidParameter(
+                "synthetic source needs at least one channel and a non-zero sample rate"
+                    .to_string(),
+            ));
+        }
+
+        let duration = self
+            .stall_after
+            .map_or(duration, |delivered| delivered.min(duration));
+        let frames = (self.sample_rate as f64 * duration.as_secs_f64()) as u64;
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        let mut samples = Vec::with_capacity(frames as usize * self.channels as usize);
+        for frame in state.frame..state.frame + frames {
+            let tone = self.tone.map_or(0.0, |(frequency_hz, amplitude)| {
+                let t = frame as f64 / self.sample_rate as f64;
+                amplitude * (std::f64::consts::TAU * frequency_hz as f64 * t).sin() as f32
+            });
+            for _ in 0..self.channels {
+                // 24 random bits mapped to [-1, 1).
+                let unit = (state.rng.next_u32() >> 8) as f32 / (1 << 23) as f32 - 1.0;
+                let noise = self.noise_amplitude * unit;
+                samples.push((self.gain * (noise + tone)).clamp(-1.0, 1.0));
+            }
+        }
+        state.frame += frames;
+
+        if samples.is_empty() {
+            return Err(RandomizeError::EmptyRecording);
+        }
+        let buffers = match self.buffer_size {
+            Some(frames) => {
+                let length = (frames as usize * self.channels as usize).max(1);
+                samples.chunks(length).map(<[f32]>::len).collect()
+            }
+            None => Vec::new(),
+        };
+        Ok(Audio {
+            samples,
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            buffers,
+            dropped_callbacks: 0,
+            callback_intervals: Vec::new(),
+        })
+    }
+}
+
+/// CPU timing jitter as a stand-in for audio on machines without an input
+/// device, such as CI runners and headless servers. Not cryptographic.
+///
+/// Each sample is the low 16 bits of the nanoseconds one short burst of
+/// arithmetic took, read as a signed 16-bit sample. Cache, pipeline and
+/// interrupt effects make the times vary, but by how much depends on the
+/// machine, and a virtual or idle one may vary very little. The source
+/// reports 44.1 kHz mono and times as many bursts as `duration` would hold
+/// at that rate, as fast as it can.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Jitter;
+
+// Arithmetic steps timed for one sample.
+const JITTER_ROUNDS: u64 = 64;
+
+impl Jitter {
+    pub fn new() -> Self {
+        Jitter
+    }
+}
+
+impl SampleSource for Jitter {
+    fn record(&self, duration: Duration) -> Result<Audio, RandomizeError> {
+        let count = samples_in(duration, SAMPLE_RATE, 1);
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        let samples: Vec<f32> = (0..count)
+            .map(|_| {
+                let start = Instant::now();
+                for round in 0..JITTER_ROUNDS {
+                    state = std::hint::black_box(state.rotate_left(5) ^ round).wrapping_mul(31);
+                }
+                // Short bursts stay near 0 rather than at full scale,
+                // where they would count as clipped.
+                let nanos = start.elapsed().as_nanos() as u16 as i16;
+                nanos as f32 / 32768.0
+            })
+            .collect();
+        if samples.is_empty() {
+            return Err(RandomizeError::EmptyRecording);
+        }
+        Ok(Audio {
+            samples,
+            sample_rate: SAMPLE_RATE,
+            channels: 1,
+            buffers: Vec::new(),
+            dropped_callbacks: 0,
+            callback_intervals: Vec::new(),
+        })
+    }
+}
@@ -0,0 +1,1621 @@
+use bitvec::prelude::*;
+use num_complex::Complex;
+
+use crate::entropy::{byte_histogram, min_entropy_mcv};
+use crate::error::RandomizeError;
+use crate::fft::forward_fft;
+use crate::stats::{erfc, igamc, ln_gamma, normal_cdf};
+
+/// Default significance level `alpha` used to turn p-values into pass/fail.
+///
+/// A test passes when its p-value is at least `alpha`, so truly random
+/// input still fails each p-value with probability `alpha`: at 0.01 about one
+/// good batch in a hundred fails a given test. Over `m` independent p-values
+/// the chance of at least one failure is `1 - (1 - alpha)^m`, about 26% for
+/// the 30 or so p-values of the full battery, so judge a battery by how many
+/// results fail rather than by any single one. A smaller `alpha` trades
+/// fewer false failures for less power against weak biases.
+pub const SIGNIFICANCE_LEVEL: f64 = 0.01;
+
+const BLOCK_FREQUENCY_BLOCK_SIZE: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+pub struct TestResult {
+    pub name: String,
+    pub statistic: f64,
+    pub p_value: f64,
+    pub passed: bool,
+}
+
+impl TestResult {
+    pub(crate) fn new(name: &str, statistic: f64, p_value: f64) -> Self {
+        TestResult {
+            name: name.to_string(),
+            statistic,
+            p_value,
+            passed: p_value >= SIGNIFICANCE_LEVEL,
+        }
+    }
+
+    /// Whether the p-value reaches `significance_level`. A NaN p-value never
+    /// passes.
+    pub fn passes(&self, significance_level: f64) -> bool {
+        self.p_value >= significance_level
+    }
+
+    /// Re-judges `passed` at `significance_level` instead of
+    /// [`SIGNIFICANCE_LEVEL`].
+    pub fn at_significance_level(mut self, significance_level: f64) -> Self {
+        self.passed = self.passes(significance_level);
+        self
+    }
+}
+
+/// Runs every test of [`TestKind::ALL`] that can be applied to `data`,
+/// judged at [`SIGNIFICANCE_LEVEL`]. Tests whose input requirements aren't
+/// met are left out; [`run_tests`] reports them instead.
+///
+/// With the `parallel` feature the tests run on the rayon thread pool; the
+/// results are returned in the same order either way.
+pub fn run_all_tests(data: &[u8]) -> Vec<TestResult> {
+    run_all_tests_at(data, SIGNIFICANCE_LEVEL)
+}
+
+/// A test [`run_tests`] could not apply to its input.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+pub struct SkippedTest {
+    /// The test's [`TestKind::name`].
+    pub name: String,
+    /// Why it was skipped, e.g. that it needs more bits than were given.
+    pub reason: String,
+}
+
+/// What [`run_tests`] ran and what it skipped.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+pub struct BatteryResults {
+    pub results: Vec<TestResult>,
+    pub skipped: Vec<SkippedTest>,
+}
+
+/// Runs the tests in `kinds`, in order, judged at `significance_level`.
+/// Instead of failing, a test whose input requirements aren't met is listed
+/// in [`skipped`](BatteryResults::skipped) with the reason, so the same
+/// selection works for a 32-byte key and a multi-megabyte stream.
+///
+/// With the `parallel` feature the tests run on the rayon thread pool.
+pub fn run_tests(data: &[u8], kinds: &[TestKind], significance_level: f64) -> BatteryResults {
+    let run = |kind: &TestKind| (*kind, kind.run_at(data, significance_level));
+    #[cfg(feature = "parallel")]
+    let outcomes: Vec<_> = {
+        use rayon::prelude::*;
+        kinds.par_iter().map(run).collect()
+    };
+    #[cfg(not(feature = "parallel"))]
+    let outcomes: Vec<_> = kinds.iter().map(run).collect();
+
+    let mut battery = BatteryResults::default();
+    for (kind, outcome) in outcomes {
+        match outcome {
+            Ok(results) => battery.results.extend(results),
+            Err(err) => battery.skipped.push(SkippedTest {
+                name: kind.name().to_string(),
+                reason: err.to_string(),
+            }),
+        }
+    }
+    battery
+}
+
+/// Like [`run_all_tests`], judged at `significance_level`.
+pub fn run_all_tests_at(data: &[u8], significance_level: f64) -> Vec<TestResult> {
+    run_tests(data, &TestKind::ALL, significance_level).results
+}
+
+/// P-values below this make a battery [`Recommendation::Suspect`] even when
+/// enough tests pass; SP800-22 section 4.2.2 uses the same cutoff for the
+/// uniformity of p-values.
+pub const SUSPECT_P_VALUE: f64 = 0.0001;
+
+/// Summary of a battery's results, see [`overall_verdict`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+pub struct Verdict {
+    pub passed: usize,
+    pub total: usize,
+    /// Smallest p-value among the results; NaN without results.
+    pub min_p_value: f64,
+    pub recommendation: Recommendation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+pub enum Recommendation {
+    /// Enough tests pass and no p-value is extreme.
+    Acceptable,
+    /// Enough tests pass, but some p-value is below [`SUSPECT_P_VALUE`], or
+    /// there were no results to judge.
+    Suspect,
+    /// Fewer tests pass than random data plausibly would.
+    Reject,
+}
+
+/// Judges a battery at [`SIGNIFICANCE_LEVEL`], see [`overall_verdict_at`].
+pub fn overall_verdict(results: &[TestResult]) -> Verdict {
+    overall_verdict_at(results, SIGNIFICANCE_LEVEL)
+}
+
+/// Judges a battery by the proportion of passing results, after SP800-22
+/// section 4.2.1.
+///
+/// Each result passes at `significance_level` with probability
+/// `p = 1 - significance_level` for random data, so over `m` results the
+/// passing proportion should lie within `p ± 3 sqrt(p (1 - p) / m)`. Below
+/// that range the battery is rejected. With few results the range is wide:
+/// at the default level one failure in 15 is acceptable, one in 2 is not.
+pub fn overall_verdict_at(results: &[TestResult], significance_level: f64) -> Verdict {
+    let total = results.len();
+    let passed = results
+        .iter()
+        .filter(|result| result.passes(significance_level))
+        .count();
+    let min_p_value = results
+        .iter()
+        .map(|result| result.p_value)
+        .reduce(f64::min)
+        .unwrap_or(f64::NAN);
+
+    let expected = 1.0 - significance_level;
+    let lower_bound = expected - 3.0 * (expected * significance_level / total.max(1) as f64).sqrt();
+    let recommendation = if total == 0 {
+        Recommendation::Suspect
+    } else if (passed as f64 / total as f64) < lower_bound {
+        Recommendation::Reject
+    } else if min_p_value < SUSPECT_P_VALUE {
+        Recommendation::Suspect
+    } else {
+        Recommendation::Acceptable
+    };
+
+    Verdict {
+        passed,
+        total,
+        min_p_value,
+        recommendation,
+    }
+}
+
+/// A heuristic 0-100 gauge of `data`, for monitoring rather than as a pass
+/// or fail. It is `round(50 e + 25 m + 25 a)`, where
+///
+/// - `e` is [`min_entropy_mcv`] divided by 8, the fraction of full entropy,
+/// - `m` is the [`monobit_p_value`] divided by [`SIGNIFICANCE_LEVEL`], capped at 1,
+/// - `a` is the lag-1 [`autocorrelation_test`] p-value scaled the same way,
+///   or 0 if there are too few bits.
+///
+/// Good output passes both tests, so it scores about `50 e + 50`. Because
+/// of the confidence bound in `e`, that is around 65 for 32 bytes and over
+/// 95 from about 100 KB on; empty input scores 0.
+pub fn quality_score(data: &[u8]) -> u8 {
+    if data.is_empty() {
+        return 0;
+    }
+    let scaled = |p_value: f64| (p_value / SIGNIFICANCE_LEVEL).min(1.0);
+    let entropy = min_entropy_mcv(data) / 8.0;
+    let monobit = scaled(monobit_p_value(data));
+    let autocorrelation = autocorrelation_test(data, 1).map_or(0.0, scaled);
+    (50.0 * entropy + 25.0 * monobit + 25.0 * autocorrelation).round() as u8
+}
+
+/// A single test of the battery, selectable by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TestKind {
+    Monobit,
+    Runs,
+    BlockFrequency,
+    LongestRun,
+    Spectral,
+    Serial,
+    CumulativeSums,
+    ApproximateEntropy,
+    Autocorrelation,
+    ChiSquareByte,
+    MatrixRank,
+    MaurerUniversal,
+    NonOverlappingTemplate,
+    OverlappingTemplate,
+    LinearComplexity,
+    RandomExcursions,
+    RandomExcursionsVariant,
+}
+
+impl TestKind {
+    pub const ALL: [TestKind; 17] = [
+        TestKind::Monobit,
+        TestKind::Runs,
+        TestKind::BlockFrequency,
+        TestKind::LongestRun,
+        TestKind::Spectral,
+        TestKind::Serial,
+        TestKind::CumulativeSums,
+        TestKind::ApproximateEntropy,
+        TestKind::Autocorrelation,
+        TestKind::ChiSquareByte,
+        TestKind::MatrixRank,
+        TestKind::MaurerUniversal,
+        TestKind::NonOverlappingTemplate,
+        TestKind::OverlappingTemplate,
+        TestKind::LinearComplexity,
+        TestKind::RandomExcursions,
+        TestKind::RandomExcursionsVariant,
+    ];
+
+    /// The name used by [`FromStr`](std::str::FromStr), e.g. `block-frequency`.
+    pub fn name(self) -> &'static str {
+        match self {
+            TestKind::Monobit => "monobit",
+            TestKind::Runs => "runs",
+            TestKind::BlockFrequency => "block-frequency",
+            TestKind::LongestRun => "longest-run",
+            TestKind::Spectral => "spectral",
+            TestKind::Serial => "serial",
+            TestKind::CumulativeSums => "cumulative-sums",
+            TestKind::ApproximateEntropy => "approximate-entropy",
+            TestKind::Autocorrelation => "autocorrelation",
+            TestKind::ChiSquareByte => "chi-square",
+            TestKind::MatrixRank => "matrix-rank",
+            TestKind::MaurerUniversal => "maurer",
+            TestKind::NonOverlappingTemplate => "non-overlapping-template",
+            TestKind::OverlappingTemplate => "overlapping-template",
+            TestKind::LinearComplexity => "linear-complexity",
+            TestKind::RandomExcursions => "random-excursions",
+            TestKind::RandomExcursionsVariant => "random-excursions-variant",
+        }
+    }
+
+    /// Like [`run`](Self::run), judged at `significance_level`.
+    pub fn run_at(
+        self,
+        data: &[u8],
+        significance_level: f64,
+    ) -> Result<Vec<TestResult>, RandomizeError> {
+        Ok(self
+            .run(data)?
+            .into_iter()
+            .map(|result| result.at_significance_level(significance_level))
+            .collect())
+    }
+
+    /// Runs the test with parameters suited to the input length, judged at
+    /// [`SIGNIFICANCE_LEVEL`]. Tests that produce several p-values return one
+    /// result each; tests whose input requirements aren't met return an
+    /// error.
+    pub fn run(self, data: &[u8]) -> Result<Vec<TestResult>, RandomizeError> {
+        let n = data.len() * 8;
+        let bits = data.view_bits::<Msb0>();
+        let results = match self {
+            TestKind::Monobit => vec![monobit_result(data)],
+            TestKind::Runs => vec![runs_result(data)?],
+            TestKind::BlockFrequency => vec![require_p_value(
+                block_frequency_result_bits(bits, BLOCK_FREQUENCY_BLOCK_SIZE),
+                BLOCK_FREQUENCY_BLOCK_SIZE,
+                n,
+            )?],
+            TestKind::LongestRun => vec![longest_run_result_bits(bits)?],
+            TestKind::Spectral => vec![require_p_value(spectral_result(data), 1, n)?],
+            TestKind::Serial => {
+                // SP800-22 requires m < log2(n) - 2.
+                let m = floor_log2(n).saturating_sub(3).min(16);
+                if m < 2 {
+                    return Err(RandomizeError::InsufficientData {
+                        required_bits: 32,
+                        available_bits: n,
+                    });
+                }
+                serial_results_bits(bits, m).to_vec()
+            }
+            TestKind::CumulativeSums => cumulative_sums_results_bits(bits).to_vec(),
+            TestKind::ApproximateEntropy => {
+                // SP800-22 requires m < log2(n) - 5.
+                let m = floor_log2(n)
+                    .saturating_sub(6)
+                    .min(DEFAULT_APPROXIMATE_ENTROPY_BLOCK_LENGTH);
+                if m < 1 {
+                    return Err(RandomizeError::InsufficientData {
+                        required_bits: 128,
+                        available_bits: n,
+                    });
+                }
+                vec![approximate_entropy_result_bits(bits, m)]
+            }
+            TestKind::Autocorrelation => vec![autocorrelation_result(data, 1)?],
+            TestKind::ChiSquareByte => {
+                if data.len() < CHI_SQUARE_MIN_BYTES {
+                    return Err(RandomizeError::InsufficientData {
+                        required_bits: CHI_SQUARE_MIN_BYTES * 8,
+                        available_bits: n,
+                    });
+                }
+                vec![chi_square_byte_result(data)]
+            }
+            TestKind::MatrixRank => vec![matrix_rank_result(data)?],
+            TestKind::MaurerUniversal => vec![maurer_universal_result(data)?],
+            TestKind::NonOverlappingTemplate => {
+                // Eight blocks, as in the standard's reference parameters.
+                let template = APERIODIC_TEMPLATES_4[0];
+                let block_size = n / 8;
+                vec![require_p_value(
+                    non_overlapping_template_result_bits(bits, template, block_size),
+                    8 * template.len(),
+                    n,
+                )?]
+            }
+            TestKind::OverlappingTemplate => vec![overlapping_template_result(
+                data,
+                9,
+                OVERLAPPING_TEMPLATE_BLOCK_SIZE,
+            )?],
+            TestKind::LinearComplexity => vec![require_p_value(
+                linear_complexity_result(data, LINEAR_COMPLEXITY_BLOCK_SIZE),
+                LINEAR_COMPLEXITY_BLOCK_SIZE,
+                n,
+            )?],
+            TestKind::RandomExcursions => random_excursions_results(data)?
+                .into_iter()
+                .map(|(_, result)| result)
+                .collect(),
+            TestKind::RandomExcursionsVariant => random_excursions_variant_results(data)?
+                .into_iter()
+                .map(|(_, result)| result)
+                .collect(),
+        };
+        Ok(results)
+    }
+}
+
+impl std::str::FromStr for TestKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TestKind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| format!("unknown test {:?}", s))
+    }
+}
+
+fn floor_log2(n: usize) -> usize {
+    n.checked_ilog2().unwrap_or(0) as usize
+}
+
+// Tests that signal an unusable input with a NaN p-value.
+fn require_p_value(
+    result: TestResult,
+    required_bits: usize,
+    available_bits: usize,
+) -> Result<TestResult, RandomizeError> {
+    if result.p_value.is_nan() {
+        Err(RandomizeError::InsufficientData {
+            required_bits,
+            available_bits,
+        })
+    } else {
+        Ok(result)
+    }
+}
+
+/// Whether [`monobit_p_value`] reaches [`SIGNIFICANCE_LEVEL`].
+pub fn monobit_test(data: &[u8]) -> bool {
+    monobit_result(data).passed
+}
+
+/// NIST SP800-22 frequency (monobit) test; returns
+/// `erfc(|S_n| / sqrt(2n))`. Unlike a fixed band on the proportion of ones,
+/// the acceptable deviation shrinks as `1 / sqrt(n)` with the input length.
+pub fn monobit_p_value(data: &[u8]) -> f64 {
+    monobit_result(data).p_value
+}
+
+/// Frequency (monobit) test; the statistic is `|S_n| / sqrt(n)`.
+pub fn monobit_result(data: &[u8]) -> TestResult {
+    let ones = data.iter().map(|&byte| byte.count_ones() as u64).sum();
+    monobit_from_counts(ones, data.len() as u64 * 8)
+}
+
+pub(crate) fn monobit_result_bits(bits: &BitSlice<u8, Msb0>) -> TestResult {
+    monobit_from_counts(bits.count_ones() as u64, bits.len() as u64)
+}
+
+pub(crate) fn monobit_from_counts(ones: u64, total_bits: u64) -> TestResult {
+    let sum = 2.0 * ones as f64 - total_bits as f64;
+    let statistic = sum.abs() / (total_bits as f64).sqrt();
+    TestResult::new("monobit", statistic, erfc(statistic / 2f64.sqrt()))
+}
+
+/// NIST SP800-22 runs test; returns the p-value.
+///
+/// The p-value is 0 when the monobit precondition `|pi - 1/2| < 2 / sqrt(n)`
+/// fails, as the standard prescribes.
+pub fn runs_test(data: &[u8]) -> Result<f64, RandomizeError> {
+    runs_result(data).map(|result| result.p_value)
+}
+
+/// Runs test; the statistic is the total number of runs `V_n(obs)`.
+pub fn runs_result(data: &[u8]) -> Result<TestResult, RandomizeError> {
+    runs_result_bits(data.view_bits::<Msb0>())
+}
+
+pub(crate) fn runs_result_bits(bits: &BitSlice<u8, Msb0>) -> Result<TestResult, RandomizeError> {
+    if bits.is_empty() {
+        return Err(RandomizeError::EmptyInput);
+    }
+
+    let runs = run_lengths(bits).count();
+    Ok(runs_from_counts(
+        bits.count_ones() as u64,
+        bits.len() as u64,
+        runs as u64,
+    ))
+}
+
+/// Buckets of [`run_length_distribution`]; the last one counts every run of
+/// at least this many bits.
+pub const RUN_LENGTH_BUCKETS: usize = 16;
+
+/// How many runs of identical bits of each length `data` contains: index `i`
+/// counts runs of `i + 1` bits, and the last of the [`RUN_LENGTH_BUCKETS`]
+/// entries all longer runs too. Random data has about half as many runs of
+/// each length as of the one shorter.
+pub fn run_length_distribution(data: &[u8]) -> Vec<u64> {
+    let mut distribution = vec![0; RUN_LENGTH_BUCKETS];
+    for length in run_lengths(data.view_bits::<Msb0>()) {
+        distribution[length.min(RUN_LENGTH_BUCKETS) - 1] += 1;
+    }
+    distribution
+}
+
+// Lengths of the maximal runs of equal bits, in order; none for no bits.
+fn run_lengths(bits: &BitSlice<u8, Msb0>) -> impl Iterator<Item = usize> + '_ {
+    bits.windows(2)
+        .enumerate()
+        .filter(|(_, pair)| pair[0] != pair[1])
+        .map(|(index, _)| index + 1)
+        .chain(std::iter::once(bits.len()))
+        .scan(0, |start, end| {
+            let length = end - *start;
+            *start = end;
+            Some(length)
+        })
+        .filter(|&length| length > 0)
+}
+
+pub(crate) fn runs_from_counts(ones: u64, total_bits: u64, runs: u64) -> TestResult {
+    let n = total_bits as f64;
+    let proportion = ones as f64 / n;
+    let statistic = runs as f64;
+
+    if (proportion - 0.5).abs() >= 2.0 / n.sqrt() {
+        return TestResult::new("runs", statistic, 0.0);
+    }
+
+    let spread = proportion * (1.0 - proportion);
+    let p_value = erfc((statistic - 2.0 * n * spread).abs() / (2.0 * (2.0 * n).sqrt() * spread));
+    TestResult::new("runs", statistic, p_value)
+}
+
+/// NIST SP800-22 frequency test within a block; returns the p-value.
+///
+/// The bit stream is split into blocks of `block_size` bits. Trailing bits
+/// that don't fill a whole block are discarded. Returns `NaN` if there isn't
+/// a single complete block.
+pub fn block_frequency_test(data: &[u8], block_size: usize) -> f64 {
+    block_frequency_result_bits(data.view_bits::<Msb0>(), block_size).p_value
+}
+
+// The statistic is `χ²(obs)`.
+pub(crate) fn block_frequency_result_bits(
+    bits: &BitSlice<u8, Msb0>,
+    block_size: usize,
+) -> TestResult {
+    let num_blocks = bits.len() / block_size;
+    if num_blocks == 0 {
+        return TestResult::new("block_frequency", f64::NAN, f64::NAN);
+    }
+
+    let chi_squared = 4.0
+        * block_size as f64
+        * bits
+            .chunks_exact(block_size)
+            .map(|block| {
+                let proportion = block.count_ones() as f64 / block_size as f64;
+                (proportion - 0.5).powi(2)
+            })
+            .sum::<f64>();
+
+    let p_value = igamc(num_blocks as f64 / 2.0, chi_squared / 2.0);
+    TestResult::new("block_frequency", chi_squared, p_value)
+}
+
+/// NIST SP800-22 test for the longest run of ones in a block; returns the p-value.
+///
+/// The block size `M` and the class boundaries are picked from the standard's
+/// table: `M = 8` below 6272 bits, `M = 128` below 750000 bits and `M = 10000`
+/// otherwise. Inputs shorter than 128 bits are rejected.
+pub fn longest_run_test(data: &[u8]) -> Result<f64, RandomizeError> {
+    longest_run_result_bits(data.view_bits::<Msb0>()).map(|result| result.p_value)
+}
+
+// The statistic is `χ²(obs)`.
+pub(crate) fn longest_run_result_bits(
+    bits: &BitSlice<u8, Msb0>,
+) -> Result<TestResult, RandomizeError> {
+    let n = bits.len();
+    if n < 128 {
+        return Err(RandomizeError::InsufficientData {
+            required_bits: 128,
+            available_bits: n,
+        });
+    }
+
+    let (block_size, min_class, probabilities): (usize, usize, &[f64]) = if n < 6272 {
+        (8, 1, &[0.2148, 0.3672, 0.2305, 0.1875])
+    } else if n < 750000 {
+        (128, 4, &[0.1174, 0.2430, 0.2493, 0.1752, 0.1027, 0.1124])
+    } else {
+        (
+            10000,
+            10,
+            &[0.0882, 0.2092, 0.2483, 0.1933, 0.1208, 0.0675, 0.0727],
+        )
+    };
+    let max_class = min_class + probabilities.len() - 1;
+
+    let mut frequencies = vec![0usize; probabilities.len()];
+    for block in bits.chunks_exact(block_size) {
+        let mut longest = 0;
+        let mut current = 0;
+        for bit in block.iter().by_vals() {
+            if bit {
+                current += 1;
+                longest = longest.max(current);
+            } else {
+                current = 0;
+            }
+        }
+        frequencies[longest.clamp(min_class, max_class) - min_class] += 1;
+    }
+
+    let num_blocks = (n / block_size) as f64;
+    let chi_squared = frequencies
+        .iter()
+        .zip(probabilities)
+        .map(|(&observed, &probability)| {
+            let expected = num_blocks * probability;
+            (observed as f64 - expected).powi(2) / expected
+        })
+        .sum::<f64>();
+
+    let degrees_of_freedom = (probabilities.len() - 1) as f64;
+    let p_value = igamc(degrees_of_freedom / 2.0, chi_squared / 2.0);
+    Ok(TestResult::new("longest_run", chi_squared, p_value))
+}
+
+/// NIST SP800-22 discrete Fourier transform (spectral) test; returns the p-value.
+///
+/// Detects periodic features such as mains hum leaking into the bit stream.
+pub fn spectral_test(data: &[u8]) -> f64 {
+    spectral_result(data).p_value
+}
+
+// The statistic is the normalized peak count difference `d`.
+fn spectral_result(data: &[u8]) -> TestResult {
+    let bits = data.view_bits::<Msb0>();
+    let n = bits.len() as f64;
+
+    let mut spectrum: Vec<Complex<f64>> = bits
+        .iter()
+        .by_vals()
+        .map(|bit| Complex::new(if bit { 1.0 } else { -1.0 }, 0.0))
+        .collect();
+    forward_fft(&mut spectrum);
+
+    let threshold = ((1.0f64 / 0.05).ln() * n).sqrt();
+    let expected_peaks = 0.95 * n / 2.0;
+    let observed_peaks = spectrum[..bits.len() / 2]
+        .iter()
+        .filter(|value| value.norm() < threshold)
+        .count() as f64;
+
+    let d = (observed_peaks - expected_peaks) / (n * 0.95 * 0.05 / 4.0).sqrt();
+    TestResult::new("spectral", d, erfc(d.abs() / 2f64.sqrt()))
+}
+
+/// NIST SP800-22 serial test over overlapping `m`-bit patterns; returns the
+/// p-values for `∇ψ²_m` and `∇²ψ²_m`.
+pub fn serial_test(data: &[u8], m: usize) -> (f64, f64) {
+    serial_test_bits(data.view_bits::<Msb0>(), m)
+}
+
+/// [`serial_test`] over an unpacked bit stream of any length.
+pub fn serial_test_bits(bits: &BitSlice<u8, Msb0>, m: usize) -> (f64, f64) {
+    let [first, second] = serial_results_bits(bits, m);
+    (first.p_value, second.p_value)
+}
+
+// The statistics are `∇ψ²_m` and `∇²ψ²_m`.
+fn serial_results_bits(bits: &BitSlice<u8, Msb0>, m: usize) -> [TestResult; 2] {
+    let psi_m = psi_squared(bits, m);
+    let psi_m1 = psi_squared(bits, m.saturating_sub(1));
+    let psi_m2 = psi_squared(bits, m.saturating_sub(2));
+
+    let delta = psi_m - psi_m1;
+    let delta_squared = psi_m - 2.0 * psi_m1 + psi_m2;
+    [
+        TestResult::new(
+            "serial_1",
+            delta,
+            igamc(2f64.powi(m as i32 - 2), delta / 2.0),
+        ),
+        TestResult::new(
+            "serial_2",
+            delta_squared,
+            igamc(2f64.powi(m as i32 - 3), delta_squared / 2.0),
+        ),
+    ]
+}
+
+// ψ²_m statistic over all overlapping `m`-bit patterns, wrapping around the end.
+fn psi_squared(bits: &BitSlice<u8, Msb0>, m: usize) -> f64 {
+    if m == 0 || bits.is_empty() {
+        return 0.0;
+    }
+
+    let n = bits.len();
+    let sum_of_squares = overlapping_pattern_counts(bits, m)
+        .iter()
+        .map(|&count| (count * count) as f64)
+        .sum::<f64>();
+    sum_of_squares * (1usize << m) as f64 / n as f64 - n as f64
+}
+
+/// NIST SP800-22 cumulative sums test; returns the forward and backward
+/// p-values. Both are `NaN` for empty input.
+pub fn cumulative_sums_test(data: &[u8]) -> (f64, f64) {
+    let [forward, backward] = cumulative_sums_results_bits(data.view_bits::<Msb0>());
+    (forward.p_value, backward.p_value)
+}
+
+// The statistics are the largest excursions `z` of the forward and
+// backward walks.
+pub(crate) fn cumulative_sums_results_bits(bits: &BitSlice<u8, Msb0>) -> [TestResult; 2] {
+    let steps: Vec<i64> = bits
+        .iter()
+        .by_vals()
+        .map(|bit| if bit { 1 } else { -1 })
+        .collect();
+    if steps.is_empty() {
+        return [
+            TestResult::new("cumulative_sums_forward", f64::NAN, f64::NAN),
+            TestResult::new("cumulative_sums_backward", f64::NAN, f64::NAN),
+        ];
+    }
+
+    let result =
+        |name: &str, z: i64| TestResult::new(name, z as f64, cusum_p_value(steps.len(), z));
+    [
+        result("cumulative_sums_forward", max_excursion(steps.iter())),
+        result(
+            "cumulative_sums_backward",
+            max_excursion(steps.iter().rev()),
+        ),
+    ]
+}
+
+fn max_excursion<'a>(steps: impl Iterator<Item = &'a i64>) -> i64 {
+    steps
+        .scan(0i64, |sum, &step| {
+            *sum += step;
+            Some(sum.abs())
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+fn cusum_p_value(n: usize, z: i64) -> f64 {
+    let n = n as f64;
+    let z = z as f64;
+    let sqrt_n = n.sqrt();
+
+    // The reference implementation truncates the lower bounds toward zero.
+    let upper = (n / z - 1.0) / 4.0;
+    let mut sum1 = 0.0;
+    let mut k = ((-n / z + 1.0) / 4.0).trunc();
+    while k <= upper {
+        sum1 += normal_cdf((4.0 * k + 1.0) * z / sqrt_n) - normal_cdf((4.0 * k - 1.0) * z / sqrt_n);
+        k += 1.0;
+    }
+
+    let mut sum2 = 0.0;
+    let mut k = ((-n / z - 3.0) / 4.0).trunc();
+    while k <= upper {
+        sum2 += normal_cdf((4.0 * k + 3.0) * z / sqrt_n) - normal_cdf((4.0 * k + 1.0) * z / sqrt_n);
+        k += 1.0;
+    }
+
+    1.0 - sum1 + sum2
+}
+
+// Occurrences of every `m`-bit pattern starting at each bit position, wrapping around the end.
+fn overlapping_pattern_counts(bits: &BitSlice<u8, Msb0>, m: usize) -> Vec<usize> {
+    let n = bits.len();
+    let mut counts = vec![0usize; 1 << m];
+    for start in 0..n {
+        let pattern = (0..m).fold(0usize, |pattern, offset| {
+            (pattern << 1) | bits[(start + offset) % n] as usize
+        });
+        counts[pattern] += 1;
+    }
+    counts
+}
+
+/// Block length used for the approximate entropy test when the caller has no preference.
+/// It needs at least `2^(m + 5)` = 32768 bits (4096 bytes) of input.
+pub const DEFAULT_APPROXIMATE_ENTROPY_BLOCK_LENGTH: usize = 10;
+
+/// NIST SP800-22 approximate entropy test; returns the p-value.
+///
+/// The standard requires `m < log2(n) - 5` for `n` input bits; shorter inputs
+/// still produce a p-value but it isn't meaningful.
+pub fn approximate_entropy_test(data: &[u8], m: usize) -> f64 {
+    approximate_entropy_result_bits(data.view_bits::<Msb0>(), m).p_value
+}
+
+// The statistic is `χ²(obs)`.
+pub(crate) fn approximate_entropy_result_bits(bits: &BitSlice<u8, Msb0>, m: usize) -> TestResult {
+    let n = bits.len() as f64;
+
+    let phi = |m: usize| {
+        if m == 0 {
+            return 0.0;
+        }
+        overlapping_pattern_counts(bits, m)
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let proportion = count as f64 / n;
+                proportion * proportion.ln()
+            })
+            .sum::<f64>()
+    };
+
+    let approximate_entropy = phi(m) - phi(m + 1);
+    let chi_squared = 2.0 * n * (std::f64::consts::LN_2 - approximate_entropy);
+    let p_value = igamc(2f64.powi(m as i32 - 1), chi_squared / 2.0);
+    TestResult::new("approximate_entropy", chi_squared, p_value)
+}
+
+/// Autocorrelation test at a given bit `lag`; returns the p-value.
+///
+/// XORs the stream with itself shifted by `lag` and compares the number of
+/// mismatches against the `(n - lag) / 2` expected from independent bits.
+/// Sweep `lag` to find where correlation between samples disappears.
+pub fn autocorrelation_test(data: &[u8], lag: usize) -> Result<f64, RandomizeError> {
+    autocorrelation_result(data, lag).map(|result| result.p_value)
+}
+
+// The statistic is the standardized mismatch count `z`.
+fn autocorrelation_result(data: &[u8], lag: usize) -> Result<TestResult, RandomizeError> {
+    if lag == 0 {
+        return Err(RandomizeError::InvalidParameter(
+            "lag must be at least 1".to_string(),
+        ));
+    }
+
+    let bits = data.view_bits::<Msb0>();
+    if lag >= bits.len() {
+        return Err(RandomizeError::InsufficientData {
+            required_bits: lag + 1,
+            available_bits: bits.len(),
+        });
+    }
+
+    let compared = (bits.len() - lag) as f64;
+    let mut mismatches = bits[..bits.len() - lag].to_bitvec();
+    mismatches ^= &bits[lag..];
+    let mismatches = mismatches.count_ones() as f64;
+    let z = 2.0 * (mismatches - compared / 2.0) / compared.sqrt();
+    Ok(TestResult::new(
+        "autocorrelation",
+        z,
+        erfc(z.abs() / 2f64.sqrt()),
+    ))
+}
+
+/// Pearson correlation between each bit of `data` and the next, from -1 to
+/// 1; independent bits give about 0. Fewer than three bits, or a stream in
+/// which one of the two compared sequences is constant, give 1.0, since no
+/// variation means complete dependence.
+pub fn lag1_autocorrelation(data: &[u8]) -> f64 {
+    let bits = data.view_bits::<Msb0>();
+    if bits.len() < 3 {
+        return 1.0;
+    }
+
+    let n = (bits.len() - 1) as f64;
+    let current = &bits[..bits.len() - 1];
+    let next = &bits[1..];
+    let p_current = current.count_ones() as f64 / n;
+    let p_next = next.count_ones() as f64 / n;
+    let mut both = current.to_bitvec();
+    both &= next;
+    let p_both = both.count_ones() as f64 / n;
+
+    let variance = p_current * (1.0 - p_current) * p_next * (1.0 - p_next);
+    if variance == 0.0 {
+        return 1.0;
+    }
+    (p_both - p_current * p_next) / variance.sqrt()
+}
+
+// Below this many bytes Spearman's rho says little about the source.
+const RANK_CORRELATION_MIN_BYTES: usize = 30;
+
+/// Spearman's rank correlation between each byte of `data` and the next,
+/// from -1 to 1; independent bytes give about 0, and a source whose level
+/// drifts slowly gives a clearly positive value. Tied bytes share their
+/// average rank, and a constant stream gives 1.0 as in
+/// [`lag1_autocorrelation`]. Needs at least 30 bytes.
+pub fn byte_rank_correlation(data: &[u8]) -> Result<f64, RandomizeError> {
+    if data.len() < RANK_CORRELATION_MIN_BYTES {
+        return Err(RandomizeError::InsufficientData {
+            required_bits: RANK_CORRELATION_MIN_BYTES * 8,
+            available_bits: data.len() * 8,
+        });
+    }
+
+    let current = byte_ranks(&data[..data.len() - 1]);
+    let next = byte_ranks(&data[1..]);
+    // Both rank sequences have the same mean, (n + 1) / 2.
+    let mean = data.len() as f64 / 2.0;
+    let (mut covariance, mut variance_current, mut variance_next) = (0.0, 0.0, 0.0);
+    for (&a, &b) in current.iter().zip(&next) {
+        covariance += (a - mean) * (b - mean);
+        variance_current += (a - mean) * (a - mean);
+        variance_next += (b - mean) * (b - mean);
+    }
+    let variance = variance_current * variance_next;
+    if variance == 0.0 {
+        return Ok(1.0);
+    }
+    Ok(covariance / variance.sqrt())
+}
+
+// The 1-based rank of each byte, ties taking the average of their ranks.
+fn byte_ranks(bytes: &[u8]) -> Vec<f64> {
+    let mut counts = [0usize; 256];
+    for &byte in bytes {
+        counts[byte as usize] += 1;
+    }
+    let mut average_rank = [0.0; 256];
+    let mut below = 0;
+    for (rank, &count) in average_rank.iter_mut().zip(&counts) {
+        *rank = below as f64 + (count as f64 + 1.0) / 2.0;
+        below += count;
+    }
+    bytes
+        .iter()
+        .map(|&byte| average_rank[byte as usize])
+        .collect()
+}
+
+/// Byte-value coverage test; returns how many standard deviations the
+/// number of distinct byte values in `data` lies from its expectation for
+/// uniform bytes, negative when values are missing.
+///
+/// `n` uniform bytes cover `256 (1 - (255/256)^n)` values on average, so 32
+/// bytes are expected to miss about 226 of them and 2048 almost none. The
+/// variance is the exact occupancy variance, and the score is 0 when only one
+/// outcome is possible, as for fewer than two bytes.
+pub fn coverage_test(data: &[u8]) -> f64 {
+    let observed = byte_histogram(data)
+        .iter()
+        .filter(|&&count| count > 0)
+        .count() as f64;
+
+    let n = data.len() as f64;
+    let values = 256f64;
+    let miss_one = (1.0 - 1.0 / values).powf(n);
+    let miss_two = (1.0 - 2.0 / values).powf(n);
+    let expected = values * (1.0 - miss_one);
+    let variance = values * miss_one + values * (values - 1.0) * miss_two
+        - values * values * miss_one * miss_one;
+
+    if variance < 1e-9 {
+        return 0.0;
+    }
+    (observed - expected) / variance.sqrt()
+}
+
+// Below this, the expected count of 10 per byte value is too small for the
+// chi-square approximation to hold.
+pub(crate) const CHI_SQUARE_MIN_BYTES: usize = 2560;
+
+/// Chi-square test of byte-value uniformity (255 degrees of freedom); returns the p-value.
+///
+/// Logs a warning for inputs shorter than 2560 bytes, where the expected
+/// count per bucket is too small for the result to be meaningful.
+pub fn chi_square_byte_test(data: &[u8]) -> f64 {
+    chi_square_byte_result(data).p_value
+}
+
+fn chi_square_byte_result(data: &[u8]) -> TestResult {
+    if data.len() < CHI_SQUARE_MIN_BYTES {
+        log::warn!(
+            "chi-square byte test on {} bytes is unreliable (at least {} recommended)",
+            data.len(),
+            CHI_SQUARE_MIN_BYTES
+        );
+    }
+
+    chi_square_from_histogram(&byte_histogram(data), data.len() as u64)
+}
+
+// The statistic is `χ²`.
+pub(crate) fn chi_square_from_histogram(counts: &[u64; 256], total: u64) -> TestResult {
+    let expected = total as f64 / 256.0;
+    let chi_squared = counts
+        .iter()
+        .map(|&count| (count as f64 - expected).powi(2) / expected)
+        .sum::<f64>();
+    let p_value = igamc(255.0 / 2.0, chi_squared / 2.0);
+    TestResult::new("chi_square_byte", chi_squared, p_value)
+}
+
+// FIPS 140-2 section 4.9.1 tests one 20 000-bit sample.
+const FIPS_SAMPLE_BITS: usize = 20_000;
+const POKER_BOUNDS: (f64, f64) = (2.16, 46.17);
+const FIPS_MONOBIT_BOUNDS: (usize, usize) = (9725, 10275);
+const FIPS_LONG_RUN: usize = 26;
+
+/// FIPS 140-2 poker test on the first 20 000 bits of `data`; returns the
+/// statistic `X = 16/5000 * sum(f_i^2) - 5000` over the counts of the 16
+/// nibble values, and whether it lies strictly between 2.16 and 46.17.
+/// Output that is too uniform fails as well as output that is skewed.
+pub fn poker_test(data: &[u8]) -> Result<(f64, bool), RandomizeError> {
+    let data = fips_sample(data)?;
+    let mut counts = [0u64; 16];
+    for &byte in data {
+        counts[(byte >> 4) as usize] += 1;
+        counts[(byte & 0x0f) as usize] += 1;
+    }
+    let nibbles = (FIPS_SAMPLE_BITS / 4) as f64;
+    let sum_of_squares: f64 = counts.iter().map(|&count| (count * count) as f64).sum();
+    let statistic = 16.0 / nibbles * sum_of_squares - nibbles;
+    let passed = POKER_BOUNDS.0 < statistic && statistic < POKER_BOUNDS.1;
+    Ok((statistic, passed))
+}
+
+/// FIPS 140-2 monobit test: whether the first 20 000 bits of `data` hold
+/// strictly between 9725 and 10 275 ones.
+pub fn fips_monobit(data: &[u8]) -> Result<bool, RandomizeError> {
+    let ones = fips_sample(data)?.view_bits::<Msb0>().count_ones();
+    Ok(FIPS_MONOBIT_BOUNDS.0 < ones && ones < FIPS_MONOBIT_BOUNDS.1)
+}
+
+/// FIPS 140-2 long run test: whether the first 20 000 bits of `data` are
+/// free of runs of 26 or more equal bits.
+pub fn fips_long_run(data: &[u8]) -> Result<bool, RandomizeError> {
+    let bits = fips_sample(data)?.view_bits::<Msb0>();
+    let mut run = 0;
+    for (i, bit) in bits.iter().enumerate() {
+        run = if i > 0 && *bit == bits[i - 1] {
+            run + 1
+        } else {
+            1
+        };
+        if run >= FIPS_LONG_RUN {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Outcome of one run of [`fips_140_2_suite`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(serde::Serialize))]
+pub struct FipsResult {
+    pub monobit: bool,
+    pub poker: bool,
+    pub long_run: bool,
+}
+
+impl FipsResult {
+    /// Whether every test passed.
+    pub fn passed(&self) -> bool {
+        self.monobit && self.poker && self.long_run
+    }
+}
+
+/// The FIPS 140-2 monobit, poker and long run tests on the first 20 000
+/// bits of `data`, each with its fixed acceptance range rather than a
+/// p-value.
+pub fn fips_140_2_suite(data: &[u8]) -> Result<FipsResult, RandomizeError> {
+    Ok(FipsResult {
+        monobit: fips_monobit(data)?,
+        poker: poker_test(data)?.1,
+        long_run: fips_long_run(data)?,
+    })
+}
+
+// The 20 000-bit sample the FIPS 140-2 tests run on.
+fn fips_sample(data: &[u8]) -> Result<&[u8], RandomizeError> {
+    if data.len() * 8 < FIPS_SAMPLE_BITS {
+        return Err(RandomizeError::InsufficientData {
+            required_bits: FIPS_SAMPLE_BITS,
+            available_bits: data.len() * 8,
+        });
+    }
+    Ok(&data[..FIPS_SAMPLE_BITS / 8])
+}
+
+const MATRIX_SIZE: usize = 32;
+const MATRIX_RANK_MIN_BITS: usize = 38 * MATRIX_SIZE * MATRIX_SIZE;
+
+/// Rank over GF(2) of a binary matrix with up to 32 columns, one `u32` per row.
+pub fn gf2_rank(rows: &[u32]) -> usize {
+    let mut rows = rows.to_vec();
+    let mut rank = 0;
+    for column in (0..32).rev() {
+        let mask = 1u32 << column;
+        let Some(pivot) = (rank..rows.len()).find(|&row| rows[row] & mask != 0) else {
+            continue;
+        };
+        rows.swap(rank, pivot);
+        for row in 0..rows.len() {
+            if row != rank && rows[row] & mask != 0 {
+                rows[row] ^= rows[rank];
+            }
+        }
+        rank += 1;
+    }
+    rank
+}
+
+/// NIST SP800-22 binary matrix rank test over 32x32 matrices; returns the p-value.
+///
+/// Needs at least 38912 bits (38 matrices); leftover bits are discarded.
+pub fn matrix_rank_test(data: &[u8]) -> Result<f64, RandomizeError> {
+    matrix_rank_result(data).map(|result| result.p_value)
+}
+
+// The statistic is `χ²(obs)`.
+fn matrix_rank_result(data: &[u8]) -> Result<TestResult, RandomizeError> {
+    let bits = data.view_bits::<Msb0>();
+    if bits.len() < MATRIX_RANK_MIN_BITS {
+        return Err(RandomizeError::InsufficientData {
+            required_bits: MATRIX_RANK_MIN_BITS,
+            available_bits: bits.len(),
+        });
+    }
+
+    let mut full_rank = 0usize;
+    let mut one_below = 0usize;
+    let mut num_matrices = 0usize;
+    for matrix in bits.chunks_exact(MATRIX_SIZE * MATRIX_SIZE) {
+        let rows: Vec<u32> = matrix
+            .chunks_exact(MATRIX_SIZE)
+            .map(|row| row.load_be::<u32>())
+            .collect();
+        match gf2_rank(&rows) {
+            MATRIX_SIZE => full_rank += 1,
+            rank if rank == MATRIX_SIZE - 1 => one_below += 1,
+            _ => {}
+        }
+        num_matrices += 1;
+    }
+
+    let lower = num_matrices - full_rank - one_below;
+    let n = num_matrices as f64;
+    let chi_squared = [(full_rank, 0.2888), (one_below, 0.5776), (lower, 0.1336)]
+        .iter()
+        .map(|&(observed, probability)| {
+            (observed as f64 - n * probability).powi(2) / (n * probability)
+        })
+        .sum::<f64>();
+    Ok(TestResult::new(
+        "matrix_rank",
+        chi_squared,
+        (-chi_squared / 2.0).exp(),
+    ))
+}
+
+// (minimum input bits, L, expected value, variance) from the SP800-22 table
+// for Maurer's universal test, longest L first.
+const MAURER_PARAMETERS: [(usize, usize, f64, f64); 11] = [
+    (1_059_061_760, 16, 15.167379, 3.421),
+    (496_435_200, 15, 14.167488, 3.419),
+    (231_669_760, 14, 13.167693, 3.416),
+    (107_560_960, 13, 12.168070, 3.410),
+    (49_643_520, 12, 11.168765, 3.401),
+    (22_753_280, 11, 10.170032, 3.384),
+    (10_342_400, 10, 9.1723243, 3.356),
+    (4_654_080, 9, 8.1764248, 3.311),
+    (2_068_480, 8, 7.1836656, 3.238),
+    (904_960, 7, 6.1962507, 3.125),
+    (387_840, 6, 5.2177052, 2.954),
+];
+
+/// NIST SP800-22 Maurer's universal statistical test; returns the p-value.
+///
+/// The block length `L` is picked from the standard's table for the input
+/// length, with `Q = 10 * 2^L` initialization blocks. Inputs shorter than the
+/// 387840 bits needed for `L = 6` are rejected.
+pub fn maurer_universal_test(data: &[u8]) -> Result<f64, RandomizeError> {
+    maurer_universal_result(data).map(|result| result.p_value)
+}
+
+// The statistic is `f_n`.
+fn maurer_universal_result(data: &[u8]) -> Result<TestResult, RandomizeError> {
+    let bits = data.view_bits::<Msb0>();
+    let n = bits.len();
+    let Some(&(_, l, expected_value, variance)) = MAURER_PARAMETERS
+        .iter()
+        .find(|&&(min_bits, ..)| n >= min_bits)
+    else {
+        return Err(RandomizeError::InsufficientData {
+            required_bits: MAURER_PARAMETERS[MAURER_PARAMETERS.len() - 1].0,
+            available_bits: n,
+        });
+    };
+
+    let q = 10 * (1usize << l);
+    let k = n / l - q;
+    let block_value = |block: usize| bits[block * l..(block + 1) * l].load_be::<usize>();
+
+    // Blocks are numbered from 1, so 0 marks a pattern that hasn't occurred.
+    let mut last_seen = vec![0usize; 1 << l];
+    for block in 0..q {
+        last_seen[block_value(block)] = block + 1;
+    }
+
+    let mut sum = 0.0;
+    for block in q..q + k {
+        let pattern = block_value(block);
+        sum += ((block + 1 - last_seen[pattern]) as f64).log2();
+        last_seen[pattern] = block + 1;
+    }
+
+    let l = l as f64;
+    let k = k as f64;
+    let c = 0.7 - 0.8 / l + (4.0 + 32.0 / l) * k.powf(-3.0 / l) / 15.0;
+    let sigma = c * (variance / k).sqrt();
+    let statistic = sum / k;
+    let p_value = erfc((statistic - expected_value).abs() / (2f64.sqrt() * sigma));
+    Ok(TestResult::new("maurer_universal", statistic, p_value))
+}
+
+/// All aperiodic 2-bit templates for [`non_overlapping_template_test`]. A
+/// template is aperiodic if no proper shift of it overlaps itself.
+pub const APERIODIC_TEMPLATES_2: &[&[bool]] = &[&[false, true], &[true, false]];
+
+/// All aperiodic 3-bit templates, in ascending order.
+pub const APERIODIC_TEMPLATES_3: &[&[bool]] = &[
+    &[false, false, true],
+    &[false, true, true],
+    &[true, false, false],
+    &[true, true, false],
+];
+
+/// All aperiodic 4-bit templates, in ascending order.
+pub const APERIODIC_TEMPLATES_4: &[&[bool]] = &[
+    &[false, false, false, true],
+    &[false, false, true, true],
+    &[false, true, true, true],
+    &[true, false, false, false],
+    &[true, true, false, false],
+    &[true, true, true, false],
+];
+
+/// All aperiodic 5-bit templates, in ascending order.
+pub const APERIODIC_TEMPLATES_5: &[&[bool]] = &[
+    &[false, false, false, false, true],
+    &[false, false, false, true, true],
+    &[false, false, true, false, true],
+    &[false, false, true, true, true],
+    &[false, true, false, true, true],
+    &[false, true, true, true, true],
+    &[true, false, false, false, false],
+    &[true, false, true, false, false],
+    &[true, true, false, false, false],
+    &[true, true, false, true, false],
+    &[true, true, true, false, false],
+    &[true, true, true, true, false],
+];
+
+/// NIST SP800-22 non-overlapping template matching test; returns the p-value.
+///
+/// Counts the non-overlapping occurrences of `template` in each block of
+/// `block_size` bits and compares them with the count expected for random
+/// data. Trailing bits that don't fill a whole block are discarded. Returns
+/// `NaN` if there isn't a single complete block or the template doesn't fit
+/// in one.
+pub fn non_overlapping_template_test(data: &[u8], template: &[bool], block_size: usize) -> f64 {
+    non_overlapping_template_test_bits(data.view_bits::<Msb0>(), template, block_size)
+}
+
+/// [`non_overlapping_template_test`] over an unpacked bit stream of any length.
+pub fn non_overlapping_template_test_bits(
+    bits: &BitSlice<u8, Msb0>,
+    template: &[bool],
+    block_size: usize,
+) -> f64 {
+    non_overlapping_template_result_bits(bits, template, block_size).p_value
+}
+
+// The statistic is `χ²(obs)`.
+fn non_overlapping_template_result_bits(
+    bits: &BitSlice<u8, Msb0>,
+    template: &[bool],
+    block_size: usize,
+) -> TestResult {
+    let m = template.len();
+    let num_blocks = bits.len().checked_div(block_size).unwrap_or(0);
+    if num_blocks == 0 || m == 0 || m > block_size {
+        return TestResult::new("non_overlapping_template", f64::NAN, f64::NAN);
+    }
+
+    let template: BitVec<u8, Msb0> = template.iter().collect();
+    let block_size_f = block_size as f64;
+    let mean = (block_size - m + 1) as f64 / 2f64.powi(m as i32);
+    let variance =
+        block_size_f * (1.0 / 2f64.powi(m as i32) - (2 * m - 1) as f64 / 2f64.powi(2 * m as i32));
+
+    let chi_squared = bits
+        .chunks_exact(block_size)
+        .map(|block| {
+            let mut occurrences = 0;
+            let mut i = 0;
+            while i + m <= block_size {
+                if block[i..i + m] == template[..] {
+                    occurrences += 1;
+                    i += m;
+                } else {
+                    i += 1;
+                }
+            }
+            (occurrences as f64 - mean).powi(2) / variance
+        })
+        .sum::<f64>();
+
+    let p_value = igamc(num_blocks as f64 / 2.0, chi_squared / 2.0);
+    TestResult::new("non_overlapping_template", chi_squared, p_value)
+}
+
+/// Block size recommended by SP800-22 for [`overlapping_template_test`].
+pub const OVERLAPPING_TEMPLATE_BLOCK_SIZE: usize = 1032;
+
+// Occurrence counts 0 to 4 get a category each, 5 or more share the last.
+const OVERLAPPING_TEMPLATE_CATEGORIES: usize = 6;
+
+// SP800-22 tabulates corrected probabilities for its recommended parameters
+// (m = 9, M = 1032); the closed form below slightly misestimates them.
+const OVERLAPPING_TEMPLATE_STANDARD_PI: [f64; OVERLAPPING_TEMPLATE_CATEGORIES] =
+    [0.364091, 0.185659, 0.139381, 0.100571, 0.070432, 0.139865];
+
+/// NIST SP800-22 overlapping template matching test for the all-ones
+/// template of `template_len` bits; returns the p-value.
+///
+/// Overlapping occurrences are counted in each block of `block_size` bits and
+/// binned into six categories. The standard recommends `template_len = 9`
+/// with [`OVERLAPPING_TEMPLATE_BLOCK_SIZE`] and at least 10^6 bits. Inputs are
+/// rejected unless every category expects at least 5 blocks, e.g. fewer than
+/// 71 blocks (73272 bits) with the recommended parameters.
+pub fn overlapping_template_test(
+    data: &[u8],
+    template_len: usize,
+    block_size: usize,
+) -> Result<f64, RandomizeError> {
+    overlapping_template_result(data, template_len, block_size).map(|result| result.p_value)
+}
+
+// The statistic is `χ²(obs)`.
+fn overlapping_template_result(
+    data: &[u8],
+    template_len: usize,
+    block_size: usize,
+) -> Result<TestResult, RandomizeError> {
+    if template_len == 0 || template_len > block_size {
+        return Err(RandomizeError::InvalidParameter(format!(
+            "template length {} must be between 1 and the block size {}",
+            template_len, block_size
+        )));
+    }
+
+    let bits = data.view_bits::<Msb0>();
+    let pi = overlapping_template_probabilities(template_len, block_size);
+    let min_pi = pi.iter().copied().fold(f64::INFINITY, f64::min);
+    let required_blocks = (5.0 / min_pi).ceil() as usize;
+    let num_blocks = bits.len() / block_size;
+    if num_blocks < required_blocks {
+        return Err(RandomizeError::InsufficientData {
+            required_bits: required_blocks * block_size,
+            available_bits: bits.len(),
+        });
+    }
+
+    let mut observed = [0usize; OVERLAPPING_TEMPLATE_CATEGORIES];
+    for block in bits.chunks_exact(block_size) {
+        let mut run = 0;
+        let mut occurrences = 0;
+        for bit in block.iter().by_vals() {
+            run = if bit { run + 1 } else { 0 };
+            if run >= template_len {
+                occurrences += 1;
+            }
+        }
+        observed[occurrences.min(OVERLAPPING_TEMPLATE_CATEGORIES - 1)] += 1;
+    }
+
+    let n = num_blocks as f64;
+    let chi_squared = observed
+        .iter()
+        .zip(pi)
+        .map(|(&count, pi)| (count as f64 - n * pi).powi(2) / (n * pi))
+        .sum::<f64>();
+    let p_value = igamc(
+        (OVERLAPPING_TEMPLATE_CATEGORIES - 1) as f64 / 2.0,
+        chi_squared / 2.0,
+    );
+    Ok(TestResult::new(
+        "overlapping_template",
+        chi_squared,
+        p_value,
+    ))
+}
+
+fn overlapping_template_probabilities(
+    template_len: usize,
+    block_size: usize,
+) -> [f64; OVERLAPPING_TEMPLATE_CATEGORIES] {
+    if template_len == 9 && block_size == OVERLAPPING_TEMPLATE_BLOCK_SIZE {
+        return OVERLAPPING_TEMPLATE_STANDARD_PI;
+    }
+
+    let lambda = (block_size - template_len + 1) as f64 / 2f64.powi(template_len as i32);
+    let eta = lambda / 2.0;
+    let category = |u: usize| {
+        if u == 0 {
+            return (-eta).exp();
+        }
+        (1..=u)
+            .map(|l| {
+                let ln_term = -eta - u as f64 * 2f64.ln() + l as f64 * eta.ln()
+                    - ln_gamma(l as f64 + 1.0)
+                    + ln_gamma(u as f64)
+                    - ln_gamma(l as f64)
+                    - ln_gamma((u - l) as f64 + 1.0);
+                ln_term.exp()
+            })
+            .sum()
+    };
+    let mut pi: [f64; OVERLAPPING_TEMPLATE_CATEGORIES] = std::array::from_fn(category);
+    pi[OVERLAPPING_TEMPLATE_CATEGORIES - 1] = 1.0
+        - pi[..OVERLAPPING_TEMPLATE_CATEGORIES - 1]
+            .iter()
+            .sum::<f64>();
+    pi
+}
+
+/// Linear complexity of `bits`, the length of the shortest LFSR that
+/// generates them, found with the Berlekamp–Massey algorithm over GF(2).
+pub fn berlekamp_massey(bits: &BitSlice<u8, Msb0>) -> usize {
+    let n = bits.len();
+    let mut connection = vec![false; n + 1];
+    let mut previous = vec![false; n + 1];
+    connection[0] = true;
+    previous[0] = true;
+    let mut complexity = 0;
+    let mut last_change: isize = -1;
+
+    for i in 0..n {
+        let discrepancy = (1..=complexity).fold(bits[i], |discrepancy, j| {
+            discrepancy ^ (connection[j] & bits[i - j])
+        });
+        if !discrepancy {
+            continue;
+        }
+
+        let shift = (i as isize - last_change) as usize;
+        let saved = connection.clone();
+        for j in 0..=n - shift {
+            connection[j + shift] ^= previous[j];
+        }
+        if 2 * complexity <= i {
+            complexity = i + 1 - complexity;
+            last_change = i as isize;
+            previous = saved;
+        }
+    }
+    complexity
+}
+
+// Smallest block size SP800-22 recommends for the linear complexity test.
+const LINEAR_COMPLEXITY_BLOCK_SIZE: usize = 500;
+
+const LINEAR_COMPLEXITY_PI: [f64; 7] = [0.010417, 0.03125, 0.125, 0.5, 0.25, 0.0625, 0.020833];
+
+/// NIST SP800-22 linear complexity test; returns the p-value.
+///
+/// Compares the [linear complexity](berlekamp_massey) of each block of
+/// `block_size` bits with its expected value. The standard recommends
+/// `500 <= block_size <= 5000` and at least 200 blocks. Trailing bits that
+/// don't fill a whole block are discarded. Returns `NaN` if there isn't a
+/// single complete block.
+pub fn linear_complexity_test(data: &[u8], block_size: usize) -> f64 {
+    linear_complexity_result(data, block_size).p_value
+}
+
+// The statistic is `χ²(obs)`.
+fn linear_complexity_result(data: &[u8], block_size: usize) -> TestResult {
+    let bits = data.view_bits::<Msb0>();
+    let num_blocks = bits.len().checked_div(block_size).unwrap_or(0);
+    if num_blocks == 0 {
+        return TestResult::new("linear_complexity", f64::NAN, f64::NAN);
+    }
+
+    let m = block_size as f64;
+    let sign = if block_size.is_multiple_of(2) {
+        1.0
+    } else {
+        -1.0
+    };
+    let mean = m / 2.0 + (9.0 - sign) / 36.0 - (m / 3.0 + 2.0 / 9.0) / 2f64.powf(m);
+
+    let mut observed = [0usize; LINEAR_COMPLEXITY_PI.len()];
+    for block in bits.chunks_exact(block_size) {
+        let t = sign * (berlekamp_massey(block) as f64 - mean) + 2.0 / 9.0;
+        let category = if t <= -2.5 {
+            0
+        } else if t > 2.5 {
+            6
+        } else {
+            // (-2.5, -1.5] is category 1, ..., (1.5, 2.5] is category 5.
+            (t + 2.5).ceil().clamp(1.0, 5.0) as usize
+        };
+        observed[category] += 1;
+    }
+
+    let n = num_blocks as f64;
+    let chi_squared = observed
+        .iter()
+        .zip(LINEAR_COMPLEXITY_PI)
+        .map(|(&count, pi)| (count as f64 - n * pi).powi(2) / (n * pi))
+        .sum::<f64>();
+    TestResult::new(
+        "linear_complexity",
+        chi_squared,
+        igamc(3.0, chi_squared / 2.0),
+    )
+}
+
+// SP800-22 only applies the random excursions tests when the walk returns to
+// zero at least max(0.005 * sqrt(n), 500) times.
+const MIN_EXCURSION_CYCLES: usize = 500;
+
+/// NIST SP800-22 random excursions test; returns `(state, p-value)` for the
+/// states -4 to -1 and 1 to 4.
+///
+/// The ±1 random walk is split into cycles between returns to zero, and the
+/// number of cycles visiting each state exactly 0 to 4 and 5+ times is
+/// compared with its distribution for random data. Walks with too few cycles
+/// (at least 500, more for inputs over 10^10 bits) are rejected.
+pub fn random_excursions_test(data: &[u8]) -> Result<Vec<(i32, f64)>, RandomizeError> {
+    Ok(random_excursions_results(data)?
+        .into_iter()
+        .map(|(state, result)| (state, result.p_value))
+        .collect())
+}
+
+// The statistic is `χ²(obs)` for each state.
+fn random_excursions_results(data: &[u8]) -> Result<Vec<(i32, TestResult)>, RandomizeError> {
+    let cycles = excursion_cycles(data)?;
+    let num_cycles = cycles.len() as f64;
+
+    let results = [-4, -3, -2, -1, 1, 2, 3, 4]
+        .into_iter()
+        .map(|state: i32| {
+            let mut observed = [0usize; 6];
+            for cycle in &cycles {
+                let visits = cycle.iter().filter(|&&position| position == state).count();
+                observed[visits.min(5)] += 1;
+            }
+
+            let escape = 1.0 / (2.0 * state.abs() as f64);
+            let pi = |k: usize| match k {
+                0 => 1.0 - escape,
+                5 => escape * (1.0 - escape).powi(4),
+                _ => escape * escape * (1.0 - escape).powi(k as i32 - 1),
+            };
+            let chi_squared = observed
+                .iter()
+                .enumerate()
+                .map(|(k, &count)| {
+                    let expected = num_cycles * pi(k);
+                    (count as f64 - expected).powi(2) / expected
+                })
+                .sum::<f64>();
+            let name = format!("random_excursions({})", state);
+            let p_value = igamc(2.5, chi_squared / 2.0);
+            (state, TestResult::new(&name, chi_squared, p_value))
+        })
+        .collect();
+    Ok(results)
+}
+
+/// NIST SP800-22 random excursions variant test; returns `(state, p-value)`
+/// for the states -9 to -1 and 1 to 9.
+///
+/// Compares the total number of visits to each state with the number of
+/// cycles of the random walk. Walks with too few cycles are rejected as in
+/// [`random_excursions_test`].
+pub fn random_excursions_variant_test(data: &[u8]) -> Result<Vec<(i32, f64)>, RandomizeError> {
+    Ok(random_excursions_variant_results(data)?
+        .into_iter()
+        .map(|(state, result)| (state, result.p_value))
+        .collect())
+}
+
+// The statistic is the total number of visits `ξ(x)` to each state.
+fn random_excursions_variant_results(
+    data: &[u8],
+) -> Result<Vec<(i32, TestResult)>, RandomizeError> {
+    let cycles = excursion_cycles(data)?;
+    let num_cycles = cycles.len() as f64;
+
+    let results = (-9..=9)
+        .filter(|&state| state != 0)
+        .map(|state: i32| {
+            let visits = cycles
+                .iter()
+                .flatten()
+                .filter(|&&position| position == state)
+                .count() as f64;
+            let deviation = (visits - num_cycles).abs()
+                / (2.0 * num_cycles * (4.0 * state.abs() as f64 - 2.0)).sqrt();
+            let name = format!("random_excursions_variant({})", state);
+            (state, TestResult::new(&name, visits, erfc(deviation)))
+        })
+        .collect();
+    Ok(results)
+}
+
+// Positions of the random walk, split into the cycles between returns to
+// zero. A final cycle that doesn't return is closed as the standard does.
+fn excursion_cycles(data: &[u8]) -> Result<Vec<Vec<i32>>, RandomizeError> {
+    let bits = data.view_bits::<Msb0>();
+    let mut cycles = Vec::new();
+    let mut cycle = Vec::new();
+    let mut position = 0i32;
+    for bit in bits.iter().by_vals() {
+        position += if bit { 1 } else { -1 };
+        if position == 0 {
+            cycles.push(std::mem::take(&mut cycle));
+        } else {
+            cycle.push(position);
+        }
+    }
+    if !cycle.is_empty() || cycles.is_empty() {
+        cycles.push(cycle);
+    }
+
+    let required = MIN_EXCURSION_CYCLES.max((0.005 * (bits.len() as f64).sqrt()) as usize);
+    if cycles.len() < required {
+        return Err(RandomizeError::TooFewCycles {
+            required,
+            available: cycles.len(),
+        });
+    }
+    Ok(cycles)
+}
@@ -0,0 +1,213 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Read};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::RandomizeError;
+use crate::pipeline::record_random_data;
+use crate::recording::SAMPLE_RATE;
+
+const DEFAULT_CHUNK_DURATION_MILLIS: u64 = 250;
+const DEFAULT_NUM_LSB: u32 = 8;
+// Number of extracted chunks buffered ahead of the consumer.
+const CHUNK_QUEUE_CAPACITY: usize = 4;
+
+/// An endless stream of random bytes extracted from the default input device.
+///
+/// A background thread keeps recording short chunks of audio and queues the
+/// extracted bytes, so `next()` only blocks when the consumer outpaces the
+/// microphone. Every adjacent-sample difference contributes `num_lsb` bits,
+/// so expect roughly `sample_rate * num_lsb / 8` bytes per second (about
+/// 44 kB/s at 44.1 kHz with `num_lsb = 8`). The iterator ends if recording fails.
+///
+/// ```no_run
+/// use randomize7::RandomByteStream;
+///
+/// let key: Vec<u8> = RandomByteStream::new().take(32).collect();
+/// ```
+///
+/// It is also a [`Read`]er, which reports a failed recording as an error.
+/// With both traits in scope, methods like `take` need naming the trait:
+///
+/// ```no_run
+/// use randomize7::RandomByteStream;
+/// use std::io::Read;
+///
+/// let mut file = std::fs::File::create("random.bin")?;
+/// std::io::copy(&mut Read::take(RandomByteStream::new(), 1 << 20), &mut file)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// Recording only runs ahead of the consumer by a few chunks: once they are
+/// queued the background thread blocks until one is taken.
+pub struct RandomByteStream {
+    buffer: VecDeque<u8>,
+    chunks: Receiver<Result<Vec<u8>, RandomizeError>>,
+    limiter: Option<TokenBucket>,
+}
+
+impl RandomByteStream {
+    pub fn new() -> Self {
+        RandomByteStream::with_config(
+            Duration::from_millis(DEFAULT_CHUNK_DURATION_MILLIS),
+            DEFAULT_NUM_LSB,
+        )
+    }
+
+    pub fn with_config(chunk_duration: Duration, num_lsb: u32) -> Self {
+        let (sender, chunks) = mpsc::sync_channel(CHUNK_QUEUE_CAPACITY);
+        let chunk_length =
+            (SAMPLE_RATE as f64 * chunk_duration.as_secs_f64() * num_lsb as f64 / 8.0) as usize;
+
+        thread::spawn(move || {
+            loop {
+                let random_data = record_random_data(chunk_duration, num_lsb, chunk_length.max(1));
+                let failed = random_data.is_err();
+                // The receiver hangs up when the stream is dropped.
+                if sender.send(random_data).is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        RandomByteStream {
+            buffer: VecDeque::new(),
+            chunks,
+            limiter: None,
+        }
+    }
+
+    /// Emits at most `bytes_per_second` on average, sleeping in `next()` and
+    /// `read` once the allowance is spent. After the consumer pauses, up to
+    /// one second's worth can be read at once.
+    ///
+    /// Excess output stays queued, and recording stops while the queue is
+    /// full, so a slow consumer costs little CPU. The trade-off is in the
+    /// chunk duration given to [`with_config`](Self::with_config): short
+    /// chunks keep the bytes fresh and the first read fast, but every chunk
+    /// is a separate recording with its own warm-up, so they cost more per
+    /// byte. At low rates, prefer chunks long enough to cover a few seconds
+    /// of output.
+    ///
+    /// ```no_run
+    /// use randomize7::RandomByteStream;
+    ///
+    /// let trickle = RandomByteStream::new().rate_limited(64);
+    /// for byte in trickle.take(256) {
+    ///     # let _ = byte;
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes_per_second` is 0.
+    pub fn rate_limited(mut self, bytes_per_second: u64) -> Self {
+        assert!(bytes_per_second > 0, "the rate limit must be positive");
+        self.limiter = Some(TokenBucket::new(bytes_per_second as f64));
+        self
+    }
+}
+
+// Allows `rate` bytes per second, holding at most one second's worth.
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: f64) -> Self {
+        TokenBucket {
+            rate,
+            tokens: 0.0,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    // Waits until at least one byte is allowed and takes up to `wanted`.
+    fn take(&mut self, wanted: usize) -> usize {
+        self.refill();
+        if self.tokens < 1.0 {
+            thread::sleep(Duration::from_secs_f64((1.0 - self.tokens) / self.rate));
+            self.refill();
+        }
+        let granted = wanted.min(self.tokens as usize).max(1);
+        self.tokens -= granted as f64;
+        granted
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate.max(1.0));
+        self.refilled_at = now;
+    }
+}
+
+/// Shows how many bytes are buffered and the rate limit, never the bytes.
+impl fmt::Debug for RandomByteStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RandomByteStream")
+            .field("buffered", &format_args!("{} bytes", self.buffer.len()))
+            .field(
+                "bytes_per_second",
+                &self.limiter.as_ref().map(|limiter| limiter.rate),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for RandomByteStream {
+    fn default() -> Self {
+        RandomByteStream::new()
+    }
+}
+
+impl Iterator for RandomByteStream {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        while self.buffer.is_empty() {
+            self.buffer.extend(self.chunks.recv().ok()?.ok()?);
+        }
+        if let Some(limiter) = &mut self.limiter {
+            limiter.take(1);
+        }
+        self.buffer.pop_front()
+    }
+}
+
+impl Read for RandomByteStream {
+    /// Fills as much of `buf` as the queued bytes allow, blocking for the
+    /// next chunk only when none are queued, so it returns 0 only for an
+    /// empty `buf`. A failed recording is returned as an error, and every
+    /// read after it fails too.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while self.buffer.is_empty() {
+            match self.chunks.recv() {
+                Ok(Ok(random_data)) => self.buffer.extend(random_data),
+                Ok(Err(err)) => return Err(io::Error::other(err)),
+                Err(_) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::BrokenPipe,
+                        "the recording thread has stopped",
+                    ))
+                }
+            }
+        }
+
+        let mut length = buf.len().min(self.buffer.len());
+        if let Some(limiter) = &mut self.limiter {
+            length = limiter.take(length);
+        }
+        for (target, byte) in buf.iter_mut().zip(self.buffer.drain(..length)) {
+            *target = byte;
+        }
+        Ok(length)
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::error::RandomizeError;
+use crate::statistical_tests::{
+    overall_verdict_at, run_tests, Recommendation, TestKind, Verdict, SIGNIFICANCE_LEVEL,
+};
+
+/// When and how [`Supervisor`] re-tests its output and recovers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetestPolicy {
+    /// Output between two runs of the battery.
+    pub interval_bytes: u64,
+    /// Most recent output the battery runs on; SP800-22 asks for at least
+    /// 10^6 bits.
+    pub sample_bytes: usize,
+    pub significance_level: f64,
+    /// Pause before the first recovery attempt; doubled after every failed
+    /// one up to `max_backoff`.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Failed recovery attempts after which the supervisor gives up.
+    pub max_attempts: u32,
+}
+
+impl Default for RetestPolicy {
+    /// Every MiB, on the last 125 000 bytes, backing off from 1 s to a
+    /// minute over at most 10 attempts.
+    fn default() -> Self {
+        RetestPolicy {
+            interval_bytes: 1 << 20,
+            sample_bytes: 125_000,
+            significance_level: SIGNIFICANCE_LEVEL,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            max_attempts: 10,
+        }
+    }
+}
+
+/// What a [`Supervisor`] reports through [`on_event`](Supervisor::on_event).
+#[derive(Debug, Clone, PartialEq)]
+pub enum SupervisorEvent {
+    /// The battery ran on recent output; `attempt` is 0 for a scheduled
+    /// re-test and counts recovery attempts otherwise.
+    Retest {
+        bytes_emitted: u64,
+        attempt: u32,
+        verdict: Verdict,
+        passed: bool,
+    },
+    /// A re-test failed; output is withheld while fresh entropy is recorded
+    /// after `backoff`.
+    Recovering { attempt: u32, backoff: Duration },
+    /// Fresh output passed and emission resumes.
+    Recovered { attempts: u32 },
+}
+
+type EventHandler = Box<dyn FnMut(&SupervisorEvent) + Send>;
+
+/// Runs a byte source as a long-lived service: every
+/// [`interval_bytes`](RetestPolicy::interval_bytes) the full battery is run
+/// on the most recent output, and if it is rejected nothing more is emitted
+/// until freshly generated output passes again.
+///
+/// Recovery discards the recent output, waits, and asks the source for
+/// [`sample_bytes`](RetestPolicy::sample_bytes) of new output to test,
+/// which is never emitted. Each failed attempt doubles the wait. After
+/// [`max_attempts`](RetestPolicy::max_attempts) failures
+/// [`next_batch`](Self::next_batch) returns
+/// [`RandomizeError::HealthCheckFailed`]. Every event is also logged.
+///
+/// ```no_run
+/// use randomize7::{record_random_data, Supervisor};
+/// use std::time::Duration;
+///
+/// let mut supervisor =
+///     Supervisor::new(|| record_random_data(Duration::from_secs(1), 8, 44_100));
+/// loop {
+///     let batch = supervisor.next_batch()?;
+///     # let _ = batch;
+/// }
+/// # Ok::<(), randomize7::RandomizeError>(())
+/// ```
+pub struct Supervisor<F> {
+    source: F,
+    policy: RetestPolicy,
+    on_event: Option<EventHandler>,
+    recent: VecDeque<u8>,
+    since_retest: u64,
+    bytes_emitted: u64,
+}
+
+impl<F> Supervisor<F>
+where
+    F: FnMut() -> Result<Vec<u8>, RandomizeError>,
+{
+    /// Supervises `source`, which returns one batch of output per call,
+    /// under the default [`RetestPolicy`].
+    pub fn new(source: F) -> Self {
+        Supervisor {
+            source,
+            policy: RetestPolicy::default(),
+            on_event: None,
+            recent: VecDeque::new(),
+            since_retest: 0,
+            bytes_emitted: 0,
+        }
+    }
+
+    pub fn policy(mut self, policy: RetestPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Calls `on_event` with every event, in addition to logging it.
+    pub fn on_event(mut self, on_event: impl FnMut(&SupervisorEvent) + Send + 'static) -> Self {
+        self.on_event = Some(Box::new(on_event));
+        self
+    }
+
+    /// Bytes handed out so far.
+    pub fn bytes_emitted(&self) -> u64 {
+        self.bytes_emitted
+    }
+
+    /// The source's next batch, once it and the output before it have
+    /// passed any re-test that fell due.
+    pub fn next_batch(&mut self) -> Result<Vec<u8>, RandomizeError> {
+        let mut batch = (self.source)()?;
+        self.remember(&batch);
+        self.since_retest += batch.len() as u64;
+        if self.since_retest >= self.policy.interval_bytes {
+            self.since_retest = 0;
+            if !self.retest(0) {
+                self.recover()?;
+                batch = (self.source)()?;
+                self.remember(&batch);
+            }
+        }
+        self.bytes_emitted += batch.len() as u64;
+        Ok(batch)
+    }
+
+    fn recover(&mut self) -> Result<(), RandomizeError> {
+        let mut backoff = self.policy.initial_backoff;
+        for attempt in 1..=self.policy.max_attempts {
+            self.emit(SupervisorEvent::Recovering { attempt, backoff });
+            std::thread::sleep(backoff);
+            backoff = (backoff * 2).min(self.policy.max_backoff);
+
+            self.recent.clear();
+            while self.recent.len() < self.policy.sample_bytes {
+                let fresh = (self.source)()?;
+                if fresh.is_empty() {
+                    return Err(RandomizeError::EmptyRecording);
+                }
+                self.remember(&fresh);
+            }
+            if self.retest(attempt) {
+                self.emit(SupervisorEvent::Recovered { attempts: attempt });
+                return Ok(());
+            }
+        }
+        Err(RandomizeError::HealthCheckFailed {
+            failing_tests: vec!["supervisor_retest".to_string()],
+        })
+    }
+
+    // Runs the battery on the recent output and reports the result.
+    fn retest(&mut self, attempt: u32) -> bool {
+        let sample: Vec<u8> = self.recent.iter().copied().collect();
+        let significance_level = self.policy.significance_level;
+        let results = run_tests(&sample, &TestKind::ALL, significance_level).results;
+        let verdict = overall_verdict_at(&results, significance_level);
+        let passed = verdict.recommendation != Recommendation::Reject;
+        self.emit(SupervisorEvent::Retest {
+            bytes_emitted: self.bytes_emitted,
+            attempt,
+            verdict,
+            passed,
+        });
+        passed
+    }
+
+    // Keeps the last `sample_bytes` of output for the next re-test.
+    fn remember(&mut self, bytes: &[u8]) {
+        self.recent.extend(bytes);
+        let excess = self.recent.len().saturating_sub(self.policy.sample_bytes);
+        self.recent.drain(..excess);
+    }
+
+    fn emit(&mut self, event: SupervisorEvent) {
+        match &event {
+            SupervisorEvent::Retest { passed: true, .. } => log::info!("{:?}", event),
+            SupervisorEvent::Recovered { .. } => log::info!("{:?}", event),
+            _ => log::warn!("{:?}", event),
+        }
+        if let Some(on_event) = &mut self.on_event {
+            on_event(&event);
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use wasm_bindgen::prelude::*;
+
+use crate::extraction::{process_samples, ExtractParams};
+
+/// Runs the extraction pipeline over samples captured outside the crate,
+/// e.g. from a Web Audio `AudioBuffer` in the browser.
+#[wasm_bindgen]
+pub fn extract_from_buffer(
+    samples: &[f32],
+    num_lsb: u32,
+    output_length: usize,
+) -> Result<Vec<u8>, JsError> {
+    process_samples(samples, ExtractParams::new(num_lsb, output_length))
+        .map_err(|err| JsError::new(&err.to_string()))
+}
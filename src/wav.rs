@@ -0,0 +1,96 @@
+use std::path::Path;
+
+use crate::error::RandomizeError;
+use crate::extraction::{process_samples, ExtractParams};
+use crate::processing::downmix;
+use crate::source::Audio;
+
+/// Runs the extraction pipeline over a WAV file instead of a live recording.
+///
+/// Integer PCM and float files are both accepted; integer samples are scaled
+/// to [-1, 1) and the file's own sample rate is used as-is. Files with more
+/// than one channel are downmixed to mono first.
+pub fn extract_from_wav(
+    path: &Path,
+    num_lsb: u32,
+    output_length: usize,
+) -> Result<Vec<u8>, RandomizeError> {
+    let audio = read_wav(path)?;
+    let samples = downmix(&audio.samples, audio.channels);
+    process_samples(&samples, ExtractParams::new(num_lsb, output_length))
+}
+
+/// Decodes a WAV file to interleaved `f32` samples with its format. 8 to
+/// 32-bit PCM, including 24-bit and WAVE_FORMAT_EXTENSIBLE files, and float32
+/// are read with any number of channels; split them with
+/// [`deinterleave`](crate::deinterleave) or mix them with [`downmix`].
+pub fn read_wav(path: &Path) -> Result<Audio, RandomizeError> {
+    let mut reader = hound::WavReader::open(path).map_err(RandomizeError::Wav)?;
+    let spec = reader.spec();
+    Ok(Audio {
+        samples: decode(&mut reader, spec)?,
+        sample_rate: spec.sample_rate,
+        channels: spec.channels,
+        buffers: Vec::new(),
+        dropped_callbacks: 0,
+        callback_intervals: Vec::new(),
+    })
+}
+
+/// Decodes every sample of a WAV file to `f32`, interleaved.
+pub fn read_wav_samples(path: &Path) -> Result<Vec<f32>, RandomizeError> {
+    read_wav(path).map(|audio| audio.samples)
+}
+
+// hound sign-extends packed 24-bit samples into the i32s.
+fn decode<R: std::io::Read>(
+    reader: &mut hound::WavReader<R>,
+    spec: hound::WavSpec,
+) -> Result<Vec<f32>, RandomizeError> {
+    match spec.sample_format {
+        hound::SampleFormat::Float => reader
+            .samples::<f32>()
+            .collect::<Result<_, _>>()
+            .map_err(RandomizeError::Wav),
+        hound::SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .map(|sample| sample.map(|sample| sample as f32 / scale))
+                .collect::<Result<_, _>>()
+                .map_err(RandomizeError::Wav)
+        }
+    }
+}
+
+/// Writes the samples bytes were extracted from, after processing, as a
+/// 32-bit float mono WAV file, one sample per frame even if they came
+/// interleaved. Reading it back with [`read_wav`] returns the same samples,
+/// so a suspicious extraction can be replayed.
+pub fn write_recording_wav(
+    samples: &[f32],
+    sample_rate: u32,
+    path: &Path,
+) -> Result<(), RandomizeError> {
+    write_wav(path, samples, sample_rate, 1)
+}
+
+/// Writes interleaved samples as a 32-bit float WAV file.
+pub fn write_wav(
+    path: &Path,
+    samples: &[f32],
+    sample_rate: u32,
+    channels: u16,
+) -> Result<(), RandomizeError> {
+    let spec = hound::WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let mut writer = hound::WavWriter::create(path, spec).map_err(RandomizeError::Wav)?;
+    for &sample in samples {
+        writer.write_sample(sample).map_err(RandomizeError::Wav)?;
+    }
+    writer.finalize().map_err(RandomizeError::Wav)
+}
@@ -0,0 +1,58 @@
+use randomize7::{
+    process_samples, whiten_sha256, EntropyBatch, ExtractParams, ExtractStrategy, HealthPolicy,
+    RandomizeError, SampleSource, Synthetic,
+};
+use std::time::Duration;
+
+fn noise() -> Vec<f32> {
+    Synthetic::new(6)
+        .record(Duration::from_millis(100))
+        .unwrap()
+        .samples
+}
+
+fn params(whiten: bool) -> ExtractParams {
+    ExtractParams {
+        strategy: ExtractStrategy::XorDifferences { window: 4 },
+        whiten,
+        ..ExtractParams::new(8, 1102)
+    }
+}
+
+#[test]
+fn noisy_samples_make_a_batch() {
+    let samples = noise();
+    let batch = EntropyBatch::try_from(&samples).unwrap();
+    let extracted = process_samples(&samples, params(false)).unwrap();
+    assert_eq!(batch[..], extracted[..]);
+    assert_eq!(format!("{:?}", batch), "EntropyBatch(1102 bytes)");
+
+    let whitened =
+        EntropyBatch::from_samples(&samples, params(true), &HealthPolicy::default()).unwrap();
+    assert_eq!(whitened.into_vec(), whiten_sha256(&extracted, 1102));
+}
+
+#[test]
+fn poor_samples_are_rejected() {
+    let constant = vec![0.25; 4410];
+    assert!(matches!(
+        EntropyBatch::try_from(constant.as_slice()),
+        Err(RandomizeError::HealthCheckFailed { .. })
+    ));
+    // Whitening would make the constant look random, but runs after the gate.
+    assert!(matches!(
+        EntropyBatch::from_samples(&constant, params(true), &HealthPolicy::default()),
+        Err(RandomizeError::HealthCheckFailed { .. })
+    ));
+
+    let clipped: Vec<f32> = noise().iter().map(|sample| sample * 50.0).collect();
+    assert!(matches!(
+        EntropyBatch::try_from(&clipped),
+        Err(RandomizeError::HealthCheckFailed { failing_tests }) if failing_tests == ["clipping"]
+    ));
+
+    assert!(matches!(
+        EntropyBatch::try_from(&[][..]),
+        Err(RandomizeError::EmptyRecording)
+    ));
+}
@@ -0,0 +1,548 @@
+#![cfg(feature = "microphone")]
+
+use randomize7::{
+    entropy_capped_length, extract_random_data, whiten_sha256, Audio, BiasSummary, CaptureConfig,
+    CaptureStats, Conditioner, EntropyCollector, ExtractStrategy, HealthPolicy, Jitter,
+    RandomizeError, SampleSource, Synthetic,
+};
+use std::time::Duration;
+
+fn collector(source: impl SampleSource + 'static) -> EntropyCollector {
+    EntropyCollector::new()
+        .duration(Duration::from_millis(500))
+        .source(source)
+}
+
+#[test]
+fn synthetic_source_is_deterministic() {
+    let first = collector(Synthetic::new(7))
+        .collect_random_data(8, 64)
+        .unwrap();
+    let second = collector(Synthetic::new(7))
+        .collect_random_data(8, 64)
+        .unwrap();
+    let other_seed = collector(Synthetic::new(8))
+        .collect_random_data(8, 64)
+        .unwrap();
+
+    assert_eq!(first.len(), 64);
+    assert_eq!(first, second);
+    assert_ne!(first, other_seed);
+}
+
+#[test]
+fn synthetic_recordings_continue_the_signal() {
+    let source = Synthetic::new(7).tone(440.0, 0.5);
+    let first = source.record(Duration::from_millis(100)).unwrap();
+    let second = source.record(Duration::from_millis(100)).unwrap();
+    let whole = Synthetic::new(7)
+        .tone(440.0, 0.5)
+        .record(Duration::from_millis(200))
+        .unwrap();
+
+    assert_eq!([first.samples, second.samples].concat(), whole.samples);
+}
+
+#[test]
+fn clipped_source_fails_health_policy() {
+    let result = collector(Synthetic::new(7).gain(50.0))
+        .health_policy(HealthPolicy::default())
+        .collect_random_data(8, 64);
+
+    assert!(matches!(
+        result,
+        Err(RandomizeError::HealthCheckFailed { failing_tests }) if failing_tests == ["clipping"]
+    ));
+}
+
+#[test]
+fn silent_source_fails_health_policy() {
+    let result = collector(Synthetic::silence())
+        .health_policy(HealthPolicy::default())
+        .collect_random_data(8, 64);
+
+    assert!(matches!(
+        result,
+        Err(RandomizeError::HealthCheckFailed { .. })
+    ));
+}
+
+#[test]
+fn collection_stages_report_throughput() {
+    let stages = collector(Synthetic::new(7))
+        .debias(true)
+        .collect_stages(8, 64)
+        .unwrap();
+
+    // Debiasing keeps a variable share of the bits, so a batch can fall short.
+    assert!(!stages.random_data.is_empty() && stages.random_data.len() <= 64);
+    assert_eq!(stages.normalized.len(), stages.raw.len());
+    assert!(stages.bytes_per_second() > 0.0 && stages.bytes_per_second().is_finite());
+}
+
+#[test]
+fn fill_bytes_records_until_the_buffer_is_full() {
+    let short = |seed| {
+        EntropyCollector::new()
+            .duration(Duration::from_millis(50))
+            .source(Synthetic::new(seed))
+    };
+    // 50 ms at 44.1 kHz holds about 2200 bytes of 8-bit differences.
+    let mut buf = [0u8; 10000];
+    short(7).fill_bytes(8, &mut buf).unwrap();
+
+    let first_recording = short(7).collect_random_data(8, buf.len()).unwrap();
+    assert!(first_recording.len() < buf.len());
+    assert_eq!(buf[..first_recording.len()], first_recording[..]);
+    assert!(buf[first_recording.len()..].iter().any(|&byte| byte != 0));
+}
+
+#[test]
+fn warmup_is_dropped_from_each_recording() {
+    let source = || Synthetic::new(7).sample_rate(8000).channels(2);
+    let samples = EntropyCollector::new()
+        .duration(Duration::from_millis(500))
+        .warmup(Duration::from_millis(50))
+        .source(source())
+        .collect()
+        .unwrap();
+
+    // 50 ms of 8 kHz stereo is 400 frames of 2 samples.
+    let whole = source().record(Duration::from_millis(550)).unwrap();
+    assert_eq!(samples.len(), 8000);
+    assert_eq!(samples, whole.samples[800..]);
+}
+
+struct Reversed;
+
+impl Conditioner for Reversed {
+    fn condition(&self, raw: &[u8], output_length: usize) -> Vec<u8> {
+        raw.iter().rev().take(output_length).copied().collect()
+    }
+}
+
+#[test]
+fn whitening_conditions_the_extracted_bytes() {
+    let raw = collector(Synthetic::new(7))
+        .collect_random_data(8, 64)
+        .unwrap();
+    let whitened = collector(Synthetic::new(7))
+        .whiten(true)
+        .full_rate(true)
+        .collect_random_data(8, 64)
+        .unwrap();
+    let reversed = collector(Synthetic::new(7))
+        .conditioner(Reversed)
+        .full_rate(true)
+        .collect_random_data(8, 64)
+        .unwrap();
+
+    assert_eq!(whitened, whiten_sha256(&raw, 64));
+    assert_eq!(reversed, raw.iter().rev().copied().collect::<Vec<u8>>());
+}
+
+#[test]
+fn collection_stages_keep_the_unconditioned_bytes() {
+    let stages = collector(Synthetic::new(7))
+        .whiten(true)
+        .full_rate(true)
+        .collect_stages(8, 64)
+        .unwrap();
+    assert_eq!(stages.random_data, whiten_sha256(&stages.extracted, 64));
+
+    let report = stages.bias_report();
+    assert_eq!(report.raw, BiasSummary::of(&stages.extracted));
+    assert_eq!(report.conditioned, BiasSummary::of(&stages.random_data));
+}
+
+// A virtual device stuck replaying one non-silent buffer.
+struct Replaying(Vec<f32>);
+
+impl SampleSource for Replaying {
+    fn record(&self, duration: Duration) -> Result<Audio, RandomizeError> {
+        let count = (44100.0 * duration.as_secs_f64()) as usize / self.0.len();
+        Ok(Audio {
+            samples: self.0.repeat(count),
+            sample_rate: 44100,
+            channels: 1,
+            buffers: vec![self.0.len(); count],
+            dropped_callbacks: 0,
+            callback_intervals: Vec::new(),
+        })
+    }
+}
+
+#[test]
+fn repeated_buffers_fail_health_policy() {
+    let buffer: Vec<f32> = (0..441).map(|i| (i as f32 * 0.37).sin() * 0.3).collect();
+    let result = EntropyCollector::new()
+        .duration(Duration::from_millis(500))
+        .source(Replaying(buffer))
+        .health_policy(HealthPolicy::default())
+        .collect_random_data(8, 64);
+
+    // 550 ms of 10 ms buffers, the first 5 dropped as warm-up.
+    assert!(matches!(
+        result,
+        Err(RandomizeError::RepeatedBuffer { repetitions: 50 })
+    ));
+}
+
+#[test]
+fn duration_for_output_records_just_enough_samples() {
+    for (num_lsb, output_length, debias) in [(8, 1000, false), (3, 4410, false), (8, 7, true)] {
+        let stages = EntropyCollector::new()
+            .source(Synthetic::new(7))
+            .warmup(Duration::ZERO)
+            .debias(debias)
+            .duration_for_output(true)
+            .collect_stages(num_lsb, output_length)
+            .unwrap();
+
+        let expansion = if debias { 4 } else { 1 };
+        let differences = (output_length * 8 * expansion).div_ceil(num_lsb as usize);
+        assert_eq!(stages.raw.len(), differences + 1);
+        if !debias {
+            assert_eq!(stages.random_data.len(), output_length);
+        }
+    }
+}
+
+#[test]
+fn conditioned_output_is_capped_at_the_min_entropy() {
+    let capped = collector(Synthetic::new(7))
+        .whiten(true)
+        .collect_random_data(8, 64)
+        .unwrap();
+    let raw = collector(Synthetic::new(7))
+        .collect_random_data(8, 64)
+        .unwrap();
+    let length = entropy_capped_length(&raw, 64);
+    assert!(length < 64);
+    assert_eq!(capped, whiten_sha256(&raw, length));
+}
+
+#[test]
+fn max_samples_stops_before_the_duration() {
+    let samples = collector(Synthetic::new(7).channels(2))
+        .max_samples(8820)
+        .collect()
+        .unwrap();
+    assert_eq!(samples.len(), 8820);
+}
+
+#[test]
+fn odd_sized_buffers_capture_exactly_max_samples() {
+    let source = Synthetic::new(7).buffer_size(333);
+    let audio = source.record(Duration::from_millis(100)).unwrap();
+    assert_eq!(audio.buffers.iter().sum::<usize>(), audio.samples.len());
+    assert_eq!(audio.buffers.last(), Some(&(4410 % 333)));
+
+    // The 2205-sample warm-up and the cap both end inside a buffer.
+    let stages = collector(Synthetic::new(7).buffer_size(333))
+        .max_samples(10_000)
+        .collect_stages(8, 16)
+        .unwrap();
+    assert_eq!(stages.raw.len(), 10_000);
+    assert_eq!(stages.capture.captured_samples, 10_000);
+    assert!(!stages.capture.is_short());
+}
+
+#[test]
+fn only_the_microphone_reports_a_stream_config() {
+    let stages = collector(Synthetic::new(7)).collect_stages(8, 16).unwrap();
+    assert_eq!(stages.stream_config, None);
+
+    let config = CaptureConfig {
+        sample_rate: 48_000,
+        channels: 2,
+        sample_format: randomize7::cpal::SampleFormat::I16,
+        buffer_size: Some(512),
+    };
+    assert_eq!(
+        config.to_string(),
+        "2 channels of I16 at 48000 Hz in buffers of 512 frames"
+    );
+}
+
+#[test]
+fn fold_into_seed_conditions_enough_min_entropy() {
+    let (seed, rounds) = collector(Synthetic::new(7))
+        .fold_into_seed::<32>(8)
+        .unwrap();
+    assert_eq!(rounds, 1);
+    let raw = collector(Synthetic::new(7))
+        .collect_random_data(8, 256)
+        .unwrap();
+    assert_eq!(seed.to_vec(), whiten_sha256(&raw, 32));
+
+    // 100 samples a round justify only part of a 64-byte seed.
+    let (_, rounds) = collector(Synthetic::new(7))
+        .max_samples(100)
+        .fold_into_seed::<64>(8)
+        .unwrap();
+    assert!(rounds > 1);
+
+    assert!(matches!(
+        collector(Synthetic::silence()).fold_into_seed::<32>(8),
+        Err(RandomizeError::InsufficientData {
+            required_bits: 256,
+            available_bits: 0,
+        })
+    ));
+}
+
+#[test]
+fn duration_stops_before_max_samples() {
+    // 500 ms of stereo at 44.1 kHz.
+    let samples = collector(Synthetic::new(7).channels(2))
+        .max_samples(88200)
+        .collect()
+        .unwrap();
+    assert_eq!(samples.len(), 44100);
+}
+
+#[test]
+fn normalization_and_dc_removal_can_be_skipped() {
+    let stages = collector(Synthetic::new(7))
+        .remove_dc(false)
+        .normalize(false)
+        .collect_stages(8, 256)
+        .unwrap();
+    assert_eq!(stages.dc_removed, stages.filtered);
+    assert_eq!(stages.normalized, stages.filtered);
+
+    let skipped = collector(Synthetic::new(7))
+        .remove_dc(false)
+        .normalize(false)
+        .collect_random_data(8, 256)
+        .unwrap();
+    let expected = extract_random_data(
+        &stages.filtered,
+        8,
+        256,
+        ExtractStrategy::default(),
+        false,
+        false,
+    )
+    .unwrap();
+    assert_eq!(skipped, expected);
+
+    let default = collector(Synthetic::new(7))
+        .collect_random_data(8, 256)
+        .unwrap();
+    assert_ne!(skipped, default);
+}
+
+#[test]
+fn correlated_source_fails_the_autocorrelation_gate() {
+    // The low bits of a noiseless low-frequency tone change slowly.
+    let result = collector(Synthetic::new(7).noise(0.0).tone(50.0, 0.5))
+        .max_autocorrelation(0.05)
+        .collect_random_data(8, 4096);
+    assert!(
+        matches!(result, Err(RandomizeError::AutocorrelationTooHigh { measured }) if measured > 0.05),
+        "{:?}",
+        result
+    );
+
+    assert!(collector(Synthetic::new(7))
+        .max_autocorrelation(0.05)
+        .collect_random_data(8, 4096)
+        .is_ok());
+}
+
+// A device that stops delivering halfway through the recording.
+struct Stalling;
+
+impl SampleSource for Stalling {
+    fn record(&self, duration: Duration) -> Result<Audio, RandomizeError> {
+        let mut audio = Synthetic::new(7).record(duration)?;
+        audio.samples.truncate(audio.samples.len() / 2);
+        audio.dropped_callbacks = 3;
+        Ok(audio)
+    }
+}
+
+#[test]
+fn capture_stats_report_short_recordings() {
+    let complete = collector(Synthetic::new(7)).collect_stages(8, 64).unwrap();
+    assert_eq!(
+        complete.capture,
+        CaptureStats {
+            requested_samples: 22_050,
+            captured_samples: 22_050,
+            dropped_callbacks: 0,
+        }
+    );
+    assert!(!complete.capture.is_short());
+
+    let short = EntropyCollector::new()
+        .duration(Duration::from_millis(500))
+        .source(Stalling)
+        .collect_stages(8, 64)
+        .unwrap()
+        .capture;
+    assert_eq!(short.requested_samples, 22_050);
+    assert_eq!(short.dropped_callbacks, 3);
+    assert!(short.is_short());
+}
+
+#[test]
+fn stalled_source_times_out() {
+    let result =
+        collector(Synthetic::new(7).stall_after(Duration::ZERO)).collect_with_timeout(8, 64);
+    assert!(
+        matches!(
+            result,
+            Err(RandomizeError::Timeout {
+                captured_samples: 0,
+                ..
+            })
+        ),
+        "{:?}",
+        result
+    );
+
+    // 200 ms delivered, the first 50 dropped as warm-up.
+    let result = collector(Synthetic::new(7).stall_after(Duration::from_millis(200)))
+        .collect_with_timeout(8, 64);
+    assert!(
+        matches!(
+            result,
+            Err(RandomizeError::Timeout {
+                captured_samples: 6615,
+                ..
+            })
+        ),
+        "{:?}",
+        result
+    );
+
+    assert_eq!(
+        collector(Synthetic::new(7))
+            .collect_with_timeout(8, 64)
+            .unwrap(),
+        collector(Synthetic::new(7))
+            .collect_random_data(8, 64)
+            .unwrap()
+    );
+}
+
+#[test]
+fn additional_source_rescues_a_constant_input() {
+    use rand::RngCore;
+
+    let silent = collector(Synthetic::silence())
+        .collect_random_data(8, 4096)
+        .unwrap();
+    assert!(silent.iter().all(|&byte| byte == silent[0]));
+
+    let mixed = collector(Synthetic::silence())
+        .additional_source(|n| {
+            let mut bytes = vec![0u8; n];
+            rand::rngs::OsRng.fill_bytes(&mut bytes);
+            bytes
+        })
+        .collect_random_data(8, 4096)
+        .unwrap();
+    // The MCV estimate credits 4096 uniform bytes with about 6.6 bits each.
+    assert!(mixed.len() > 3000, "{}", mixed.len());
+    // OS entropy differs every run, so judge it at a level good output
+    // practically never misses.
+    let policy = HealthPolicy {
+        significance_level: 1e-6,
+        ..HealthPolicy::default()
+    };
+    assert!(policy.check(&mixed).is_ok());
+}
+
+// Synthetic noise on the left channel and `scale` times it on the right.
+struct SharedAdc {
+    scale: f32,
+}
+
+impl SampleSource for SharedAdc {
+    fn record(&self, duration: Duration) -> Result<Audio, RandomizeError> {
+        let mut audio = Synthetic::new(3).record(duration)?;
+        audio.samples = audio
+            .samples
+            .iter()
+            .flat_map(|&sample| [sample, self.scale * sample])
+            .collect();
+        audio.channels = 2;
+        audio.buffers.iter_mut().for_each(|length| *length *= 2);
+        Ok(audio)
+    }
+}
+
+#[test]
+fn channel_difference_rejects_correlated_channels() {
+    let independent = collector(Synthetic::new(3).channels(2))
+        .channel_difference(0, 1)
+        .collect_random_data(8, 64)
+        .unwrap();
+    assert_eq!(independent.len(), 64);
+
+    for scale in [0.5, -1.0] {
+        let result = collector(SharedAdc { scale })
+            .channel_difference(0, 1)
+            .collect_random_data(8, 64);
+        assert!(
+            matches!(
+                &result,
+                Err(RandomizeError::HealthCheckFailed { failing_tests })
+                    if failing_tests == &["channel_correlation"]
+            ),
+            "{:?}",
+            result
+        );
+    }
+}
+
+// Silence delivered in 512-sample callbacks that arrive with random jitter.
+struct TimedSilence;
+
+impl SampleSource for TimedSilence {
+    fn record(&self, duration: Duration) -> Result<Audio, RandomizeError> {
+        use rand::{Rng, SeedableRng};
+
+        let mut audio = Synthetic::silence().record(duration)?;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(8);
+        audio.callback_intervals = (0..audio.samples.len() / 512)
+            .map(|_| Duration::from_nanos(11_600_000 + rng.gen_range(0..100_000)))
+            .collect();
+        Ok(audio)
+    }
+}
+
+#[test]
+fn callback_timing_is_mixed_into_the_output() {
+    let silent = collector(TimedSilence)
+        .collect_random_data(8, 4096)
+        .unwrap();
+    assert!(silent.iter().all(|&byte| byte == silent[0]));
+
+    let mixed = collector(TimedSilence)
+        .callback_timing(true)
+        .collect_random_data(8, 4096)
+        .unwrap();
+    // Only the intervals carry entropy, and there are few of them.
+    assert!(!mixed.is_empty() && mixed.len() < 500, "{}", mixed.len());
+    assert!(mixed.iter().any(|&byte| byte != mixed[0]));
+}
+
+#[test]
+fn jitter_source_records_at_its_nominal_rate() {
+    let audio = Jitter::new().record(Duration::from_millis(100)).unwrap();
+    assert_eq!((audio.sample_rate, audio.channels), (44100, 1));
+    assert_eq!(audio.samples.len(), 4410);
+    assert!(audio
+        .samples
+        .iter()
+        .all(|sample| (-1.0..1.0).contains(sample)));
+    assert!(audio
+        .samples
+        .iter()
+        .any(|&sample| sample != audio.samples[0]));
+}
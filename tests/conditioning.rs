@@ -0,0 +1,154 @@
+#[cfg(any(feature = "blake3", feature = "sha3"))]
+use randomize7::encode_hex;
+#[cfg(feature = "blake3")]
+use randomize7::Blake3Conditioner;
+#[cfg(feature = "sha3")]
+use randomize7::Sha3Conditioner;
+#[cfg(feature = "microphone")]
+use randomize7::{toeplitz_condition, EntropyCollector, Synthetic, ToeplitzConditioner};
+use randomize7::{
+    toeplitz_extract, whiten_sha256, Conditioner, IncrementalConditioner, Sha256Conditioner,
+};
+#[cfg(feature = "microphone")]
+use std::time::Duration;
+
+#[test]
+fn toeplitz_matches_hand_computed_product() {
+    // Seed 1101 gives the matrix [[0, 1, 1], [1, 0, 1]].
+    let raw = [true, false, true];
+    let seed = [true, true, false, true];
+    assert_eq!(toeplitz_extract(&raw, &seed, 2), vec![true, false]);
+
+    // The extractor is linear over GF(2): extract(a ^ b) == extract(a) ^ extract(b).
+    let other = [false, true, true];
+    let combined: Vec<bool> = raw.iter().zip(&other).map(|(a, b)| a ^ b).collect();
+    let expected: Vec<bool> = toeplitz_extract(&raw, &seed, 2)
+        .iter()
+        .zip(toeplitz_extract(&other, &seed, 2))
+        .map(|(a, b)| a ^ b)
+        .collect();
+    assert_eq!(toeplitz_extract(&combined, &seed, 2), expected);
+}
+
+#[cfg(feature = "microphone")]
+#[test]
+fn collector_conditions_with_a_fixed_toeplitz_seed() {
+    let collector = || {
+        EntropyCollector::new()
+            .duration(Duration::from_millis(500))
+            .source(Synthetic::new(7))
+            .full_rate(true)
+    };
+    let seed: Vec<u8> = (0..128u32).map(|i| (i * 37 + 11) as u8).collect();
+    let raw = collector().collect_random_data(8, 64).unwrap();
+    let conditioned = collector()
+        .conditioner(ToeplitzConditioner::new(seed.clone()))
+        .collect_random_data(8, 64)
+        .unwrap();
+
+    // Compressed to the min-entropy estimate of the 64 raw bytes.
+    assert!(!conditioned.is_empty() && conditioned.len() < 64);
+    assert_eq!(conditioned, toeplitz_condition(&raw, &seed).unwrap());
+    // A seed too short for the input yields nothing rather than panicking.
+    assert!(ToeplitzConditioner::new(vec![0; 8])
+        .condition(&raw, 64)
+        .is_empty());
+}
+
+#[test]
+fn sha256_conditioner_matches_whiten_sha256() {
+    let raw: Vec<u8> = (0..=255).collect();
+    for length in [0, 16, 32, 100] {
+        assert_eq!(
+            Sha256Conditioner.condition(&raw, length),
+            whiten_sha256(&raw, length)
+        );
+    }
+}
+
+#[cfg(feature = "blake3")]
+#[test]
+fn blake3_conditioner_extends_the_digest() {
+    let output = Blake3Conditioner.condition(b"", 64);
+    assert_eq!(output.len(), 64);
+    assert_eq!(
+        encode_hex(&output[..32]),
+        "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+    );
+}
+
+#[cfg(feature = "sha3")]
+#[test]
+fn sha3_conditioner_starts_with_the_digest() {
+    let output = Sha3Conditioner.condition(b"", 40);
+    assert_eq!(output.len(), 40);
+    assert_eq!(
+        encode_hex(&output[..32]),
+        "a7ffc6f8bf1ed76651c14756a061d662f580ff4de43b49fa82d80a4b80f8434a"
+    );
+}
+
+// Feeds `raw` in uneven chunks, including an empty one.
+fn condition_in_chunks(
+    mut conditioner: IncrementalConditioner,
+    raw: &[u8],
+    output_length: usize,
+) -> Vec<u8> {
+    for chunk in raw.chunks(1000).chain([&[][..]]) {
+        conditioner.update(&chunk[..chunk.len() / 2]);
+        conditioner.update(&chunk[chunk.len() / 2..]);
+    }
+    conditioner.finalize_xof(output_length)
+}
+
+#[test]
+fn incremental_conditioning_matches_a_single_call() {
+    let raw: Vec<u8> = (0..10_000u32).map(|i| (i * 7 + i / 256) as u8).collect();
+    for output_length in [0, 1, 32, 33, 100_000] {
+        assert_eq!(
+            condition_in_chunks(IncrementalConditioner::sha256(), &raw, output_length),
+            whiten_sha256(&raw, output_length)
+        );
+    }
+    #[cfg(feature = "blake3")]
+    assert_eq!(
+        condition_in_chunks(IncrementalConditioner::blake3(), &raw, 100_000),
+        Blake3Conditioner.condition(&raw, 100_000)
+    );
+}
+
+#[test]
+fn incremental_conditioner_debug_hides_its_input() {
+    let mut conditioner = IncrementalConditioner::default();
+    conditioner.update(&[0xab; 64]);
+    let debug = format!("{:?}", conditioner);
+    assert_eq!(debug, r#"IncrementalConditioner { hash: "sha256", .. }"#);
+}
+
+#[cfg(feature = "sha3")]
+#[test]
+fn shake256_conditioner_matches_the_standard() {
+    let output = IncrementalConditioner::shake256().finalize_xof(32);
+    assert_eq!(
+        encode_hex(&output),
+        "46b9dd2b0ba88d13233b3feb743eeb243fcd52ea62b81b82b50c27646ed5762f"
+    );
+}
+
+#[test]
+fn contexts_separate_the_conditioned_output() {
+    let raw: Vec<u8> = (0..=255).collect();
+    let first = Sha256Conditioner.condition_with_context(&raw, b"app one", 64);
+    let second = Sha256Conditioner.condition_with_context(&raw, b"app two", 64);
+
+    assert_eq!(first.len(), 64);
+    assert_eq!(
+        first,
+        Sha256Conditioner.condition_with_context(&raw, b"app one", 64)
+    );
+    assert!(first.iter().zip(&second).filter(|(a, b)| a == b).count() < 8);
+    assert_eq!(
+        Sha256Conditioner.condition_with_context(&raw, b"", 64),
+        whiten_sha256(&raw, 64)
+    );
+}
@@ -0,0 +1,110 @@
+#[cfg(feature = "microphone")]
+use randomize7::{EntropyCollector, Synthetic};
+use randomize7::{HmacDrbg, RandomizeError};
+#[cfg(feature = "microphone")]
+use std::time::Duration;
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+// NIST CAVP HMAC_DRBG.rsp, [SHA-256], no prediction resistance, 1024-bit
+// outputs. Each case generates twice and checks the second output.
+fn check_cavp_vector(
+    entropy_input: &str,
+    nonce: &str,
+    additional_inputs: [&str; 2],
+    expected: &str,
+) {
+    let mut drbg = HmacDrbg::new(&hex(entropy_input), &hex(nonce), &[]).unwrap();
+    drbg.generate_with_additional_input(128, &hex(additional_inputs[0]));
+    let output = drbg.generate_with_additional_input(128, &hex(additional_inputs[1]));
+    assert_eq!(output, hex(expected));
+}
+
+#[test]
+fn hmac_drbg_matches_cavp_vectors() {
+    check_cavp_vector(
+        "ca851911349384bffe89de1cbdc46e6831e44d34a4fb935ee285dd14b71a7488",
+        "659ba96c601dc69fc902940805ec0ca8",
+        ["", ""],
+        "e528e9abf2dece54d47c7e75e5fe302149f817ea9fb4bee6f4199697d04d5b89\
+         d54fbb978a15b5c443c9ec21036d2460b6f73ebad0dc2aba6e624abf07745bc1\
+         07694bb7547bb0995f70de25d6b29e2d3011bb19d27676c07162c8b5ccde0668\
+         961df86803482cb37ed6d5c0bb8d50cf1f50d476aa0458bdaba806f48be9dcb8",
+    );
+    check_cavp_vector(
+        "79737479ba4e7642a221fcfd1b820b134e9e3540a35bb48ffae29c20f5418ea3",
+        "3593259c092bef4129bc2c6c9e19f343",
+        ["", ""],
+        "cf5ad5984f9e43917aa9087380dac46e410ddc8a7731859c84e9d0f31bd43655\
+         b924159413e2293b17610f211e09f770f172b8fb693a35b85d3b9e5e63b1dc25\
+         2ac0e115002e9bedfb4b5b6fd43f33b8e0eafb2d072e1a6fee1f159df9b51e6c\
+         8da737e60d5032dd30544ec51558c6f080bdbdab1de8a939e961e06b5f1aca37",
+    );
+    check_cavp_vector(
+        "d3cc4d1acf3dde0c4bd2290d262337042dc632948223d3a2eaab87da44295fbd",
+        "0109b0e729f457328aa18569a9224921",
+        [
+            "3c311848183c9a212a26f27f8c6647e40375e466a0857cc39c4e47575d53f1f6",
+            "fcb9abd19ccfbccef88c9c39bfb3dd7b1c12266c9808992e305bc3cff566e4e4",
+        ],
+        "9c7b758b212cd0fcecd5daa489821712e3cdea4467b560ef5ddc24ab47749a1f\
+         1ffdbbb118f4e62fcfca3371b8fbfc5b0646b83e06bfbbab5fac30ea09ea2bc7\
+         6f1ea568c9be0444b2cc90517b20ca825f2d0eccd88e7175538b85d90ab39018\
+         3ca6395535d34473af6b5a5b88f5a59ee7561573337ea819da0dcc3573a22974",
+    );
+}
+
+#[test]
+fn hmac_drbg_reseed_changes_output() {
+    let mut original = HmacDrbg::new(&[7; 32], &[1; 16], &[]).unwrap();
+    let mut reseeded = HmacDrbg::new(&[7; 32], &[1; 16], &[]).unwrap();
+    assert_eq!(original.generate(32), reseeded.generate(32));
+    assert_eq!(reseeded.reseed_counter(), 2);
+
+    reseeded.reseed(&[9; 32], &[]).unwrap();
+    assert_eq!(reseeded.reseed_counter(), 1);
+    assert_ne!(original.generate(32), reseeded.generate(32));
+}
+
+#[test]
+fn hmac_drbg_rejects_short_entropy_input() {
+    assert!(matches!(
+        HmacDrbg::new(&[7; 16], &[1; 16], &[]),
+        Err(RandomizeError::InsufficientData {
+            required_bits: 256,
+            available_bits: 128,
+        })
+    ));
+}
+
+#[cfg(feature = "microphone")]
+#[test]
+fn hmac_drbg_seeds_from_audio() {
+    let collector = |source| {
+        EntropyCollector::new()
+            .duration(Duration::from_millis(500))
+            .source(source)
+    };
+
+    let mut drbg = HmacDrbg::from_collector(&collector(Synthetic::new(7)), 8).unwrap();
+    let mut same_audio = HmacDrbg::from_collector(&collector(Synthetic::new(7)), 8).unwrap();
+    assert_eq!(drbg.generate(64), same_audio.generate(64));
+
+    assert!(matches!(
+        HmacDrbg::from_collector(&collector(Synthetic::silence()), 8),
+        Err(RandomizeError::HealthCheckFailed { .. })
+    ));
+}
+
+#[test]
+fn debug_output_redacts_the_state() {
+    let mut drbg = HmacDrbg::new(&[7; 32], &[1; 16], b"").unwrap();
+    drbg.generate(32);
+    let debug = format!("{:?}", drbg);
+    assert_eq!(debug, "HmacDrbg { reseed_counter: 2, .. }");
+}
@@ -0,0 +1,83 @@
+use rand::{Rng, RngCore, SeedableRng};
+use randomize7::{
+    bit_position_bias, byte_histogram, entropy_capped_length, extract_random_data,
+    extraction_efficiency, min_entropy_mcv, normalize_audio, shannon_entropy, ExtractStrategy,
+};
+
+#[test]
+fn histogram_and_bit_bias_expose_a_stuck_bit() {
+    // The high bit never set, the low bit always.
+    let data: Vec<u8> = (0..=255u8).map(|byte| (byte & 0x7f) | 1).collect();
+
+    let histogram = byte_histogram(&data);
+    assert_eq!(histogram.iter().sum::<u64>(), 256);
+    assert_eq!(histogram[0x01], 4);
+    assert_eq!(histogram[0x80], 0);
+
+    let bias = bit_position_bias(&data);
+    assert_eq!(bias[0], 0.0);
+    assert_eq!(bias[7], 1.0);
+    assert!(bias[1..7].iter().all(|&fraction| fraction == 0.5));
+    assert!(bit_position_bias(&[])
+        .iter()
+        .all(|fraction| fraction.is_nan()));
+}
+
+#[test]
+fn conditioning_is_capped_only_for_low_entropy_input() {
+    let mut high = vec![0u8; 4096];
+    rand::rngs::StdRng::seed_from_u64(1).fill_bytes(&mut high);
+    assert_eq!(entropy_capped_length(&high, 32), 32);
+
+    // Four equally likely values: about 2 bits per byte, less the bound.
+    let low: Vec<u8> = high.iter().map(|byte| byte & 0x03).collect();
+    let capped = entropy_capped_length(&low, 4096);
+    assert!(capped < 1024 && capped > 900, "{}", capped);
+}
+
+#[test]
+fn extraction_efficiency_flags_normalized_quiet_input() {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+    let extract = |samples: &[f32]| {
+        let mut normalized = samples.to_vec();
+        normalize_audio(&mut normalized, 1.0);
+        extract_random_data(
+            &normalized,
+            4,
+            samples.len() / 2,
+            ExtractStrategy::default(),
+            false,
+            false,
+        )
+        .unwrap()
+        .len()
+    };
+
+    let loud: Vec<f32> = (0..44_100).map(|_| rng.gen_range(-1.0..1.0)).collect();
+    let efficiency = extraction_efficiency(&loud, extract(&loud), 4);
+    assert!(efficiency > 0.9 && efficiency <= 1.0, "{}", efficiency);
+
+    // Seven levels of the ADC, stretched to full scale before extraction.
+    let quiet: Vec<f32> = loud.iter().map(|sample| sample * 1e-4).collect();
+    let efficiency = extraction_efficiency(&quiet, extract(&quiet), 4);
+    assert!(efficiency > 1.5, "{}", efficiency);
+
+    assert_eq!(extraction_efficiency(&[0.0; 100], 10, 4), f64::INFINITY);
+    assert_eq!(extraction_efficiency(&loud, 0, 4), 0.0);
+}
+
+#[test]
+fn shannon_entropy_exceeds_min_entropy_on_skewed_bytes() {
+    let all_values: Vec<u8> = (0..=255).collect();
+    assert!((shannon_entropy(&all_values) - 8.0).abs() < 1e-12);
+    assert_eq!(shannon_entropy(&[0x42; 64]), 0.0);
+    assert_eq!(shannon_entropy(&[]), 0.0);
+
+    // Half zeros, the rest spread over 128 values: 4.5 bits but a guess of 0
+    // succeeds half the time.
+    let skewed: Vec<u8> = (0..=255u8)
+        .map(|byte| if byte < 128 { 0 } else { byte })
+        .collect();
+    assert!((shannon_entropy(&skewed) - 4.5).abs() < 1e-12);
+    assert!(min_entropy_mcv(&skewed) < 1.0);
+}
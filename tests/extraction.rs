@@ -0,0 +1,512 @@
+use rand::{RngCore, SeedableRng};
+use randomize7::{
+    annotate_random_data, auto_select_lsb, bytes_to_bits, center_and_normalize, elias_debias,
+    estimate_duration_for_bytes, extract_fixed, extract_lsb_planes, extract_magnitude_data,
+    extract_phase_data, extract_random_bits, extract_random_bits_exact,
+    extract_random_bits_exact_as_bytes, extract_random_data, f32_to_u8, f32_to_u8_with,
+    iterated_von_neumann_debias, monobit_result, pack_lsbs, process_samples, serial_test_bits,
+    u8_to_f32, u8_to_f32_with, Debiaser, Endianness, ExtractParams, ExtractStrategy,
+    MagnitudeParams, PhaseParams, RandomizeError, MIN_ENTROPY_PER_BIT, SIGNIFICANCE_LEVEL,
+};
+use std::time::Duration;
+
+#[test]
+fn short_recording_does_not_panic() {
+    let samples = [0.1, -0.2, 0.3];
+    let random_data =
+        extract_random_data(&samples, 8, 32, ExtractStrategy::default(), false, false).unwrap();
+    assert_eq!(random_data.len(), 2);
+}
+
+#[test]
+fn lsb_packing_round_trips() {
+    let values: Vec<u32> = (0..64u32).map(|i| i.wrapping_mul(0x9e37_79b9)).collect();
+
+    for num_lsb in [1, 3, 8] {
+        let packed = pack_lsbs(&values, num_lsb);
+        let unpacked = bytes_to_bits(&packed);
+        assert_eq!(unpacked.len(), values.len() * num_lsb as usize / 8 * 8);
+
+        for (i, &bit) in unpacked.iter().enumerate() {
+            let value = values[i / num_lsb as usize];
+            let shift = num_lsb - 1 - (i % num_lsb as usize) as u32;
+            assert_eq!(
+                bit,
+                (value >> shift) & 1 == 1,
+                "num_lsb = {}, bit {}",
+                num_lsb,
+                i
+            );
+        }
+    }
+}
+
+#[test]
+fn f32_bytes_round_trip() {
+    let samples = [0.0, -0.0, 1.0, -1.0, 0.123_456, f32::MIN_POSITIVE, f32::MAX];
+    let bytes = f32_to_u8(&samples);
+    assert_eq!(bytes.len(), samples.len() * 4);
+
+    let restored = u8_to_f32(&bytes);
+    let bits = |values: &[f32]| values.iter().map(|v| v.to_bits()).collect::<Vec<_>>();
+    assert_eq!(bits(&restored), bits(&samples));
+}
+
+#[test]
+fn f32_bytes_round_trip_in_both_byte_orders() {
+    let samples = [1.0f32, -0.5, 0.123, f32::MIN_POSITIVE];
+    let little = f32_to_u8_with(&samples, Endianness::Little);
+    let big = f32_to_u8_with(&samples, Endianness::Big);
+
+    assert_eq!(little, f32_to_u8(&samples));
+    assert_eq!(&big[..4], &[0x3f, 0x80, 0x00, 0x00]);
+    assert_eq!(&little[..4], &[0x00, 0x00, 0x80, 0x3f]);
+    assert_eq!(u8_to_f32_with(&little, Endianness::Little), samples);
+    assert_eq!(u8_to_f32_with(&big, Endianness::Big), samples);
+}
+
+#[test]
+fn auto_select_lsb_tracks_signal_entropy() {
+    // Alternating signs make every difference a rounded sum of two random
+    // mantissas, so its low bits are close to uniform.
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(1);
+    let noise: Vec<f32> = (0..88200)
+        .map(|i| {
+            let sample = f32::from_bits(0x3f80_0000 | (rng.next_u32() >> 9));
+            if i % 2 == 0 {
+                sample
+            } else {
+                -sample
+            }
+        })
+        .collect();
+    let (num_lsb, entropy) = auto_select_lsb(&noise);
+    assert!(num_lsb > 8, "chose {} bits", num_lsb);
+    assert!(entropy >= MIN_ENTROPY_PER_BIT);
+
+    assert_eq!(auto_select_lsb(&[0.5; 88200]), (1, 0.0));
+}
+
+#[test]
+fn byte_extraction_wraps_bit_extraction() {
+    let samples: Vec<f32> = (0..1000).map(|i| (i as f32 * 0.37).sin()).collect();
+
+    // 3 bits per difference over 7 samples: 18 bits, not a whole number of bytes.
+    let bits = extract_random_bits(&samples[..7], 3, 100, ExtractStrategy::default()).unwrap();
+    assert_eq!(bits.len(), 18);
+
+    let bits = extract_random_bits(&samples, 3, 8 * 40, ExtractStrategy::default()).unwrap();
+    let bytes =
+        extract_random_data(&samples, 3, 40, ExtractStrategy::default(), false, false).unwrap();
+    assert_eq!(bits.into_vec(), bytes);
+}
+
+#[test]
+fn annotations_match_the_extracted_bytes() {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+    let samples: Vec<f32> = (0..1000)
+        .map(|_| rng.next_u32() as f32 / u32::MAX as f32 - 0.5)
+        .collect();
+
+    for num_lsb in [3, 8] {
+        let annotated = annotate_random_data(&samples, num_lsb, 32).unwrap();
+        let values: Vec<u8> = annotated.iter().map(|byte| byte.value).collect();
+        let expected = extract_random_data(
+            &samples,
+            num_lsb,
+            32,
+            ExtractStrategy::default(),
+            false,
+            false,
+        )
+        .unwrap();
+        assert_eq!(values, expected);
+
+        for source in annotated.iter().flat_map(|byte| &byte.sources) {
+            let i = source.sample_index;
+            assert_eq!(source.difference, samples[i + 1] - samples[i]);
+            assert_eq!(
+                source.lsb,
+                source.difference.to_bits() & ((1 << num_lsb) - 1)
+            );
+        }
+    }
+
+    // With 8 bits per difference every byte is one difference's low byte.
+    for byte in annotate_random_data(&samples, 8, 32).unwrap() {
+        assert_eq!(byte.sources.len(), 1);
+        assert_eq!(byte.sources[0].lsb, byte.value as u32);
+    }
+}
+
+#[test]
+fn exact_bits_mask_the_final_byte() {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+    let samples: Vec<f32> = (0..2000)
+        .map(|_| rng.next_u32() as f32 / u32::MAX as f32 - 0.5)
+        .collect();
+
+    let bits = extract_random_bits_exact(&samples, 3, 521).unwrap();
+    assert_eq!(bits.len(), 521);
+    let bytes = extract_random_bits_exact_as_bytes(&samples, 3, 521).unwrap();
+    assert_eq!(bytes.len(), 66);
+    assert_eq!(bytes[65] & 0x7f, 0);
+    assert_eq!(bytes_to_bits(&bytes)[..521], bits[..]);
+
+    assert!(matches!(
+        extract_random_bits_exact(&samples[..10], 3, 521),
+        Err(RandomizeError::InsufficientData {
+            required_bits: 521,
+            available_bits: 27,
+        })
+    ));
+}
+
+#[test]
+fn fixed_extraction_matches_the_vec_api() {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(4);
+    let samples: Vec<f32> = (0..1000)
+        .map(|_| rng.next_u32() as f32 / u32::MAX as f32 - 0.5)
+        .collect();
+
+    let seed: [u8; 32] = extract_fixed(&samples, 4).unwrap();
+    let random_data =
+        extract_random_data(&samples, 4, 32, ExtractStrategy::default(), false, false).unwrap();
+    assert_eq!(seed[..], random_data[..]);
+
+    assert!(matches!(
+        extract_fixed::<32>(&samples[..10], 4),
+        Err(RandomizeError::InsufficientData {
+            required_bits: 256,
+            available_bits: 36,
+        })
+    ));
+}
+
+#[test]
+fn lsb_planes_break_up_correlation_within_a_sample() {
+    // Raw samples whose low 8 bits are 4 random bits, each one doubled.
+    let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+    let samples: Vec<f32> = (0..4000)
+        .map(|_| {
+            let nibble = rng.next_u32() & 0xf;
+            let doubled = (0..4).fold(0, |doubled, bit| {
+                doubled | (((nibble >> bit) & 1) * (3 << (2 * bit)))
+            });
+            f32::from_bits(0x3f80_0000 | doubled)
+        })
+        .collect();
+
+    let by_sample = extract_random_bits(&samples, 8, 32_000, ExtractStrategy::LsbRaw).unwrap();
+    let by_plane = extract_lsb_planes(&samples, 8, 32_000, ExtractStrategy::LsbRaw).unwrap();
+    assert_eq!(by_plane.count_ones(), by_sample.count_ones());
+    // The top plane is the highest bit of every sample in turn.
+    assert!((0..4000).all(|unit| by_plane[unit] == by_sample[unit * 8]));
+
+    let (by_sample_p, _) = serial_test_bits(&by_sample, 2);
+    let (by_plane_p, _) = serial_test_bits(&by_plane, 2);
+    assert!(by_sample_p < SIGNIFICANCE_LEVEL);
+    assert!(by_plane_p >= SIGNIFICANCE_LEVEL);
+}
+
+#[test]
+fn process_samples_centers_normalizes_and_extracts() {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+    let samples: Vec<f32> = (0..4000)
+        .map(|_| 0.3 + (rng.next_u32() as f32 / u32::MAX as f32 - 0.5) * 0.01)
+        .collect();
+
+    let mut normalized = samples.clone();
+    center_and_normalize(&mut normalized, 1.0);
+    let params = ExtractParams {
+        whiten: true,
+        ..ExtractParams::new(5, 64)
+    };
+    assert_eq!(
+        process_samples(&samples, params).unwrap(),
+        extract_random_data(&normalized, 5, 64, ExtractStrategy::default(), false, true).unwrap()
+    );
+    assert!(matches!(
+        process_samples(&[], params),
+        Err(RandomizeError::EmptyRecording)
+    ));
+}
+
+#[test]
+fn duration_estimate_covers_the_required_samples() {
+    assert_eq!(
+        estimate_duration_for_bytes(0, 8, 44100, true),
+        Duration::ZERO
+    );
+    // 44099 differences start from 44100 samples, a second at 44.1 kHz.
+    let one_second = estimate_duration_for_bytes(44099, 8, 44100, false);
+    assert!(one_second > Duration::from_secs(1));
+    assert!(one_second < Duration::from_secs(1) + Duration::from_micros(23));
+    let debiased = estimate_duration_for_bytes(1000, 8, 48000, true);
+    assert_eq!(
+        (48000.0 * debiased.as_secs_f64()) as usize,
+        4001,
+        "debiasing needs four times the differences"
+    );
+}
+
+#[test]
+fn xor_differences_fold_each_window() {
+    let samples: Vec<f32> = (0..64)
+        .map(|i| ((i * 37 % 11) as f32 - 5.0) / 7.0)
+        .collect();
+    let single = extract_random_bits(&samples, 8, 8 * 16, ExtractStrategy::default()).unwrap();
+    let window_one = extract_random_bits(
+        &samples,
+        8,
+        8 * 16,
+        ExtractStrategy::XorDifferences { window: 1 },
+    )
+    .unwrap();
+    assert_eq!(single, window_one);
+
+    // 63 differences make 21 windows of 3; ask for every one of them.
+    let folded = extract_random_data(
+        &samples,
+        8,
+        21,
+        ExtractStrategy::XorDifferences { window: 3 },
+        false,
+        false,
+    )
+    .unwrap();
+    let expected: Vec<u8> = samples[..64]
+        .chunks(3)
+        .zip(samples[1..].chunks(3))
+        .take(21)
+        .map(|(starts, ends)| {
+            starts
+                .iter()
+                .zip(ends)
+                .fold(0u32, |folded, (a, b)| folded ^ (b - a).to_bits()) as u8
+        })
+        .collect();
+    assert_eq!(folded, expected);
+}
+
+// Spans several chunks of the parallel extraction, so with the `parallel`
+// feature every chunk boundary's difference is checked.
+#[test]
+fn long_recordings_extract_every_difference_in_order() {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(100);
+    let samples: Vec<f32> = (0..100_001)
+        .map(|_| rng.next_u32() as f32 / u32::MAX as f32 * 2.0 - 1.0)
+        .collect();
+    let bytes = extract_random_data(
+        &samples,
+        8,
+        100_000,
+        ExtractStrategy::default(),
+        false,
+        false,
+    )
+    .unwrap();
+    let expected: Vec<u8> = samples
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).to_bits() as u8)
+        .collect();
+    assert_eq!(bytes, expected);
+}
+
+#[test]
+fn phase_extraction_quantizes_each_bin() {
+    // A cosine at exactly bin 200 has phase 0 there, level 2 of 4.
+    let samples: Vec<f32> = (0..4096)
+        .map(|i| (std::f64::consts::TAU * 200.0 * (i % 1024) as f64 / 1024.0).cos() as f32)
+        .collect();
+    let params = PhaseParams {
+        bins: 200..204,
+        ..PhaseParams::new(1024)
+    };
+    // Bins 201-203 hold only rounding noise; check bin 200's two bits.
+    let data = extract_phase_data(&samples, &params, 4).unwrap();
+    assert_eq!(data.len(), 4);
+    assert!(data.iter().all(|byte| byte >> 6 == 0b10), "{:02x?}", data);
+}
+
+#[test]
+fn phase_bits_of_noise_survive_a_loud_tone() {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(108);
+    // A tone at bin 64, outside the default middle band, 40 dB above the noise.
+    let samples: Vec<f32> = (0..1024 * 64)
+        .map(|i| {
+            let tone = (std::f64::consts::TAU * i as f64 / 16.0).sin() as f32;
+            let noise = rng.next_u32() as f32 / u32::MAX as f32 * 2.0 - 1.0;
+            tone + 0.01 * noise
+        })
+        .collect();
+    let data = extract_phase_data(&samples, &PhaseParams::default(), 4096).unwrap();
+    assert_eq!(data.len(), 4096);
+    assert!(monobit_result(&data).passes(SIGNIFICANCE_LEVEL));
+}
+
+#[test]
+fn phase_params_are_validated() {
+    let samples = vec![0.0; 1024];
+    for params in [
+        PhaseParams {
+            bins: 0..10,
+            ..PhaseParams::default()
+        },
+        PhaseParams {
+            bins: 10..513,
+            ..PhaseParams::default()
+        },
+        PhaseParams {
+            bits_per_bin: 17,
+            ..PhaseParams::default()
+        },
+    ] {
+        assert!(matches!(
+            extract_phase_data(&samples, &params, 1),
+            Err(RandomizeError::InvalidParameter(_))
+        ));
+    }
+    assert!(matches!(
+        extract_phase_data(&samples[..100], &PhaseParams::default(), 1),
+        Err(RandomizeError::InsufficientSamples { .. })
+    ));
+}
+
+#[test]
+fn magnitude_bits_of_noise_survive_a_loud_tone() {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(142);
+    // A tone at bin 64, outside the default middle band, 40 dB above the noise.
+    let samples: Vec<f32> = (0..1024 * 64)
+        .map(|i| {
+            let tone = (std::f64::consts::TAU * i as f64 / 16.0).sin() as f32;
+            let noise = rng.next_u32() as f32 / u32::MAX as f32 * 2.0 - 1.0;
+            tone + 0.01 * noise
+        })
+        .collect();
+    let data = extract_magnitude_data(&samples, &MagnitudeParams::default(), 4096).unwrap();
+    assert_eq!(data.len(), 4096);
+    assert!(monobit_result(&data).passes(SIGNIFICANCE_LEVEL));
+
+    // 64 windows of 256 bins at 4 bits fall short of 16 KiB.
+    let short = extract_magnitude_data(&samples, &MagnitudeParams::default(), 16384).unwrap();
+    assert_eq!(short.len(), 8192);
+}
+
+#[test]
+fn magnitude_params_are_validated() {
+    let samples = vec![0.0; 1024];
+    for params in [
+        MagnitudeParams {
+            bins: 0..10,
+            ..MagnitudeParams::default()
+        },
+        MagnitudeParams {
+            bins: 10..514,
+            ..MagnitudeParams::default()
+        },
+        MagnitudeParams {
+            lsb_per_bin: 24,
+            ..MagnitudeParams::default()
+        },
+    ] {
+        assert!(matches!(
+            extract_magnitude_data(&samples, &params, 1),
+            Err(RandomizeError::InvalidParameter(_))
+        ));
+    }
+    // Nyquist has a magnitude too.
+    let nyquist = MagnitudeParams {
+        bins: 10..513,
+        ..MagnitudeParams::default()
+    };
+    assert!(extract_magnitude_data(&samples, &nyquist, 1).is_ok());
+    assert!(matches!(
+        extract_magnitude_data(&samples[..100], &MagnitudeParams::default(), 1),
+        Err(RandomizeError::InsufficientSamples { .. })
+    ));
+}
+
+#[test]
+fn num_lsb_must_be_between_1_and_32() {
+    let samples: Vec<f32> = (0..1000).map(|i| (i as f32 * 0.37).sin()).collect();
+    let extract = |num_lsb| {
+        extract_random_data(
+            &samples,
+            num_lsb,
+            16,
+            ExtractStrategy::default(),
+            false,
+            false,
+        )
+    };
+
+    for num_lsb in [0, 33] {
+        assert!(
+            matches!(extract(num_lsb), Err(RandomizeError::InvalidParameter(_))),
+            "num_lsb = {}",
+            num_lsb
+        );
+    }
+    for num_lsb in [1, 32] {
+        assert_eq!(extract(num_lsb).unwrap().len(), 16, "num_lsb = {}", num_lsb);
+    }
+
+    // The 32-bit mask doesn't overflow: every bit of each difference is kept.
+    let differences: Vec<u32> = samples[..5]
+        .windows(2)
+        .map(|pair| (pair[1] - pair[0]).to_bits())
+        .collect();
+    let expected: Vec<u8> = differences
+        .iter()
+        .flat_map(|value| value.to_be_bytes())
+        .collect();
+    let bits = extract_random_bits(&samples[..5], 32, 128, ExtractStrategy::default()).unwrap();
+    assert_eq!(bits.into_vec(), expected);
+}
+
+#[test]
+fn debiasers_make_a_biased_coin_fair() {
+    use rand::Rng;
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(9);
+    let coin: Vec<bool> = (0..400_000).map(|_| rng.gen_bool(0.7)).collect();
+    for (debiaser, min_yield) in [
+        (Debiaser::VonNeumann, 0.2),
+        (Debiaser::IteratedVonNeumann, 0.8),
+        (Debiaser::Elias, 0.7),
+    ] {
+        let debiased = debiaser.debias(&coin);
+        let ones = debiased.iter().filter(|&&bit| bit).count() as f64 / debiased.len() as f64;
+        assert!((ones - 0.5).abs() < 0.005, "{:?}: {}", debiaser, ones);
+        let efficiency = debiased.len() as f64 / coin.len() as f64;
+        assert!(efficiency > min_yield, "{:?}: {}", debiaser, efficiency);
+    }
+    assert_eq!(Debiaser::None.debias(&coin), coin);
+}
+
+#[test]
+fn iterated_von_neumann_recovers_discarded_pairs() {
+    // Pairs 11, 01, 00, 10: Von Neumann gives 0 1, their XORs 0 1 0 1
+    // give 0 0, and the bits of the equal pairs, 1 0, give 1.
+    let bits = [true, true, false, true, false, false, true, false];
+    assert_eq!(
+        iterated_von_neumann_debias(&bits),
+        [false, true, false, false, true]
+    );
+}
+
+#[test]
+fn elias_maps_each_block_to_its_rank() {
+    // All 32 bits equal: one arrangement, no output.
+    assert!(elias_debias(&[true; 32]).is_empty());
+    // One one among 32: 32 arrangements, 5 bits of its position.
+    let mut block = [false; 32];
+    block[5] = true;
+    let expected: Vec<bool> = (0..5)
+        .rev()
+        .map(|shift| (31 - 5) >> shift & 1 == 1)
+        .collect();
+    assert_eq!(elias_debias(&block), expected);
+    assert!(elias_debias(&block[..31]).is_empty());
+}
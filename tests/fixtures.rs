@@ -0,0 +1,48 @@
+use randomize7::{
+    autocorrelation_test, downmix, overall_verdict, process_samples, read_wav, run_all_tests,
+    spectral_test, ExtractParams, ExtractStrategy, HealthPolicy, RandomizeError, Recommendation,
+    SIGNIFICANCE_LEVEL,
+};
+
+// The fixtures are 16-bit PCM except white_noise.wav, 8192 float32 samples
+// of uniform noise; 16-bit steps leave the low bits of float differences
+// zero, so only float noise is random in them. Differences are XOR-folded
+// in fours, which the noise passes without whitening, so whitening can't
+// hide a bad input.
+fn extract(name: &str) -> Vec<u8> {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    let audio = read_wav(&path).unwrap();
+    let mut params = ExtractParams::new(8, 8192);
+    params.strategy = ExtractStrategy::XorDifferences { window: 4 };
+    process_samples(&downmix(&audio.samples, audio.channels), params).unwrap()
+}
+
+#[test]
+fn white_noise_passes_the_battery() {
+    let data = extract("white_noise.wav");
+    let results = run_all_tests(&data);
+    assert!(results.iter().all(|result| result.passed), "{:?}", results);
+    assert_eq!(
+        overall_verdict(&results).recommendation,
+        Recommendation::Acceptable
+    );
+    assert!(HealthPolicy::default().check(&data).is_ok());
+}
+
+#[test]
+fn silence_fails_the_health_check() {
+    let data = extract("silence.wav");
+    assert!(matches!(
+        HealthPolicy::default().check(&data),
+        Err(RandomizeError::HealthCheckFailed { .. })
+    ));
+}
+
+#[test]
+fn pure_tone_fails_spectral_and_autocorrelation() {
+    let data = extract("tone.wav");
+    assert!(spectral_test(&data) < SIGNIFICANCE_LEVEL);
+    assert!(autocorrelation_test(&data, 1).unwrap() < SIGNIFICANCE_LEVEL);
+}
@@ -0,0 +1,123 @@
+// Golden vectors: SHA-256 hashes of what the pipeline produces from seeded
+// synthetic noise. A change that alters any output byte, e.g. a refactor of
+// extraction or conditioning, or the `parallel` and `simd` paths, fails
+// here. If the change is intended, update the hashes and say why in the
+// commit. The tone is left out since `sin` may round differently across
+// platforms.
+
+use randomize7::{
+    encode_hex, process_samples, Debiaser, ExtractParams, ExtractStrategy, SampleSource, Synthetic,
+};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+fn hash(data: &[u8]) -> String {
+    encode_hex(&Sha256::digest(data))
+}
+
+// Samples of `duration` from the synthetic source seeded with `seed`.
+fn samples(seed: u64, duration: Duration) -> Vec<f32> {
+    Synthetic::new(seed).record(duration).unwrap().samples
+}
+
+// The hash of what `process_samples` extracts with `params` from
+// `duration` of `seed`'s noise.
+fn golden_hash(seed: u64, duration: Duration, params: ExtractParams) -> String {
+    hash(&process_samples(&samples(seed, duration), params).unwrap())
+}
+
+#[test]
+fn extraction_matches_the_golden_vectors() {
+    let xor_folded = ExtractParams {
+        strategy: ExtractStrategy::XorDifferences { window: 4 },
+        ..ExtractParams::new(8, 512)
+    };
+    let debiased = ExtractParams {
+        debiaser: Debiaser::VonNeumann,
+        ..ExtractParams::new(4, 256)
+    };
+    let whitened = ExtractParams {
+        whiten: true,
+        ..ExtractParams::new(8, 64)
+    };
+    let short = Duration::from_millis(100);
+    for (seed, duration, params, expected) in [
+        (
+            1,
+            short,
+            ExtractParams::new(8, 1024),
+            "d73256448611b1bc9b29711d6e37ca0aed7ceb5fcb075e3df9895517015a3a19",
+        ),
+        (
+            2,
+            short,
+            xor_folded,
+            "c14f159243c8d60b61966b5e424d361982a3e3318ea926eaea51762c027d8162",
+        ),
+        (
+            3,
+            short,
+            debiased,
+            "3116e0ec8cfe728f28c49509f92895ea5b95f0c482acb0e6417f59039305c2f3",
+        ),
+        (
+            4,
+            short,
+            whitened,
+            "aad9a2c7467a83df02b272fe84b0932151054567c0b7a03f3e9501eb64ada1d1",
+        ),
+        // Long enough to span several of the `parallel` feature's chunks.
+        (
+            8,
+            Duration::from_secs(2),
+            ExtractParams::new(8, 65536),
+            "91cfabbc2a5d8d9cc71d16a955cb4a5ff18f80610a29c4b73ddd9c024081bb2b",
+        ),
+    ] {
+        assert_eq!(
+            golden_hash(seed, duration, params),
+            expected,
+            "seed {}",
+            seed
+        );
+    }
+}
+
+#[cfg(feature = "microphone")]
+#[test]
+fn collection_matches_the_golden_vectors() {
+    use randomize7::{Debiaser, EntropyCollector};
+
+    let collector = |seed| {
+        EntropyCollector::new()
+            .duration(Duration::from_millis(200))
+            .source(Synthetic::new(seed).channels(2))
+    };
+    let cases = [
+        (
+            5,
+            collector(5).collect_random_data(8, 256).unwrap(),
+            "4bfd924bc4ba368928e08d1f0af920d6c50094322b2b929d9a8e006e5b5d8780",
+        ),
+        (
+            6,
+            collector(6)
+                .debiaser(Debiaser::IteratedVonNeumann)
+                .lsb_planes(true)
+                .collect_random_data(4, 256)
+                .unwrap(),
+            "8856b147ae4911fd0baf07b59af63ce2380aca3b010be8cb44303168e3e117cf",
+        ),
+        (
+            7,
+            collector(7)
+                .whiten(true)
+                .collect_random_data(8, 64)
+                .unwrap(),
+            "7030ab3f80fde3d6110571f8b60ac2c74177b00059a925651ac302351ccf5ffb",
+        ),
+    ];
+    for (seed, output, expected) in cases {
+        assert_eq!(hash(&output), expected, "seed {}", seed);
+    }
+}
@@ -0,0 +1,13 @@
+use std::process::Command;
+
+#[test]
+fn builds_without_default_features() {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let status = Command::new(env!("CARGO"))
+        .args(["build", "--lib", "--no-default-features", "--target-dir"])
+        .arg(format!("{}/target/no-default-features", manifest_dir))
+        .current_dir(manifest_dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
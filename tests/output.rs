@@ -0,0 +1,24 @@
+use randomize7::{read_framed, write_formatted, OutputFormat};
+use std::io::ErrorKind;
+
+#[test]
+fn framed_batches_round_trip() {
+    let batches = vec![vec![1, 2, 3], Vec::new(), vec![0xff; 300]];
+    let mut written = Vec::new();
+    for batch in &batches {
+        write_formatted(batch, OutputFormat::Framed, &mut written).unwrap();
+    }
+    assert_eq!(&written[..7], &[3, 0, 0, 0, 1, 2, 3]);
+    assert_eq!(read_framed(written.as_slice()).unwrap(), batches);
+}
+
+#[test]
+fn truncated_frames_are_rejected() {
+    let mut written = Vec::new();
+    write_formatted(&[7; 10], OutputFormat::Framed, &mut written).unwrap();
+    for length in [2, 9] {
+        let err = read_framed(&written[..length]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+    }
+    assert!(read_framed(&[][..]).unwrap().is_empty());
+}
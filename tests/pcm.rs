@@ -0,0 +1,82 @@
+use rand::{Rng, SeedableRng};
+use randomize7::{
+    downmix, extract_from_raw_pcm, process_samples, read_raw_pcm, Endianness, ExtractParams,
+    PcmFormat, PcmSampleType, RandomizeError,
+};
+
+const VALUES: [f32; 4] = [-1.0, -0.5, 0.0, 0.5];
+
+fn encode(values: &[f32], format: PcmFormat) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for &value in values {
+        let mut sample = match format.sample_type {
+            PcmSampleType::I16 => ((value * 32768.0) as i16).to_le_bytes().to_vec(),
+            PcmSampleType::I24 => ((value * 8388608.0) as i32).to_le_bytes()[..3].to_vec(),
+            PcmSampleType::I32 => ((value as f64 * 2147483648.0) as i32)
+                .to_le_bytes()
+                .to_vec(),
+            PcmSampleType::F32 => value.to_le_bytes().to_vec(),
+        };
+        if format.endianness == Endianness::Big {
+            sample.reverse();
+        }
+        bytes.extend(sample);
+    }
+    bytes
+}
+
+fn formats() -> Vec<PcmFormat> {
+    let mut formats = Vec::new();
+    for sample_type in [
+        PcmSampleType::I16,
+        PcmSampleType::I24,
+        PcmSampleType::I32,
+        PcmSampleType::F32,
+    ] {
+        for endianness in [Endianness::Little, Endianness::Big] {
+            formats.push(PcmFormat::new(sample_type, endianness));
+        }
+    }
+    formats
+}
+
+#[test]
+fn every_format_decodes_to_unit_range() {
+    for format in formats() {
+        let bytes = encode(&VALUES, format);
+        assert_eq!(bytes.len(), VALUES.len() * format.sample_type.width());
+        assert_eq!(
+            read_raw_pcm(&bytes[..], format, 2).unwrap(),
+            VALUES,
+            "{:?}",
+            format
+        );
+    }
+}
+
+#[test]
+fn trailing_partial_frame_is_dropped() {
+    let format = PcmFormat::new(PcmSampleType::I16, Endianness::Little);
+    let bytes = encode(&VALUES, format);
+    // One and a half stereo frames short of two.
+    let samples = read_raw_pcm(&bytes[..bytes.len() - 3], format, 2).unwrap();
+    assert_eq!(samples, VALUES[..2]);
+    assert!(matches!(
+        read_raw_pcm(&bytes[..], format, 0),
+        Err(RandomizeError::InvalidParameter(_))
+    ));
+}
+
+#[test]
+fn raw_pcm_runs_the_standard_pipeline_on_the_downmix() {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(99);
+    let values: Vec<f32> = (0..4000)
+        .map(|_| rng.gen_range(-32768..32768) as f32 / 32768.0)
+        .collect();
+    for format in formats() {
+        let bytes = encode(&values, format);
+        let output = extract_from_raw_pcm(&bytes[..], format, 2, 4, 100).unwrap();
+        let expected = process_samples(&downmix(&values, 2), ExtractParams::new(4, 100)).unwrap();
+        assert_eq!(output, expected, "{:?}", format);
+    }
+}
@@ -0,0 +1,74 @@
+use randomize7::{EntropyPool, RandomizeError, SEED_FILE_BYTES};
+
+fn seed_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("randomize7-{}-{}.seed", name, std::process::id()))
+}
+
+fn fresh() -> Vec<u8> {
+    (0..=255).collect()
+}
+
+#[test]
+fn load_mixes_fresh_entropy_and_rewrites_the_seed() {
+    let path = seed_path("resume");
+    let mut pool = EntropyPool::new();
+    pool.stir(&fresh());
+    pool.save(&path).unwrap();
+    let saved = std::fs::read(&path).unwrap();
+    assert_eq!(saved.len(), 8 + SEED_FILE_BYTES);
+
+    let mut first = EntropyPool::load_with(&path, &fresh()).unwrap();
+    assert!(first.is_seeded());
+    let rewritten = std::fs::read(&path).unwrap();
+    assert_ne!(saved, rewritten);
+
+    // Resuming the same seed with different fresh bytes diverges.
+    std::fs::write(&path, &saved).unwrap();
+    let mut other_fresh = fresh();
+    other_fresh.reverse();
+    let mut second = EntropyPool::load_with(&path, &other_fresh).unwrap();
+    assert_ne!(first.extract(32), second.extract(32));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn load_refuses_to_resume_without_fresh_entropy() {
+    let path = seed_path("stale");
+    EntropyPool::new().save(&path).unwrap();
+    for fresh in [&[][..], &[7; 64][..]] {
+        assert!(matches!(
+            EntropyPool::load_with(&path, fresh),
+            Err(RandomizeError::InvalidParameter(_))
+        ));
+    }
+
+    std::fs::write(&path, b"not a seed").unwrap();
+    assert!(matches!(
+        EntropyPool::load_with(&path, &fresh()),
+        Err(RandomizeError::InvalidParameter(_))
+    ));
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn seed_file_is_private() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = seed_path("private");
+    let _ = std::fs::remove_file(&path);
+    EntropyPool::new().save(&path).unwrap();
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn debug_output_redacts_the_key() {
+    let mut pool = EntropyPool::new();
+    pool.stir(&fresh());
+    let debug = format!("{:?}", pool);
+    assert!(debug.contains("reseed_count: 1"), "{}", debug);
+    // A derived `Debug` would print the key as a byte array.
+    assert!(!debug.contains('['), "{}", debug);
+}
@@ -0,0 +1,281 @@
+use rand::{Rng, SeedableRng};
+use randomize7::{
+    average_spectrum, center_and_normalize, channel_cross_correlation, clipping_ratio, decorrelate,
+    detect_agc_drift, high_pass_filter, i16_to_f32, magnitude_spectrum, noise_shape_metrics,
+    normalize_audio, normalize_audio_to, remove_dc_offset, sliding_normalize, spectral_whiten,
+    u16_to_f32, window_rms, NormalizeMode, DECORRELATION_ORDER, DEFAULT_AGC_WINDOW,
+    DEFAULT_HIGH_PASS_CUTOFF_HZ, DEFAULT_MAX_KURTOSIS_DEVIATION, DEFAULT_SPECTRAL_WHITEN_WINDOW,
+    MAX_CHANNEL_CORRELATION,
+};
+
+const SAMPLE_RATE: u32 = 44100;
+
+fn sine(frequency: f32, len: usize) -> Vec<f32> {
+    (0..len)
+        .map(|i| (2.0 * std::f32::consts::PI * frequency * i as f32 / SAMPLE_RATE as f32).sin())
+        .collect()
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+#[test]
+fn high_pass_attenuates_mains_hum() {
+    let mut hum = sine(60.0, SAMPLE_RATE as usize);
+    let mut tone = sine(5000.0, SAMPLE_RATE as usize);
+    high_pass_filter(&mut hum, SAMPLE_RATE, DEFAULT_HIGH_PASS_CUTOFF_HZ);
+    high_pass_filter(&mut tone, SAMPLE_RATE, DEFAULT_HIGH_PASS_CUTOFF_HZ);
+
+    // Skip the filter's start-up transient.
+    let settled = SAMPLE_RATE as usize / 4;
+    let hum_rms = rms(&hum[settled..]);
+    let tone_rms = rms(&tone[settled..]);
+    assert!(
+        hum_rms < 0.4 * tone_rms,
+        "hum {} vs tone {}",
+        hum_rms,
+        tone_rms
+    );
+}
+
+#[test]
+fn normalizing_silence_produces_no_nan() {
+    let mut samples = vec![0.0f32; 1024];
+    normalize_audio(&mut samples, 1.0);
+    assert!(samples.iter().all(|sample| *sample == 0.0));
+}
+
+#[test]
+fn empty_buffers_are_left_alone() {
+    let mut samples: Vec<f32> = Vec::new();
+    remove_dc_offset(&mut samples);
+    normalize_audio(&mut samples, 1.0);
+    normalize_audio_to(&mut samples, 1.0, NormalizeMode::Rms);
+    center_and_normalize(&mut samples, 1.0);
+    assert!(samples.is_empty());
+}
+
+fn lag_one_correlation(samples: &[f32]) -> f32 {
+    let products: f32 = samples.windows(2).map(|pair| pair[0] * pair[1]).sum();
+    products / samples.iter().map(|s| s * s).sum::<f32>()
+}
+
+#[test]
+fn spectral_whitening_decorrelates_colored_noise() {
+    // White noise from an LCG, low-pass filtered into strongly correlated noise.
+    let mut state: u32 = 1;
+    let mut level = 0.0f32;
+    let mut samples: Vec<f32> = (0..SAMPLE_RATE as usize / 2)
+        .map(|_| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let white = (state >> 8) as f32 / (1 << 24) as f32 - 0.5;
+            level = 0.9 * level + white;
+            level
+        })
+        .collect();
+    assert!(lag_one_correlation(&samples) > 0.8);
+
+    spectral_whiten(&mut samples, DEFAULT_SPECTRAL_WHITEN_WINDOW);
+    let correlation = lag_one_correlation(&samples);
+    assert!(correlation.abs() < 0.1, "lag-1 correlation {}", correlation);
+}
+
+#[test]
+fn decorrelation_removes_lag_one_correlation() {
+    // The same low-passed LCG noise, which a short predictor models well.
+    let mut state: u32 = 1;
+    let mut level = 0.0f32;
+    let mut samples: Vec<f32> = (0..SAMPLE_RATE as usize / 2)
+        .map(|_| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let white = (state >> 8) as f32 / (1 << 24) as f32 - 0.5;
+            level = 0.9 * level + white;
+            level
+        })
+        .collect();
+    let length = samples.len();
+    assert!(lag_one_correlation(&samples) > 0.8);
+
+    decorrelate(&mut samples);
+    assert_eq!(samples.len(), length - DECORRELATION_ORDER);
+    let correlation = lag_one_correlation(&samples);
+    assert!(
+        correlation.abs() < 0.02,
+        "lag-1 correlation {}",
+        correlation
+    );
+}
+
+#[test]
+fn fused_normalization_matches_separate_passes() {
+    // An odd length exercises the remainder after any 8-wide chunks. The
+    // mean is summed in a different order, so results differ by rounding.
+    let samples: Vec<f32> = sine(440.0, 10_001).iter().map(|s| 0.3 * s + 0.05).collect();
+
+    let mut separate = samples.clone();
+    remove_dc_offset(&mut separate);
+    normalize_audio(&mut separate, 1.0);
+
+    let mut fused = samples;
+    center_and_normalize(&mut fused, 1.0);
+
+    for (a, b) in separate.iter().zip(&fused) {
+        assert!((a - b).abs() <= 1e-5, "{} vs {}", a, b);
+    }
+}
+
+#[test]
+fn clipped_recording_has_high_clipping_ratio() {
+    let tone = sine(440.0, 4410);
+    let clipped: Vec<f32> = tone.iter().map(|s| (4.0 * s).clamp(-1.0, 1.0)).collect();
+    let quiet: Vec<f32> = tone.iter().map(|s| 0.5 * s).collect();
+
+    assert!(clipping_ratio(&clipped) > 0.5);
+    assert_eq!(clipping_ratio(&quiet), 0.0);
+}
+
+#[test]
+fn integer_samples_convert_to_unit_range() {
+    assert_eq!(
+        i16_to_f32(&[i16::MIN, -16384, 0, 16384, i16::MAX]),
+        [-1.0, -0.5, 0.0, 0.5, 32767.0 / 32768.0]
+    );
+    assert_eq!(
+        u16_to_f32(&[0, 16384, 32768, 49152, u16::MAX]),
+        [-1.0, -0.5, 0.0, 0.5, 32767.0 / 32768.0]
+    );
+}
+
+#[test]
+fn normalization_modes_scale_a_sine() {
+    let rms = |samples: &[f32]| {
+        (samples.iter().map(|&s| s as f64 * s as f64).sum::<f64>() / samples.len() as f64).sqrt()
+    };
+    // 10 whole periods, so the RMS is exactly amplitude / sqrt(2).
+    let quiet: Vec<f32> = sine(441.0, 1000).iter().map(|s| s * 0.2).collect();
+
+    let mut peak = quiet.clone();
+    normalize_audio_to(&mut peak, 0.8, NormalizeMode::Peak);
+    let max = peak.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+    assert!((max - 0.8).abs() < 1e-6);
+    assert!((rms(&peak) - 0.8 / 2f64.sqrt()).abs() < 1e-3);
+
+    let mut by_rms = quiet.clone();
+    normalize_audio_to(&mut by_rms, 0.5, NormalizeMode::Rms);
+    assert!((rms(&by_rms) - 0.5).abs() < 1e-6);
+    let max = by_rms.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+    assert!((max as f64 - 0.5 * 2f64.sqrt()).abs() < 1e-3);
+
+    let mut default = quiet;
+    normalize_audio(&mut default, 0.8);
+    assert_eq!(default, peak);
+}
+
+#[test]
+fn noise_shape_separates_tones_from_gaussian_noise() {
+    let metrics = noise_shape_metrics(&[1.0, -1.0, 1.0, -1.0]);
+    assert_eq!(
+        (
+            metrics.mean,
+            metrics.variance,
+            metrics.skewness,
+            metrics.kurtosis
+        ),
+        (0.0, 1.0, 0.0, 1.0)
+    );
+
+    let tone = noise_shape_metrics(&sine(441.0, 44100));
+    assert!((tone.kurtosis - 1.5).abs() < 0.01);
+    assert!(tone.looks_non_thermal(DEFAULT_MAX_KURTOSIS_DEVIATION));
+
+    // Sums of 12 uniforms are close to Gaussian, kurtosis 2.9.
+    let mut state = 1u32;
+    let mut uniform = || {
+        state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+        state as f32 / u32::MAX as f32 - 0.5
+    };
+    let gaussian: Vec<f32> = (0..20000)
+        .map(|_| (0..12).map(|_| uniform()).sum())
+        .collect();
+    let noise = noise_shape_metrics(&gaussian);
+    assert!((noise.kurtosis - 2.9).abs() < 0.15, "{}", noise.kurtosis);
+    assert!(!noise.looks_non_thermal(DEFAULT_MAX_KURTOSIS_DEVIATION));
+}
+
+fn noise(seed: u64, len: usize) -> Vec<f32> {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+    (0..len).map(|_| rng.gen_range(-1.0..1.0)).collect()
+}
+
+#[test]
+fn agc_drift_flags_slow_level_changes_only() {
+    let steady: Vec<f32> = noise(105, 88200).iter().map(|x| 0.2 * x).collect();
+    let drift = detect_agc_drift(&steady, DEFAULT_AGC_WINDOW);
+    assert!(!drift.detected, "{:?}", drift);
+    assert!(drift.ratio() < 1.2);
+
+    // The gain ramps from 0.05 to 0.5 over two seconds.
+    let ramped: Vec<f32> = steady
+        .iter()
+        .enumerate()
+        .map(|(i, x)| x * (0.25 + 2.25 * i as f32 / 88200.0))
+        .collect();
+    assert!(detect_agc_drift(&ramped, DEFAULT_AGC_WINDOW).detected);
+
+    let mut compensated = ramped;
+    sliding_normalize(&mut compensated, DEFAULT_AGC_WINDOW);
+    assert!(!detect_agc_drift(&compensated, DEFAULT_AGC_WINDOW).detected);
+    let levels = window_rms(&compensated, DEFAULT_AGC_WINDOW);
+    assert!(
+        levels.iter().all(|level| (level - 1.0).abs() < 0.1),
+        "{:?}",
+        levels
+    );
+}
+
+#[test]
+fn short_or_silent_recordings_report_no_drift() {
+    assert!(!detect_agc_drift(&noise(1, 1000), DEFAULT_AGC_WINDOW).detected);
+    let mut silence = vec![0.0; 88200];
+    assert!(!detect_agc_drift(&silence, DEFAULT_AGC_WINDOW).detected);
+    sliding_normalize(&mut silence, DEFAULT_AGC_WINDOW);
+    assert!(silence.iter().all(|&sample| sample == 0.0));
+}
+
+#[test]
+fn channel_cross_correlation_flags_a_shared_adc() {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+    let left: Vec<f32> = (0..10_000).map(|_| rng.gen_range(-0.5..0.5)).collect();
+    let right: Vec<f32> = (0..10_000).map(|_| rng.gen_range(-0.5..0.5)).collect();
+    assert!(channel_cross_correlation(&left, &right).abs() < 0.05);
+
+    let duplicated: Vec<f32> = left.iter().map(|x| x * 0.8 + 0.01).collect();
+    assert!((channel_cross_correlation(&left, &duplicated) - 1.0).abs() < 1e-4);
+    let inverted: Vec<f32> = left.iter().map(|x| -x).collect();
+    assert!((channel_cross_correlation(&left, &inverted) + 1.0).abs() < 1e-4);
+
+    // Shared signal plus a little independent noise is still one ADC.
+    let shared: Vec<f32> = left.iter().zip(&right).map(|(l, r)| l + 0.1 * r).collect();
+    assert!(channel_cross_correlation(&left, &shared) > MAX_CHANNEL_CORRELATION);
+    assert_eq!(channel_cross_correlation(&left, &[0.0; 100]), 1.0);
+}
+
+#[test]
+fn magnitude_spectrum_finds_a_tone() {
+    // Bin 64 of a 1024-sample window.
+    let frequency = 64.0 * SAMPLE_RATE as f32 / 1024.0;
+    let tone: Vec<f32> = sine(frequency, 4096).iter().map(|x| 0.5 * x).collect();
+    let spectrum = magnitude_spectrum(&tone[..1024]);
+    assert_eq!(spectrum.len(), 513);
+    assert!((spectrum[64] - 0.5).abs() < 1e-3, "{}", spectrum[64]);
+    assert!(spectrum
+        .iter()
+        .enumerate()
+        .filter(|(bin, _)| !(63..=65).contains(bin))
+        .all(|(_, &magnitude)| magnitude < 1e-3));
+
+    let average = average_spectrum(&tone, 1024);
+    assert!((average[64] - 0.5).abs() < 1e-3);
+    assert!(average_spectrum(&tone[..1000], 1024).is_empty());
+}
@@ -0,0 +1,12 @@
+use randomize7::secure_compare;
+
+#[test]
+fn secure_compare_matches_equality() {
+    let key = [0x5a; 32];
+    let mut other = key;
+    assert!(secure_compare(&key, &other));
+    other[31] ^= 1;
+    assert!(!secure_compare(&key, &other));
+    assert!(!secure_compare(&key, &key[..31]));
+    assert!(secure_compare(&[], &[]));
+}
@@ -0,0 +1,405 @@
+use rand::{RngCore, SeedableRng};
+use randomize7::bitvec::prelude::*;
+use randomize7::{
+    berlekamp_massey, byte_rank_correlation, chi_square_byte_test, coverage_test, fips_140_2_suite,
+    fips_long_run, fips_monobit, gf2_rank, lag1_autocorrelation, monobit_p_value, monobit_result,
+    monobit_test, non_overlapping_template_test_bits, overall_verdict, overlapping_template_test,
+    poker_test, quality_score, random_excursions_test, run_all_tests, run_length_distribution,
+    run_tests, runs_result, self_test, serial_test_bits, BiasMeter, HealthPolicy, RandomizeError,
+    Recommendation, StreamingMonitor, TestKind, TestResult, OVERLAPPING_TEMPLATE_BLOCK_SIZE,
+    RUN_LENGTH_BUCKETS,
+};
+
+fn bits(s: &str) -> BitVec<u8, Msb0> {
+    s.chars().map(|c| c == '1').collect()
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-6,
+        "expected {}, got {}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn serial_test_matches_sp800_22_example() {
+    // SP800-22 section 2.11.8.
+    let (p1, p2) = serial_test_bits(&bits("0011011101"), 3);
+    assert_close(p1, 0.808792);
+    assert_close(p2, 0.670320);
+}
+
+#[test]
+fn gf2_rank_of_known_matrices() {
+    let identity: Vec<u32> = (0..32).map(|i| 1 << i).collect();
+    assert_eq!(gf2_rank(&identity), 32);
+    assert_eq!(gf2_rank(&[0; 32]), 0);
+
+    // The third row is the XOR of the first two.
+    assert_eq!(gf2_rank(&[0b110, 0b011, 0b101]), 2);
+    assert_eq!(gf2_rank(&[0b010, 0b110, 0b001]), 3);
+}
+
+#[test]
+fn non_overlapping_template_matches_sp800_22_example() {
+    // SP800-22 section 2.7.4.
+    let p_value = non_overlapping_template_test_bits(
+        &bits("10100100101110010110"),
+        &[false, false, true],
+        10,
+    );
+    assert_close(p_value, 0.344154);
+}
+
+#[test]
+fn overlapping_template_rejects_short_input() {
+    let data = [0u8; 1000];
+    assert!(overlapping_template_test(&data, 9, OVERLAPPING_TEMPLATE_BLOCK_SIZE).is_err());
+}
+
+#[test]
+fn berlekamp_massey_finds_known_complexities() {
+    // SP800-22 section 2.10.4.
+    assert_eq!(berlekamp_massey(&bits("1101011110001")), 4);
+    assert_eq!(berlekamp_massey(&bits("0000000")), 0);
+    assert_eq!(berlekamp_massey(&bits("0001")), 4);
+    assert_eq!(berlekamp_massey(&bits("1111111")), 1);
+    // x_n = x_{n-2} ^ x_{n-3} has complexity 3.
+    assert_eq!(berlekamp_massey(&bits("1001011100101110")), 3);
+}
+
+#[test]
+fn random_excursions_reports_too_few_cycles() {
+    // A walk that never returns to zero has a single cycle.
+    let result = random_excursions_test(&[0xff; 1000]);
+    assert!(matches!(
+        result,
+        Err(RandomizeError::TooFewCycles { available: 1, .. })
+    ));
+}
+
+#[test]
+fn significance_level_decides_pass_fail() {
+    // 12 ones in 16 bits: S_n = 8, p = erfc(sqrt(2)) ~ 0.0455.
+    let result = monobit_result(&[0xf0, 0xff]);
+    assert_close(result.p_value, 0.045500);
+    assert!(result.passed);
+    assert!(!result.clone().at_significance_level(0.05).passed);
+    assert!(result.at_significance_level(0.001).passed);
+}
+
+#[test]
+fn monobit_p_value_matches_reference_and_scales_with_length() {
+    // The first 96 bits of the SP800-22 section 2.1.8 example (pi): S_n = -14.
+    let pi = [
+        0xc9, 0x0f, 0xda, 0xa2, 0x21, 0x68, 0xc2, 0x34, 0xc4, 0xc6, 0x62, 0x8b,
+    ];
+    assert_close(monobit_p_value(&pi), 0.153042);
+
+    // 52% ones is fine in 100 bytes but a clear bias in 10000.
+    let biased = |len: usize| -> Vec<u8> {
+        (0..len)
+            .map(|i| if i % 25 == 0 { 0xff } else { 0xaa })
+            .collect()
+    };
+    assert!(monobit_test(&biased(100)));
+    assert!(!monobit_test(&biased(10_000)));
+}
+
+#[test]
+fn verdict_follows_the_passing_proportion() {
+    let results = |p_values: &[f64]| -> Vec<TestResult> {
+        p_values
+            .iter()
+            .map(|&p_value| TestResult {
+                name: "test".to_string(),
+                statistic: f64::NAN,
+                p_value,
+                passed: p_value >= 0.01,
+            })
+            .collect()
+    };
+    let mut p_values = vec![0.5; 14];
+
+    // 14 of 15 is above the lower bound 0.99 - 3 sqrt(0.0099 / 15) = 0.913.
+    p_values.push(0.005);
+    let verdict = overall_verdict(&results(&p_values));
+    assert_eq!((verdict.passed, verdict.total), (14, 15));
+    assert_eq!(verdict.min_p_value, 0.005);
+    assert_eq!(verdict.recommendation, Recommendation::Acceptable);
+
+    p_values[14] = 0.00001;
+    assert_eq!(
+        overall_verdict(&results(&p_values)).recommendation,
+        Recommendation::Suspect
+    );
+    assert_eq!(
+        overall_verdict(&results(&[0.5, 0.005])).recommendation,
+        Recommendation::Reject
+    );
+    assert_eq!(overall_verdict(&[]).recommendation, Recommendation::Suspect);
+}
+
+#[test]
+fn streaming_monitor_matches_batch_results() {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(9);
+    let mut data = vec![0u8; 5000];
+    rng.fill_bytes(&mut data);
+
+    let mut monitor = StreamingMonitor::new();
+    for chunk in data.chunks(333) {
+        monitor.update(chunk);
+    }
+    let snapshot = monitor.snapshot();
+
+    assert_eq!(monitor.len(), 5000);
+    assert_eq!(snapshot[0], monobit_result(&data));
+    assert_eq!(snapshot[1], runs_result(&data).unwrap());
+    assert_eq!(snapshot[2].p_value, chi_square_byte_test(&data));
+}
+
+#[test]
+fn run_length_distribution_counts_each_run() {
+    // 1111 000000 111111
+    let distribution = run_length_distribution(&[0b1111_0000, 0b0011_1111]);
+    assert_eq!(distribution.len(), RUN_LENGTH_BUCKETS);
+    assert_eq!(distribution[3], 1);
+    assert_eq!(distribution[5], 2);
+    assert_eq!(distribution.iter().sum::<u64>(), 3);
+
+    // One 32-bit run lands in the overflow bucket.
+    let distribution = run_length_distribution(&[0xff; 4]);
+    assert_eq!(distribution[RUN_LENGTH_BUCKETS - 1], 1);
+    assert!(run_length_distribution(&[]).iter().all(|&count| count == 0));
+}
+
+#[test]
+fn self_test_agrees_with_every_reference_value() {
+    for result in self_test() {
+        assert!(
+            result.passed,
+            "{} ({}): expected {}, got {}",
+            result.name, result.reference, result.expected, result.actual
+        );
+    }
+}
+
+#[test]
+fn coverage_expectation_scales_with_length() {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+    for len in [32, 256, 4096] {
+        let mut data = vec![0u8; len];
+        rng.fill_bytes(&mut data);
+        assert!(coverage_test(&data).abs() < 4.0, "{} bytes", len);
+    }
+
+    // Half the values can never appear.
+    let mut data = vec![0u8; 4096];
+    rng.fill_bytes(&mut data);
+    data.iter_mut().for_each(|byte| *byte &= 0x7f);
+    assert!(coverage_test(&data) < -100.0);
+    assert_eq!(coverage_test(&[0x42]), 0.0);
+}
+
+#[test]
+fn quality_score_separates_good_from_stuck_output() {
+    let mut good = vec![0u8; 100_000];
+    rand::rngs::StdRng::seed_from_u64(4).fill_bytes(&mut good);
+    assert!(quality_score(&good) > 95);
+    assert!((60..=70).contains(&quality_score(&good[..32])));
+
+    assert_eq!(quality_score(&[0; 1000]), 0);
+    assert_eq!(quality_score(&[]), 0);
+}
+
+#[test]
+fn bias_meter_tracks_the_running_ones_fraction() {
+    let mut meter = BiasMeter::new();
+    assert!(meter.ones_fraction().is_nan());
+    meter.update(&[0xff; 3]);
+    meter.update(&[0x01]);
+    assert_eq!(meter.bits(), 32);
+    assert_eq!(meter.ones_fraction(), 25.0 / 32.0);
+}
+
+// Bits that repeat their predecessor with probability 0.7 have a lag-1
+// autocorrelation of 0.4.
+fn correlated_bytes(len: usize, seed: u64) -> Vec<u8> {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+    let mut bit = false;
+    let bits: BitVec<u8, Msb0> = (0..len * 8)
+        .map(|_| {
+            if rng.next_u32() % 10 >= 7 {
+                bit = !bit;
+            }
+            bit
+        })
+        .collect();
+    bits.into_vec()
+}
+
+#[test]
+fn lag1_autocorrelation_measures_neighbouring_bits() {
+    let measured = lag1_autocorrelation(&correlated_bytes(10_000, 1));
+    assert!((measured - 0.4).abs() < 0.02, "{}", measured);
+
+    let mut independent = vec![0u8; 10_000];
+    rand::rngs::StdRng::seed_from_u64(1).fill_bytes(&mut independent);
+    assert!(lag1_autocorrelation(&independent).abs() < 0.02);
+
+    assert_close(lag1_autocorrelation(&[0b0101_0101; 16]), -1.0);
+    assert_close(lag1_autocorrelation(&[0xff; 16]), 1.0);
+}
+
+#[test]
+fn byte_rank_correlation_detects_drift() {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+    let mut independent = vec![0u8; 10_000];
+    rng.fill_bytes(&mut independent);
+    assert!(byte_rank_correlation(&independent).unwrap().abs() < 0.03);
+
+    // A slow ramp with a little noise on top.
+    let drifting: Vec<u8> = (0..10_000u32)
+        .map(|i| ((i / 40) as u8).wrapping_add(rng.next_u32() as u8 % 8))
+        .collect();
+    assert!(byte_rank_correlation(&drifting).unwrap() > 0.9);
+
+    let ramp: Vec<u8> = (0..=255).collect();
+    assert_close(byte_rank_correlation(&ramp).unwrap(), 1.0);
+    assert_close(byte_rank_correlation(&[7; 64]).unwrap(), 1.0);
+    assert!(matches!(
+        byte_rank_correlation(&[0; 29]),
+        Err(RandomizeError::InsufficientData {
+            required_bits: 240,
+            available_bits: 232
+        })
+    ));
+}
+
+#[test]
+fn health_policy_rejects_correlated_bits() {
+    let policy = HealthPolicy {
+        max_autocorrelation: Some(0.05),
+        ..HealthPolicy::default()
+    };
+    let result = policy.check(&correlated_bytes(10_000, 2));
+    assert!(
+        matches!(result, Err(RandomizeError::AutocorrelationTooHigh { measured }) if measured > 0.3),
+        "{:?}",
+        result
+    );
+
+    let mut independent = vec![0u8; 10_000];
+    rand::rngs::StdRng::seed_from_u64(2).fill_bytes(&mut independent);
+    assert!(policy.check(&independent).is_ok());
+}
+
+#[test]
+fn run_tests_skips_tests_the_input_is_too_short_for() {
+    let mut key = [0u8; 32];
+    rand::rngs::StdRng::seed_from_u64(4).fill_bytes(&mut key);
+    let battery = run_tests(&key, &TestKind::ALL, 0.01);
+
+    assert_eq!(battery.results[0].name, "monobit");
+    let matrix_rank = battery
+        .skipped
+        .iter()
+        .find(|test| test.name == "matrix-rank")
+        .expect("32 bytes are too few for the rank test");
+    assert!(
+        matrix_rank.reason.contains("requires at least"),
+        "{}",
+        matrix_rank.reason
+    );
+    assert!(battery.skipped.iter().all(|test| test.name != "monobit"));
+
+    let subset = run_tests(&key, &[TestKind::Runs, TestKind::Monobit], 0.01);
+    let names: Vec<&str> = subset
+        .results
+        .iter()
+        .map(|result| result.name.as_str())
+        .collect();
+    assert_eq!(names, ["runs", "monobit"]);
+    assert!(subset.skipped.is_empty());
+}
+
+#[test]
+fn every_battery_result_reports_its_statistic() {
+    let mut data = vec![0u8; 50_000];
+    rand::rngs::StdRng::seed_from_u64(12).fill_bytes(&mut data);
+    let battery = run_tests(&data, &TestKind::ALL, 0.01);
+
+    assert!(battery.results.len() >= 17, "{:?}", battery.skipped);
+    for result in &battery.results {
+        assert!(result.statistic.is_finite(), "{:?}", result);
+    }
+    assert_eq!(run_all_tests(&data), battery.results);
+}
+
+#[test]
+fn poker_test_follows_fips_140_2() {
+    let mut random = vec![0u8; 2500];
+    rand::rngs::StdRng::seed_from_u64(6).fill_bytes(&mut random);
+    let (statistic, passed) = poker_test(&random).unwrap();
+    assert!(passed, "{}", statistic);
+
+    let (statistic, passed) = poker_test(&[0; 2500]).unwrap();
+    assert_close(statistic, 75_000.0);
+    assert!(!passed);
+
+    // Every nibble 312 or 313 times is too even to be random.
+    let counting: Vec<u8> = [0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef]
+        .repeat(400)
+        .into_iter()
+        .take(2500)
+        .collect();
+    let (statistic, passed) = poker_test(&counting).unwrap();
+    assert_close(statistic, 0.0128);
+    assert!(!passed);
+
+    assert!(matches!(
+        poker_test(&random[..2499]),
+        Err(RandomizeError::InsufficientData {
+            required_bits: 20_000,
+            available_bits: 19_992
+        })
+    ));
+}
+
+// 20 000 bits with `ones` ones in front.
+fn with_ones(ones: usize) -> Vec<u8> {
+    let mut bits = bitvec![u8, Msb0; 0; 20_000];
+    bits[..ones].fill(true);
+    bits.into_vec()
+}
+
+// Alternating bits with one run of `length` ones starting at bit 1001.
+fn with_run(length: usize) -> Vec<u8> {
+    let mut bits = vec![0x55u8; 2500];
+    let view = bits.view_bits_mut::<Msb0>();
+    view[1001..1001 + length].fill(true);
+    view.set(1001 + length, false);
+    bits
+}
+
+#[test]
+fn fips_140_2_suite_uses_the_fixed_thresholds() {
+    let mut random = vec![0u8; 2500];
+    rand::rngs::StdRng::seed_from_u64(7).fill_bytes(&mut random);
+    let result = fips_140_2_suite(&random).unwrap();
+    assert!(result.passed(), "{:?}", result);
+
+    assert!(!fips_monobit(&with_ones(9725)).unwrap());
+    assert!(fips_monobit(&with_ones(9726)).unwrap());
+    assert!(fips_monobit(&with_ones(10_274)).unwrap());
+    assert!(!fips_monobit(&with_ones(10_275)).unwrap());
+
+    assert!(fips_long_run(&with_run(25)).unwrap());
+    assert!(!fips_long_run(&with_run(26)).unwrap());
+
+    let result = fips_140_2_suite(&with_run(26)).unwrap();
+    assert!(result.monobit && !result.long_run && !result.passed());
+    assert!(fips_140_2_suite(&random[..2499]).is_err());
+}
@@ -0,0 +1,48 @@
+use randomize7::stats::{erfc, igam, igamc, ln_gamma, normal_cdf};
+
+// Reference values from mpmath at 30 digits.
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() <= 1e-12 * expected.abs().max(1e-300),
+        "expected {}, got {}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn erfc_matches_reference_values() {
+    assert_close(erfc(0.0), 1.0);
+    assert_close(erfc(0.5), 0.4795001221869535);
+    assert_close(erfc(1.0), 0.15729920705028513);
+    assert_close(erfc(2.0), 0.004677734981047266);
+    assert_close(erfc(4.0), 1.541725790028002e-08);
+    assert_close(erfc(-1.0), 1.842700792949715);
+}
+
+#[test]
+fn igamc_matches_reference_values() {
+    assert_close(igamc(0.5, 0.5), 0.3173105078629141);
+    assert_close(igamc(1.0, 1.0), 0.36787944117144233);
+    assert_close(igamc(2.5, 3.0), 0.3062189184132784);
+    assert_close(igamc(10.0, 5.0), 0.9681719426937951);
+    assert_close(igamc(50.0, 60.0), 0.08440668109369183);
+    assert_close(igamc(3.0, 0.1), 0.9998453469297354);
+    assert_close(igam(2.5, 3.0) + igamc(2.5, 3.0), 1.0);
+}
+
+#[test]
+fn normal_cdf_matches_reference_values() {
+    assert_close(normal_cdf(0.0), 0.5);
+    assert_close(normal_cdf(1.0), 0.8413447460685429);
+    assert_close(normal_cdf(-1.96), 0.024997895148220435);
+    assert_close(normal_cdf(3.0), 0.9986501019683699);
+}
+
+#[test]
+fn ln_gamma_matches_reference_values() {
+    assert_close(ln_gamma(0.5), 0.5723649429247001);
+    assert_close(ln_gamma(5.5), 3.9578139676187165);
+    assert_close(ln_gamma(100.0), 359.1342053695754);
+    assert!(ln_gamma(1.0).abs() < 1e-14);
+}
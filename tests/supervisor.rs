@@ -0,0 +1,105 @@
+use rand::{RngCore, SeedableRng};
+use randomize7::{RandomizeError, RetestPolicy, Supervisor, SupervisorEvent};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+fn policy() -> RetestPolicy {
+    RetestPolicy {
+        interval_bytes: 4096,
+        sample_bytes: 4096,
+        initial_backoff: Duration::ZERO,
+        max_backoff: Duration::ZERO,
+        max_attempts: 3,
+        ..RetestPolicy::default()
+    }
+}
+
+// Random batches of 1 KiB, except that batches `stuck` are all zeros.
+fn source(stuck: std::ops::Range<usize>) -> impl FnMut() -> Result<Vec<u8>, RandomizeError> {
+    let mut rng = rand::rngs::StdRng::seed_from_u64(110);
+    let mut calls = 0;
+    move || {
+        let mut batch = vec![0; 1024];
+        if !stuck.contains(&calls) {
+            rng.fill_bytes(&mut batch);
+        }
+        calls += 1;
+        Ok(batch)
+    }
+}
+
+type Events = Arc<Mutex<Vec<SupervisorEvent>>>;
+
+fn recorder() -> (Events, impl FnMut(&SupervisorEvent) + Send + 'static) {
+    let events = Events::default();
+    let sink = events.clone();
+    (events, move |event: &SupervisorEvent| {
+        sink.lock().unwrap().push(event.clone())
+    })
+}
+
+#[test]
+fn healthy_output_is_retested_every_interval() {
+    let (events, on_event) = recorder();
+    let mut supervisor = Supervisor::new(source(0..0))
+        .policy(policy())
+        .on_event(on_event);
+    for _ in 0..8 {
+        assert_eq!(supervisor.next_batch().unwrap().len(), 1024);
+    }
+    assert_eq!(supervisor.bytes_emitted(), 8192);
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 2);
+    assert!(events
+        .iter()
+        .all(|event| matches!(event, SupervisorEvent::Retest { passed: true, .. })));
+    // The whole battery, not just the tests that need the least data.
+    assert!(events.iter().all(|event| matches!(
+        event,
+        SupervisorEvent::Retest { verdict, .. } if verdict.total > 5
+    )));
+}
+
+#[test]
+fn failed_retest_withholds_output_until_fresh_output_passes() {
+    // Batches 2-7 are stuck: the first re-test and the first recovery
+    // attempt see zeros, the second attempt sees random bytes again.
+    let (events, on_event) = recorder();
+    let mut supervisor = Supervisor::new(source(2..8))
+        .policy(policy())
+        .on_event(on_event);
+    let emitted: Vec<Vec<u8>> = (0..4).map(|_| supervisor.next_batch().unwrap()).collect();
+    assert!(emitted[3].iter().any(|&byte| byte != 0));
+
+    let events = events.lock().unwrap();
+    let kinds: Vec<&str> = events
+        .iter()
+        .map(|event| match event {
+            SupervisorEvent::Retest { passed: true, .. } => "pass",
+            SupervisorEvent::Retest { passed: false, .. } => "fail",
+            SupervisorEvent::Recovering { .. } => "recovering",
+            SupervisorEvent::Recovered { .. } => "recovered",
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            "fail",
+            "recovering",
+            "fail",
+            "recovering",
+            "pass",
+            "recovered"
+        ]
+    );
+}
+
+#[test]
+fn persistent_failure_gives_up_after_max_attempts() {
+    let mut supervisor = Supervisor::new(source(0..usize::MAX)).policy(policy());
+    let result: Result<Vec<_>, _> = (0..4).map(|_| supervisor.next_batch()).collect();
+    assert!(matches!(
+        result,
+        Err(RandomizeError::HealthCheckFailed { .. })
+    ));
+}
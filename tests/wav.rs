@@ -0,0 +1,77 @@
+use randomize7::{downmix, read_wav, read_wav_samples, write_recording_wav, write_wav};
+
+#[test]
+fn written_wav_reads_back_unchanged() {
+    let samples: Vec<f32> = (0..1000).map(|i| (i as f32 * 0.01).sin()).collect();
+    let path = std::env::temp_dir().join(format!("randomize7-{}.wav", std::process::id()));
+
+    write_wav(&path, &samples, 44100, 1).unwrap();
+    let read = read_wav_samples(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(read, samples);
+}
+
+fn fixture(name: &str) -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name)
+}
+
+#[test]
+fn pcm16_is_scaled_to_unit_range() {
+    let audio = read_wav(&fixture("pcm16_stereo.wav")).unwrap();
+    assert_eq!((audio.sample_rate, audio.channels), (44100, 2));
+    assert_eq!(
+        audio.samples,
+        [
+            -1.0,
+            32767.0 / 32768.0,
+            0.0,
+            0.5,
+            -1.0 / 32768.0,
+            1.0 / 32768.0
+        ]
+    );
+}
+
+#[test]
+fn pcm24_extensible_is_sign_extended() {
+    let audio = read_wav(&fixture("pcm24_quad.wav")).unwrap();
+    assert_eq!((audio.sample_rate, audio.channels), (48000, 4));
+    let scale = 8388608.0;
+    assert_eq!(
+        audio.samples,
+        [
+            -1.0,
+            8388607.0 / scale,
+            -1.0 / scale,
+            0x123456 as f32 / scale,
+            0.0,
+            0.5,
+            -0.5,
+            1.0 / scale,
+        ]
+    );
+}
+
+#[test]
+fn float32_multichannel_downmixes_to_mono() {
+    let audio = read_wav(&fixture("float32_3ch.wav")).unwrap();
+    assert_eq!((audio.sample_rate, audio.channels), (96000, 3));
+    assert_eq!(audio.samples, [0.25, -0.5, 1.0, -1.0, 0.0, 0.75]);
+    assert_eq!(downmix(&audio.samples, audio.channels), [0.25, -0.25 / 3.0]);
+}
+
+#[test]
+fn recording_wav_is_mono_at_the_given_rate() {
+    let samples: Vec<f32> = (0..1000).map(|i| (i as f32 * 0.37).cos() * 0.9).collect();
+    let path = std::env::temp_dir().join(format!("randomize7-rec-{}.wav", std::process::id()));
+
+    write_recording_wav(&samples, 48000, &path).unwrap();
+    let audio = read_wav(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!((audio.sample_rate, audio.channels), (48000, 1));
+    assert_eq!(audio.samples, samples);
+}